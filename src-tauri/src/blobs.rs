@@ -0,0 +1,619 @@
+//! File contents stored once per SHA-256, in `Junkdrawer/files/blobs/ab/cd/abcd….ext`. Items
+//! point at a blob through `content_hash`, and the `blobs` table counts how many do.
+
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+use crate::entities::{blob, item, Blob, BlobActiveModel, Item, ItemModel};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Shared by everything storing a blob until its item is saved, and taken alone while unused
+/// blobs are collected.
+static IN_USE: RwLock<()> = RwLock::const_new(());
+
+/// A blob as it ended up on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredBlob {
+    pub hash: String,
+    pub path: PathBuf,
+    pub size: u64,
+    /// False when identical contents were already stored.
+    pub created: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    dir: PathBuf,
+}
+
+impl BlobStore {
+    pub fn at(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Copy `source` in. The source is left alone whatever happens to the item.
+    pub fn copy_in(&self, source: &Path) -> io::Result<Staged> {
+        Ok(Staged {
+            blob: self.copy_verified(source)?,
+            source: source.to_path_buf(),
+            kind: StagedKind::Copied,
+        })
+    }
+
+    /// Move `source` in: a rename when it is on the same filesystem, otherwise a verified
+    /// copy whose source is only removed once the item is saved. Identical contents already
+    /// stored aren't written again, and the source is removed on commit as well.
+    pub fn move_in(&self, source: &Path) -> io::Result<Staged> {
+        self.move_in_with(source, |from, to| fs::rename(from, to))
+    }
+
+    fn move_in_with(
+        &self,
+        source: &Path,
+        rename: impl Fn(&Path, &Path) -> io::Result<()>,
+    ) -> io::Result<Staged> {
+        let (hash, size) = hash_file(source)?;
+        if let Some(path) = self.find(&hash) {
+            return Ok(Staged {
+                blob: StoredBlob {
+                    hash,
+                    path,
+                    size,
+                    created: false,
+                },
+                source: source.to_path_buf(),
+                kind: StagedKind::CopiedForMove,
+            });
+        }
+
+        let path = self.path_for(&hash, extension(source).as_deref());
+        fs::create_dir_all(path.parent().unwrap_or(&self.dir))?;
+        match rename(source, &path) {
+            Ok(()) => Ok(Staged {
+                blob: StoredBlob {
+                    hash,
+                    path,
+                    size,
+                    created: true,
+                },
+                source: source.to_path_buf(),
+                kind: StagedKind::Renamed,
+            }),
+            // Another drive or mount point, where only a copy will do
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => Ok(Staged {
+                blob: self.copy_verified(source)?,
+                source: source.to_path_buf(),
+                kind: StagedKind::CopiedForMove,
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Copy `source` to a temporary file, hashing it on the way, then flush it to disk and
+    /// read it back to check it matches before giving it its name.
+    fn copy_verified(&self, source: &Path) -> io::Result<StoredBlob> {
+        let partial = self.partial_path();
+        let copied = (|| {
+            let mut reader = fs::File::open(source)?;
+            let mut writer = fs::File::create(&partial)?;
+            let (hash, size) = hash_reader(&mut reader, Some(&mut writer))?;
+            writer.sync_all()?;
+            verify(&partial, &hash)?;
+            Ok((hash, size))
+        })();
+        let (hash, size) = match copied {
+            Ok(copied) => copied,
+            Err(e) => {
+                let _ = fs::remove_file(&partial);
+                return Err(e);
+            }
+        };
+        self.settle(partial, hash, size, extension(source))
+    }
+
+    /// Store `bytes`, e.g. a download, under the extension of `file_name`.
+    pub fn put_bytes(&self, bytes: &[u8], file_name: &str) -> io::Result<StoredBlob> {
+        let partial = self.partial_path();
+        let written = fs::File::create(&partial).and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        });
+        if let Err(e) = written {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+        let hash = hex(&Sha256::digest(bytes));
+        self.settle(
+            partial,
+            hash,
+            bytes.len() as u64,
+            extension(Path::new(file_name)),
+        )
+    }
+
+    /// The stored file for `hash`, whatever extension it was first stored with.
+    pub fn find(&self, hash: &str) -> Option<PathBuf> {
+        let dir = self.shard_dir(hash)?;
+        fs::read_dir(dir)
+            .ok()?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .find(|path| {
+                path.file_stem().and_then(|stem| stem.to_str()) == Some(hash)
+                    && path.extension().is_none_or(|ext| ext != "partial")
+            })
+    }
+
+    fn path_for(&self, hash: &str, extension: Option<&str>) -> PathBuf {
+        let name = match extension {
+            Some(extension) => format!("{}.{}", hash, extension),
+            None => hash.to_string(),
+        };
+        self.shard_dir(hash)
+            .unwrap_or_else(|| self.dir.clone())
+            .join(name)
+    }
+
+    /// Two levels of two hex digits each keep any one directory down to a few entries.
+    fn shard_dir(&self, hash: &str) -> Option<PathBuf> {
+        Some(self.dir.join(hash.get(0..2)?).join(hash.get(2..4)?))
+    }
+
+    fn partial_path(&self) -> PathBuf {
+        self.dir
+            .join(format!("incoming-{:016x}.partial", rand::random::<u64>()))
+    }
+
+    /// Give a fully written `partial` file its name, unless the blob already exists.
+    fn settle(
+        &self,
+        partial: PathBuf,
+        hash: String,
+        size: u64,
+        extension: Option<String>,
+    ) -> io::Result<StoredBlob> {
+        if let Some(path) = self.find(&hash) {
+            fs::remove_file(&partial)?;
+            return Ok(StoredBlob {
+                hash,
+                path,
+                size,
+                created: false,
+            });
+        }
+
+        let path = self.path_for(&hash, extension.as_deref());
+        let renamed = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::rename(&partial, &path));
+        if let Err(e) = renamed {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+        Ok(StoredBlob {
+            hash,
+            path,
+            size,
+            created: true,
+        })
+    }
+}
+
+/// A file brought into the store for an item that isn't saved yet. `commit` once it is, which
+/// removes the source of a copied move; `rollback` if it can't be, which puts back a copy of a
+/// renamed file. The blob itself always stays, since another item may have started using it
+/// meanwhile; `release` it so `collect_garbage` removes it if nothing did.
+#[must_use]
+#[derive(Debug)]
+pub struct Staged {
+    pub blob: StoredBlob,
+    source: PathBuf,
+    kind: StagedKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StagedKind {
+    Copied,
+    Renamed,
+    /// Copied, or found already stored, with the source to be removed on commit.
+    CopiedForMove,
+}
+
+impl Staged {
+    pub fn commit(self) -> io::Result<()> {
+        match self.kind {
+            StagedKind::CopiedForMove => fs::remove_file(&self.source),
+            StagedKind::Copied | StagedKind::Renamed => Ok(()),
+        }
+    }
+
+    pub fn rollback(self) -> io::Result<()> {
+        match self.kind {
+            StagedKind::Renamed => fs::copy(&self.blob.path, &self.source).map(|_| ()),
+            StagedKind::Copied | StagedKind::CopiedForMove => Ok(()),
+        }
+    }
+}
+
+/// Check the file at `path` hashes to `expected`.
+fn verify(path: &Path, expected: &str) -> io::Result<()> {
+    let (hash, _) = hash_file(path)?;
+    if hash == expected {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} does not match what was copied", path.display()),
+        ))
+    }
+}
+
+/// The hex SHA-256 and size of the file at `path`.
+pub fn hash_file(path: &Path) -> io::Result<(String, u64)> {
+    hash_reader(&mut fs::File::open(path)?, None)
+}
+
+fn hash_reader(
+    reader: &mut impl Read,
+    mut copy: Option<&mut fs::File>,
+) -> io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut size = 0;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..read]);
+        if let Some(copy) = copy.as_mut() {
+            copy.write_all(&buffer[..read])?;
+        }
+        size += read as u64;
+    }
+    Ok((hex(&hasher.finalize()), size))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The lowercased extension of `path`, if it has a sensible one.
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(str::to_ascii_lowercase)
+}
+
+/// Count `blob` as stored for nothing, unless an item already uses it, so `collect_garbage`
+/// can remove it. Only items add to the count, so a blob no item ever used has no row until
+/// this gives it one.
+pub async fn release(db: &DatabaseConnection, blob: &StoredBlob) -> Result<(), DbErr> {
+    Blob::insert(BlobActiveModel {
+        hash: Set(blob.hash.clone()),
+        size: Set(Some(blob.size as i64)),
+        ref_count: Set(0),
+    })
+    .on_conflict(
+        OnConflict::column(blob::Column::Hash)
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+    Ok(())
+}
+
+/// Keep `collect_garbage` from running until the guard is dropped. Take it before storing a
+/// blob and hold it until the item pointing at it is saved, or contents found already stored
+/// with nothing using them could be deleted in between.
+pub async fn hold() -> RwLockReadGuard<'static, ()> {
+    IN_USE.read().await
+}
+
+/// The oldest item storing the contents hashed as `hash`.
+pub async fn find_duplicate(
+    db: &DatabaseConnection,
+    hash: &str,
+) -> Result<Option<ItemModel>, DbErr> {
+    Item::find()
+        .filter(item::Column::ContentHash.eq(hash))
+        .order_by_asc(item::Column::Id)
+        .one(db)
+        .await
+}
+
+/// Delete blobs no item refers to any more, returning how many files were removed. Waits for
+/// anything holding `hold` to finish, so a blob about to be used again isn't taken away.
+pub async fn collect_garbage(db: &DatabaseConnection, store: &BlobStore) -> Result<usize, DbErr> {
+    let _collecting = IN_USE.write().await;
+    let unused = Blob::find()
+        .filter(blob::Column::RefCount.lte(0))
+        .all(db)
+        .await?;
+
+    let mut removed = 0;
+    for blob in unused {
+        let deleted = Blob::delete_many()
+            .filter(blob::Column::Hash.eq(&blob.hash))
+            .filter(blob::Column::RefCount.lte(0))
+            .exec(db)
+            .await?;
+        if deleted.rows_affected == 0 {
+            // Picked up again since we looked
+            continue;
+        }
+        if let Some(path) = store.find(&blob.hash) {
+            match fs::remove_file(&path) {
+                Ok(()) => removed += 1,
+                Err(e) => eprintln!("Failed to remove blob {}: {}", path.display(), e),
+            }
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::ItemActiveModel;
+    use crate::test_support::{temp_dir, test_database};
+    use sea_orm::{ActiveModelTrait, Set};
+
+    #[test]
+    fn identical_contents_are_stored_once() {
+        let dir = temp_dir("blobs");
+        let store = BlobStore::at(dir.join("blobs")).expect("store");
+        let report = dir.join("Report.PDF");
+        fs::write(&report, b"%PDF-1.7 same").expect("write");
+        let copy = dir.join("copy of report.pdf");
+        fs::write(&copy, b"%PDF-1.7 same").expect("write");
+
+        let first = store.copy_in(&report).expect("copy").blob;
+        assert!(first.created);
+        assert_eq!(first.size, 13);
+        assert_eq!(
+            first.path,
+            dir.join("blobs")
+                .join(&first.hash[0..2])
+                .join(&first.hash[2..4])
+                .join(format!("{}.pdf", first.hash))
+        );
+        assert_eq!(fs::read(&first.path).expect("read"), b"%PDF-1.7 same");
+        assert!(report.exists());
+
+        let second = store.move_in(&copy).expect("move");
+        assert!(!second.blob.created);
+        assert_eq!(second.blob.path, first.path);
+        // Already stored, so the source only goes once the item is saved
+        assert!(copy.exists());
+        second.commit().expect("commit");
+        assert!(!copy.exists());
+
+        let download = store.put_bytes(b"%PDF-1.7 same", "download").expect("put");
+        assert_eq!(download.path, first.path);
+        let other = store.put_bytes(b"other", "notes.txt").expect("put");
+        assert!(other.created);
+        assert!(other.path.to_string_lossy().ends_with(".txt"));
+
+        // Nothing half-written is left behind
+        let leftovers = fs::read_dir(dir.join("blobs"))
+            .expect("read dir")
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "partial"))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn moves_fall_back_to_a_copy_and_roll_back() {
+        let dir = temp_dir("blobs-move");
+        let store = BlobStore::at(dir.join("blobs")).expect("store");
+        let across_devices = |_: &Path, _: &Path| Err(io::ErrorKind::CrossesDevices.into());
+
+        // Same filesystem: renamed away, and copied back on rollback
+        let renamed = dir.join("renamed.txt");
+        fs::write(&renamed, b"renamed").expect("write");
+        let staged = store.move_in(&renamed).expect("move");
+        assert!(!renamed.exists());
+        assert_eq!(fs::read(&staged.blob.path).expect("read"), b"renamed");
+        let path = staged.blob.path.clone();
+        staged.rollback().expect("rollback");
+        assert_eq!(fs::read(&renamed).expect("read"), b"renamed");
+        // Left for collection, in case another item has started using it
+        assert!(path.exists());
+
+        // Across devices: copied and verified, with the source kept until commit
+        let source = dir.join("usb.txt");
+        fs::write(&source, b"from a stick").expect("write");
+        let staged = store.move_in_with(&source, across_devices).expect("move");
+        assert!(staged.blob.created);
+        assert_eq!(fs::read(&staged.blob.path).expect("read"), b"from a stick");
+        assert!(source.exists());
+        let path = staged.blob.path.clone();
+        staged.rollback().expect("rollback");
+        assert!(source.exists());
+        assert!(path.exists());
+
+        let staged = store.move_in_with(&source, across_devices).expect("move");
+        staged.commit().expect("commit");
+        assert!(!source.exists());
+        assert_eq!(fs::read(&path).expect("read"), b"from a stick");
+
+        // Other rename failures are errors, not copies
+        let locked = dir.join("locked.txt");
+        fs::write(&locked, b"locked").expect("write");
+        let denied = store.move_in_with(&locked, |_: &Path, _: &Path| {
+            Err(io::ErrorKind::PermissionDenied.into())
+        });
+        assert_eq!(
+            denied.expect_err("denied").kind(),
+            io::ErrorKind::PermissionDenied
+        );
+        assert!(locked.exists());
+    }
+
+    #[test]
+    fn verification_catches_a_bad_copy() {
+        let dir = temp_dir("blobs-verify");
+        let file = dir.join("file.txt");
+        fs::write(&file, b"contents").expect("write");
+        let (hash, _) = hash_file(&file).expect("hash");
+        assert!(verify(&file, &hash).is_ok());
+
+        fs::write(&file, b"corrupted").expect("write");
+        assert_eq!(
+            verify(&file, &hash).expect_err("mismatch").kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[tokio::test]
+    async fn blobs_are_collected_once_unreferenced() {
+        let dir = temp_dir("blobs-gc");
+        let store = BlobStore::at(dir.clone()).expect("store");
+        let db = test_database("blobs-gc").await;
+        let blob = store.put_bytes(b"shared", "a.txt").expect("put");
+
+        let item = |title: &str| ItemActiveModel {
+            title: Set(title.to_string()),
+            item_type: Set("file".to_string()),
+            file_size: Set(Some(blob.size as i64)),
+            content_hash: Set(Some(blob.hash.clone())),
+            ..Default::default()
+        };
+        let first = item("a.txt").insert(&db).await.expect("insert");
+        let second = item("b.txt").insert(&db).await.expect("insert");
+        let count = || async {
+            Blob::find_by_id(blob.hash.clone())
+                .one(&db)
+                .await
+                .expect("query")
+                .map(|blob| blob.ref_count)
+        };
+        assert_eq!(count().await, Some(2));
+        assert_eq!(
+            find_duplicate(&db, &blob.hash)
+                .await
+                .expect("query")
+                .map(|item| item.id),
+            Some(first.id)
+        );
+
+        Item::delete_by_id(first.id)
+            .exec(&db)
+            .await
+            .expect("delete");
+        assert_eq!(count().await, Some(1));
+        assert_eq!(collect_garbage(&db, &store).await.expect("gc"), 0);
+        assert!(blob.path.exists());
+
+        Item::delete_by_id(second.id)
+            .exec(&db)
+            .await
+            .expect("delete");
+        assert_eq!(count().await, Some(0));
+        assert_eq!(collect_garbage(&db, &store).await.expect("gc"), 1);
+        assert!(!blob.path.exists());
+        assert_eq!(count().await, None);
+    }
+
+    #[tokio::test]
+    async fn rolling_back_keeps_a_blob_another_item_took_up() {
+        let dir = temp_dir("blobs-shared-rollback");
+        let store = BlobStore::at(dir.join("blobs")).expect("store");
+        let db = test_database("blobs-shared-rollback").await;
+        let first = dir.join("first.txt");
+        let second = dir.join("second.txt");
+        fs::write(&first, b"same").expect("write");
+        fs::write(&second, b"same").expect("write");
+
+        // Two imports of the same contents at once: the first stores them, the second finds
+        // them stored and saves its item, then the first gives up
+        let moved = store.move_in(&first).expect("move");
+        assert!(moved.blob.created);
+        let copied = store.copy_in(&second).expect("copy");
+        assert!(!copied.blob.created);
+        ItemActiveModel {
+            title: Set("second.txt".to_string()),
+            item_type: Set("file".to_string()),
+            content_hash: Set(Some(copied.blob.hash.clone())),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .expect("insert");
+        let blob = moved.blob.clone();
+        moved.rollback().expect("rollback");
+        release(&db, &blob).await.expect("release");
+
+        assert_eq!(fs::read(&first).expect("read"), b"same");
+        assert_eq!(collect_garbage(&db, &store).await.expect("gc"), 0);
+        assert_eq!(fs::read(&blob.path).expect("read"), b"same");
+    }
+
+    #[tokio::test]
+    async fn released_blobs_no_item_used_are_collected() {
+        let dir = temp_dir("blobs-released");
+        let store = BlobStore::at(dir.join("blobs")).expect("store");
+        let db = test_database("blobs-released").await;
+        let source = dir.join("unsaved.txt");
+        fs::write(&source, b"never saved").expect("write");
+
+        let staged = store.copy_in(&source).expect("copy");
+        let blob = staged.blob.clone();
+        staged.rollback().expect("rollback");
+        assert_eq!(collect_garbage(&db, &store).await.expect("gc"), 0);
+        release(&db, &blob).await.expect("release");
+        assert_eq!(collect_garbage(&db, &store).await.expect("gc"), 1);
+        assert!(!blob.path.exists());
+        assert!(source.exists());
+    }
+
+    #[tokio::test]
+    async fn collection_waits_for_blobs_being_stored() {
+        let dir = temp_dir("blobs-gc-held");
+        let store = BlobStore::at(dir.clone()).expect("store");
+        let db = test_database("blobs-gc-held").await;
+        let item = |hash: &str| ItemActiveModel {
+            title: Set("kept.txt".to_string()),
+            item_type: Set("file".to_string()),
+            content_hash: Set(Some(hash.to_string())),
+            ..Default::default()
+        };
+        let blob = store.put_bytes(b"kept", "kept.txt").expect("put");
+        let removed = item(&blob.hash).insert(&db).await.expect("insert");
+        Item::delete_by_id(removed.id)
+            .exec(&db)
+            .await
+            .expect("delete");
+
+        // Stored again while unused, as an import finding the contents already there would
+        let storing = hold().await;
+        let again = store.put_bytes(b"kept", "kept.txt").expect("put");
+        assert!(!again.created);
+        let collecting = collect_garbage(&db, &store);
+        tokio::pin!(collecting);
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(50), &mut collecting)
+                .await
+                .is_err()
+        );
+
+        item(&again.hash).insert(&db).await.expect("insert");
+        drop(storing);
+        assert_eq!(collecting.await.expect("gc"), 0);
+        assert!(again.path.exists());
+    }
+}
@@ -0,0 +1,155 @@
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use super::BookmarkError;
+
+/// Icons bigger than this are almost certainly not icons.
+pub const MAX_ICON_BYTES: usize = 1024 * 1024;
+pub const MAX_PREVIEW_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Images fetched for bookmarks, named by the SHA-256 of their contents so the same favicon
+/// shared by a thousand pages on one site is stored once.
+pub struct AssetStore {
+    dir: PathBuf,
+}
+
+impl AssetStore {
+    /// The store in `Junkdrawer/assets`, next to the `files` directory.
+    pub fn open(app_handle: &tauri::AppHandle) -> Result<Self, BookmarkError> {
+        Self::at(junkdrawer_dir(app_handle)?.join("assets"))
+    }
+
+    /// Page snapshots live with the user's files in `Junkdrawer/files/snapshots`.
+    pub fn open_snapshots(app_handle: &tauri::AppHandle) -> Result<Self, BookmarkError> {
+        Self::at(junkdrawer_dir(app_handle)?.join("files").join("snapshots"))
+    }
+
+    pub fn at(dir: PathBuf) -> Result<Self, BookmarkError> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Write `bytes` unless an identical asset is already stored, returning its path.
+    pub fn store(&self, bytes: &[u8], extension: &str) -> Result<PathBuf, BookmarkError> {
+        let hash = Sha256::digest(bytes)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        let path = self.dir.join(format!("{}.{}", hash, extension));
+        if path.exists() {
+            return Ok(path);
+        }
+
+        // Write under a temporary name so a crash never leaves a truncated file at the real one
+        let partial = self.dir.join(format!("{}.{}.partial", hash, extension));
+        let mut file = fs::File::create(&partial)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&partial, &path)?;
+        Ok(path)
+    }
+}
+
+fn junkdrawer_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, BookmarkError> {
+    let documents_dir = app_handle
+        .path()
+        .document_dir()
+        .map_err(|e| BookmarkError::Storage(std::io::Error::other(e.to_string())))?;
+    Ok(documents_dir.join("Junkdrawer"))
+}
+
+/// Work out the file extension from the leading bytes, rejecting anything that isn't an image.
+/// Servers routinely answer missing favicons with a 200 and an HTML error page.
+pub fn image_extension(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "png"),
+        (b"\xff\xd8\xff", "jpg"),
+        (b"GIF87a", "gif"),
+        (b"GIF89a", "gif"),
+        (b"\x00\x00\x01\x00", "ico"),
+        (b"BM", "bmp"),
+    ];
+
+    if let Some((_, extension)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+    {
+        return Some(extension);
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("webp");
+    }
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" && matches!(&bytes[8..12], b"avif" | b"avis") {
+        return Some("avif");
+    }
+
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_ascii_lowercase();
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    if (head.starts_with("<svg") || head.starts_with("<?xml")) && head.contains("<svg") {
+        return Some("svg");
+    }
+    None
+}
+
+/// The MIME type for an extension returned by [`image_extension`].
+pub fn image_mime_type(extension: &str) -> &'static str {
+    match extension {
+        "png" => "image/png",
+        "jpg" => "image/jpeg",
+        "gif" => "image/gif",
+        "ico" => "image/x-icon",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "svg" => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_common_image_formats() {
+        assert_eq!(image_extension(b"\x89PNG\r\n\x1a\n...."), Some("png"));
+        assert_eq!(image_extension(b"\xff\xd8\xff\xe0"), Some("jpg"));
+        assert_eq!(image_extension(b"\x00\x00\x01\x00\x01\x00"), Some("ico"));
+        assert_eq!(
+            image_extension(b"RIFF\x10\x00\x00\x00WEBPVP8 "),
+            Some("webp")
+        );
+        assert_eq!(
+            image_extension(
+                b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>"
+            ),
+            Some("svg")
+        );
+        assert_eq!(image_extension(b"<!doctype html><title>404</title>"), None);
+        assert_eq!(image_extension(b""), None);
+    }
+
+    #[test]
+    fn identical_assets_are_stored_once() {
+        let dir = std::env::temp_dir().join(format!(
+            "junkdrawer-assets-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let store = AssetStore::at(dir.clone()).expect("store");
+
+        let first = store.store(b"icon bytes", "png").expect("first");
+        let second = store.store(b"icon bytes", "png").expect("second");
+        let other = store.store(b"other bytes", "png").expect("other");
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert_eq!(fs::read(&first).expect("read"), b"icon bytes");
+        assert_eq!(fs::read_dir(&dir).expect("list").count(), 2);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
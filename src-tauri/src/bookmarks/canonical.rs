@@ -0,0 +1,158 @@
+use reqwest::Url;
+
+/// Query parameters that only say where a click came from. Anything starting with `utm_` is
+/// dropped as well.
+const TRACKING_PARAMETERS: &[&str] = &[
+    "_hsenc", "_hsmi", "dclid", "fbclid", "gclid", "gclsrc", "igshid", "mc_cid", "mc_eid",
+    "mkt_tok", "msclkid", "ref_src", "ref_url", "s_cid", "twclid", "wickedid", "yclid",
+];
+
+/// The key we deduplicate URLs on. Two URLs with the same key are the same page as far as a
+/// reader is concerned, though not necessarily byte-for-byte identical to fetch:
+///
+/// - `http` becomes `https`, the host is lowercased and a leading `www.` is dropped
+/// - default ports, fragments and a trailing slash on non-root paths go
+/// - tracking parameters are removed and the rest sorted
+///
+/// Returns `None` for anything that isn't an `http(s)` URL.
+pub fn canonicalize(url: &str) -> Option<String> {
+    let mut url = Url::parse(url.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    // The explicit port has to go before the scheme changes, or :443 on http would stay
+    if url.port() == Some(443) {
+        url.set_port(None).ok()?;
+    }
+    url.set_scheme("https").ok()?;
+
+    let host = url.host_str()?.trim_end_matches('.').to_ascii_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    url.set_host(Some(&host)).ok()?;
+
+    // Hash-bang routes are how some single-page apps address real pages, so those stay
+    if !url
+        .fragment()
+        .is_some_and(|fragment| fragment.starts_with('!'))
+    {
+        url.set_fragment(None);
+    }
+
+    let mut parameters = url
+        .query_pairs()
+        .filter(|(name, _)| !is_tracking_parameter(name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    parameters.sort();
+    if parameters.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(parameters);
+    }
+
+    let path = url.path().to_string();
+    if path.len() > 1 && path.ends_with('/') {
+        url.set_path(path.trim_end_matches('/'));
+    }
+
+    Some(url.to_string())
+}
+
+/// Pick the URL a page should be deduplicated on: its `<link rel="canonical">` when that looks
+/// trustworthy, otherwise the URL we ended up at after redirects.
+pub fn page_key(final_url: &str, declared: Option<&str>) -> Option<String> {
+    let fetched = canonicalize(final_url)?;
+    let Some(declared) = declared.and_then(canonicalize) else {
+        return Some(fetched);
+    };
+
+    let (Ok(fetched_url), Ok(declared_url)) = (Url::parse(&fetched), Url::parse(&declared)) else {
+        return Some(fetched);
+    };
+    // A canonical link on another site could merge unrelated bookmarks, and one pointing every
+    // page at the home page is a common template mistake
+    let same_site = fetched_url.host_str() == declared_url.host_str();
+    let lost_path = declared_url.path() == "/" && fetched_url.path() != "/";
+    if same_site && !lost_path {
+        Some(declared)
+    } else {
+        Some(fetched)
+    }
+}
+
+fn is_tracking_parameter(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMETERS.contains(&name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(url: &str) -> String {
+        canonicalize(url).expect("canonical")
+    }
+
+    #[test]
+    fn variants_of_one_page_share_a_key() {
+        let expected = "https://example.com/a";
+        for url in [
+            "https://example.com/a?utm_source=x",
+            "http://example.com/a/",
+            "https://www.example.com/a#top",
+            "HTTPS://Example.COM:443/a",
+            "http://example.com:80/a?fbclid=123&utm_medium=social",
+        ] {
+            assert_eq!(key(url), expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn meaningful_differences_are_kept() {
+        assert_eq!(
+            key("https://example.com/search?q=rust&page=2&utm_campaign=x"),
+            "https://example.com/search?page=2&q=rust"
+        );
+        assert_eq!(
+            key("https://example.com:8443/a"),
+            "https://example.com:8443/a"
+        );
+        assert_eq!(key("https://example.com/"), "https://example.com/");
+        assert_eq!(
+            key("https://example.com/#!/inbox"),
+            "https://example.com/#!/inbox"
+        );
+        assert_eq!(key("https://example.com/A"), "https://example.com/A");
+        assert_eq!(canonicalize("ftp://example.com/file"), None);
+        assert_eq!(canonicalize("not a url"), None);
+    }
+
+    #[test]
+    fn declared_canonical_is_honoured_within_the_site() {
+        assert_eq!(
+            page_key(
+                "https://example.com/posts/42?ref=feed",
+                Some("https://example.com/posts/42")
+            )
+            .as_deref(),
+            Some("https://example.com/posts/42")
+        );
+        assert_eq!(
+            page_key(
+                "https://m.example.com/posts/42",
+                Some("https://example.com/posts/42")
+            )
+            .as_deref(),
+            Some("https://m.example.com/posts/42")
+        );
+        assert_eq!(
+            page_key("https://example.com/posts/42", Some("https://example.com/")).as_deref(),
+            Some("https://example.com/posts/42")
+        );
+        assert_eq!(
+            page_key("https://example.com/posts/42", None).as_deref(),
+            Some("https://example.com/posts/42")
+        );
+    }
+}
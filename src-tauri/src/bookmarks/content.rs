@@ -0,0 +1,423 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use percent_encoding::percent_decode_str;
+
+use super::assets;
+
+/// What a fetched URL turned out to be, which decides the kind of item it becomes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentKind {
+    Html,
+    Feed,
+    File { mime_type: String },
+}
+
+/// How far into a body we look for signatures and `<meta charset>`; browsers use 1024 bytes.
+const SNIFF_BYTES: usize = 1024;
+
+const FEED_TYPES: &[&str] = &[
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/rdf+xml",
+    "application/feed+json",
+];
+const HTML_TYPES: &[&str] = &["text/html", "application/xhtml+xml"];
+/// Types servers send when they don't know better, so the body has the final say.
+const VAGUE_TYPES: &[&str] = &[
+    "",
+    "application/octet-stream",
+    "binary/octet-stream",
+    "text/plain",
+    "application/xml",
+    "text/xml",
+    // JSON Feeds are often served as plain JSON
+    "application/json",
+];
+
+/// Classify a response from its `Content-Type` header and the leading bytes of its body. Known
+/// binary signatures win over the header, because servers label PDFs and images as HTML more
+/// often than you would hope.
+pub fn classify(content_type: Option<&str>, body: &[u8]) -> ContentKind {
+    if let Some(mime_type) = binary_signature(body) {
+        return ContentKind::File {
+            mime_type: mime_type.to_string(),
+        };
+    }
+
+    let declared = content_type.map(essence).unwrap_or_default();
+    if FEED_TYPES.contains(&declared.as_str()) {
+        return ContentKind::Feed;
+    }
+    if HTML_TYPES.contains(&declared.as_str()) {
+        return ContentKind::Html;
+    }
+    if !VAGUE_TYPES.contains(&declared.as_str()) {
+        return ContentKind::File {
+            mime_type: declared,
+        };
+    }
+
+    let head = sniff_text(body);
+    let json_feed = head.starts_with('{') && head.contains("jsonfeed.org/version");
+    if json_feed
+        || ["<rss", "<feed", "<rdf:rdf"]
+            .iter()
+            .any(|marker| head.contains(marker))
+    {
+        ContentKind::Feed
+    } else if head.starts_with("<!doctype html") || head.starts_with("<html") {
+        ContentKind::Html
+    } else {
+        ContentKind::File {
+            mime_type: if declared.is_empty() {
+                "application/octet-stream".to_string()
+            } else {
+                declared
+            },
+        }
+    }
+}
+
+/// Decode a text body using, in order: a byte order mark, the header's charset, a charset
+/// declared in the document itself, then UTF-8 if the bytes are valid UTF-8 and Windows-1252
+/// (the web's de facto default) if they are not.
+pub fn decode(content_type: Option<&str>, body: &[u8]) -> String {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(body) {
+        return encoding
+            .decode_without_bom_handling(&body[bom_length..])
+            .0
+            .into_owned();
+    }
+
+    let declared = content_type
+        .and_then(header_charset)
+        .or_else(|| document_charset(body))
+        .and_then(|label| Encoding::for_label(label.as_bytes()));
+    let encoding = match declared {
+        Some(encoding) => encoding,
+        None if std::str::from_utf8(body).is_ok() => UTF_8,
+        None => WINDOWS_1252,
+    };
+    // Pages that declare UTF-16 in a meta tag are really ASCII-compatible; browsers do the same
+    let encoding = encoding.output_encoding();
+    encoding.decode_without_bom_handling(body).0.into_owned()
+}
+
+/// A file name for a download: the one in `Content-Disposition` if the server sent one,
+/// otherwise the last path segment, with an extension added from the MIME type when missing.
+pub fn file_name(url: &reqwest::Url, content_disposition: Option<&str>, mime_type: &str) -> String {
+    let name = content_disposition
+        .and_then(disposition_file_name)
+        .or_else(|| {
+            let segment = url.path_segments()?.rev().find(|s| !s.is_empty())?;
+            Some(percent_decode_str(segment).decode_utf8_lossy().into_owned())
+        })
+        .map(|name| sanitize_file_name(&name))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "download".to_string());
+
+    if std::path::Path::new(&name).extension().is_some() {
+        return name;
+    }
+    match extension_for(mime_type) {
+        Some(extension) => format!("{}.{}", name, extension),
+        None => name,
+    }
+}
+
+fn extension_for(mime_type: &str) -> Option<&'static str> {
+    // mime_guess lists extensions alphabetically, which makes plain text `.asm`
+    const PREFERRED: &[(&str, &str)] = &[
+        ("text/plain", "txt"),
+        ("text/csv", "csv"),
+        ("image/jpeg", "jpg"),
+        ("audio/mpeg", "mp3"),
+        ("video/mp4", "mp4"),
+    ];
+    if mime_type == "application/octet-stream" {
+        return None;
+    }
+    PREFERRED
+        .iter()
+        .find(|(preferred, _)| *preferred == mime_type)
+        .map(|(_, extension)| *extension)
+        .or_else(|| {
+            mime_guess::get_mime_extensions_str(mime_type)?
+                .first()
+                .copied()
+        })
+}
+
+fn disposition_file_name(header: &str) -> Option<String> {
+    let parameters = header
+        .split(';')
+        .skip(1)
+        .filter_map(|parameter| parameter.split_once('='))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim()))
+        .collect::<Vec<_>>();
+
+    // RFC 6266: the extended `filename*=UTF-8''...` form wins when both are present
+    let extended = parameters.iter().find_map(|(name, value)| {
+        let (charset, encoded) = value.split_once("''").filter(|_| name == "filename*")?;
+        let bytes = percent_decode_str(encoded).collect::<Vec<_>>();
+        let encoding = Encoding::for_label(charset.as_bytes()).unwrap_or(UTF_8);
+        Some(encoding.decode_without_bom_handling(&bytes).0.into_owned())
+    });
+    extended.or_else(|| {
+        parameters
+            .iter()
+            .find(|(name, _)| name == "filename")
+            .map(|(_, value)| value.trim_matches('"').to_string())
+    })
+}
+
+/// Keep just the final path component, without anything a file system would choke on.
+fn sanitize_file_name(name: &str) -> String {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    name.chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>()
+        .trim()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+/// The MIME type without parameters, lowercased: `"Text/HTML; charset=utf-8"` -> `"text/html"`.
+pub fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn header_charset(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']).to_string())
+    })
+}
+
+/// A charset from `<meta charset>`, `<meta http-equiv="Content-Type">` or an XML declaration.
+fn document_charset(body: &[u8]) -> Option<String> {
+    let head = sniff_text(body);
+    let start = ["charset=", "encoding="]
+        .iter()
+        .filter_map(|marker| head.find(marker).map(|index| index + marker.len()))
+        .min()?;
+    let value = head[start..].trim_start_matches(['"', '\'', ' ']);
+    let end = value
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')))
+        .unwrap_or(value.len());
+    let label = &value[..end];
+    (!label.is_empty()).then(|| label.to_string())
+}
+
+/// The start of the body as lowercase text with leading whitespace removed, for sniffing.
+fn sniff_text(body: &[u8]) -> String {
+    let head = &body[..body.len().min(SNIFF_BYTES)];
+    String::from_utf8_lossy(head)
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .to_ascii_lowercase()
+}
+
+fn binary_signature(body: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"Rar!\x1a\x07", "application/vnd.rar"),
+        (b"ID3", "audio/mpeg"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"\x1aE\xdf\xa3", "video/webm"),
+    ];
+
+    if let Some((_, mime_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| body.starts_with(signature))
+    {
+        return Some(mime_type);
+    }
+    if body.len() >= 12 && &body[4..8] == b"ftyp" && !matches!(&body[8..12], b"avif" | b"avis") {
+        return Some("video/mp4");
+    }
+    // SVG is text and perfectly happy to be served as XML, so leave it to the header
+    assets::image_extension(body)
+        .filter(|extension| *extension != "svg")
+        .map(assets::image_mime_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(mime_type: &str) -> ContentKind {
+        ContentKind::File {
+            mime_type: mime_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn classifies_by_header() {
+        assert_eq!(
+            classify(Some("text/html; charset=utf-8"), b"<p>hi"),
+            ContentKind::Html
+        );
+        assert_eq!(
+            classify(Some("application/rss+xml"), b"<?xml version=\"1.0\"?>"),
+            ContentKind::Feed
+        );
+        assert_eq!(
+            classify(Some("application/vnd.ms-excel"), b"anything"),
+            file("application/vnd.ms-excel")
+        );
+    }
+
+    #[test]
+    fn signatures_beat_a_lying_header() {
+        assert_eq!(
+            classify(Some("text/html"), b"%PDF-1.7\n..."),
+            file("application/pdf")
+        );
+        assert_eq!(
+            classify(Some("text/html"), b"\x89PNG\r\n\x1a\n...."),
+            file("image/png")
+        );
+        assert_eq!(
+            classify(None, b"PK\x03\x04\x14\x00"),
+            file("application/zip")
+        );
+    }
+
+    #[test]
+    fn vague_headers_are_sniffed() {
+        assert_eq!(
+            classify(
+                Some("text/xml"),
+                b"<?xml version=\"1.0\"?>\n<rss version=\"2.0\"><channel>"
+            ),
+            ContentKind::Feed
+        );
+        assert_eq!(
+            classify(
+                Some("application/octet-stream"),
+                b"\n  <!DOCTYPE html><html>"
+            ),
+            ContentKind::Html
+        );
+        assert_eq!(
+            classify(None, b"<feed xmlns=\"http://www.w3.org/2005/Atom\">"),
+            ContentKind::Feed
+        );
+        assert_eq!(
+            classify(Some("text/plain"), b"just some notes"),
+            file("text/plain")
+        );
+        assert_eq!(
+            classify(
+                Some("application/json; charset=utf-8"),
+                br#"{"version": "https://jsonfeed.org/version/1.1", "title": "Notes"}"#
+            ),
+            ContentKind::Feed
+        );
+        assert_eq!(
+            classify(Some("application/json"), br#"{"items": []}"#),
+            file("application/json")
+        );
+    }
+
+    #[test]
+    fn names_downloads() {
+        let url = |s: &str| reqwest::Url::parse(s).expect("url");
+
+        assert_eq!(
+            file_name(
+                &url("https://example.com/papers/Attention%20Is%20All.pdf"),
+                None,
+                "application/pdf"
+            ),
+            "Attention Is All.pdf"
+        );
+        assert_eq!(
+            file_name(
+                &url("https://example.com/download?id=7"),
+                Some("attachment; filename=\"report.xlsx\""),
+                "application/octet-stream"
+            ),
+            "report.xlsx"
+        );
+        assert_eq!(
+            file_name(
+                &url("https://example.com/d"),
+                Some(
+                    "attachment; filename=\"fallback.txt\"; filename*=UTF-8''r%C3%A9sum%C3%A9.txt"
+                ),
+                "text/plain"
+            ),
+            "résumé.txt"
+        );
+        assert_eq!(
+            file_name(
+                &url("https://example.com/d"),
+                Some("attachment; filename=\"../../etc/passwd\""),
+                "text/plain"
+            ),
+            "passwd.txt"
+        );
+        assert_eq!(
+            file_name(&url("https://example.com/"), None, "application/pdf"),
+            "download.pdf"
+        );
+    }
+
+    #[test]
+    fn decodes_charset_from_header() {
+        assert_eq!(
+            decode(Some("text/html; charset=ISO-8859-1"), b"caf\xe9"),
+            "café"
+        );
+        assert_eq!(
+            decode(
+                Some("text/html; charset=\"Shift_JIS\""),
+                b"\x93\xfa\x96\x7b"
+            ),
+            "日本"
+        );
+    }
+
+    #[test]
+    fn decodes_charset_from_document() {
+        assert_eq!(
+            decode(
+                Some("text/html"),
+                b"<meta charset=\"windows-1251\"><title>\xcf\xf0\xe8\xe2\xe5\xf2</title>"
+            ),
+            "<meta charset=\"windows-1251\"><title>Привет</title>"
+        );
+        assert_eq!(
+            decode(
+                None,
+                b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=euc-kr\">\xc7\xd1"
+            ),
+            "<meta http-equiv=\"Content-Type\" content=\"text/html; charset=euc-kr\">한"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_utf8_then_windows_1252() {
+        assert_eq!(decode(None, "naïve".as_bytes()), "naïve");
+        assert_eq!(decode(None, b"na\xefve \x93quoted\x94"), "naïve “quoted”");
+        assert_eq!(decode(None, b"\xef\xbb\xbfbom"), "bom");
+    }
+}
@@ -0,0 +1,324 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Metadata declared in a page's markup. Entities are decoded and whitespace collapsed by the
+/// time values land here.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Icon URLs, largest declared size first, ending with the site's `/favicon.ico`.
+    pub icons: Vec<String>,
+    pub details: PageDetails,
+}
+
+/// The fields we keep beyond title and description, stored as the item's `metadata` JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// OpenGraph properties without the `og:` prefix, e.g. `site_name`, `image`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub open_graph: BTreeMap<String, String>,
+    /// Twitter Card fields without the `twitter:` prefix, e.g. `card`, `creator`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub twitter: BTreeMap<String, String>,
+}
+
+impl PageDetails {
+    /// The image the page wants shown when it is shared.
+    pub fn preview_image(&self) -> Option<&str> {
+        ["image", "image:url", "image:secure_url"]
+            .iter()
+            .find_map(|key| self.open_graph.get(*key))
+            .or_else(|| self.twitter.get("image"))
+            .map(String::as_str)
+    }
+}
+
+/// Parse `html` fetched from `page_url`; relative URLs are resolved against it.
+pub fn parse(page_url: &str, html: &str) -> PageMetadata {
+    let document = Html::parse_document(html);
+    let base = reqwest::Url::parse(page_url).ok();
+    let meta = MetaTags::collect(&document);
+
+    let mut details = PageDetails {
+        lang: first_attr(&document, "html[lang]", "lang"),
+        author: meta.get("author").or_else(|| meta.get("article:author")),
+        published_time: meta
+            .get("article:published_time")
+            .or_else(|| meta.get("date")),
+        canonical_url: first_attr(&document, "link[rel~=canonical][href]", "href")
+            .or_else(|| meta.get("og:url"))
+            .map(|href| resolve(base.as_ref(), &href)),
+        open_graph: meta.prefixed("og:"),
+        twitter: meta.prefixed("twitter:"),
+    };
+    for key in ["image", "url"] {
+        if let Some(value) = details.open_graph.get_mut(key) {
+            *value = resolve(base.as_ref(), value);
+        }
+    }
+    if let Some(value) = details.twitter.get_mut("image") {
+        *value = resolve(base.as_ref(), value);
+    }
+
+    let title = select(&document, "title")
+        .first()
+        .map(text)
+        .and_then(non_empty)
+        .or_else(|| details.open_graph.get("title").cloned())
+        .or_else(|| details.twitter.get("title").cloned());
+
+    let description = meta
+        .get("description")
+        .or_else(|| details.open_graph.get("description").cloned())
+        .or_else(|| details.twitter.get("description").cloned());
+
+    PageMetadata {
+        title,
+        description,
+        icons: icons(&document, base.as_ref()),
+        details,
+    }
+}
+
+/// Assumed size of an apple-touch-icon that doesn't declare one; iOS asks for 180x180.
+const APPLE_TOUCH_ICON_SIZE: u32 = 180;
+/// `sizes="any"` marks a scalable icon, which beats any bitmap.
+const SCALABLE_ICON_SIZE: u32 = u32::MAX;
+
+fn icons(document: &Html, base: Option<&reqwest::Url>) -> Vec<String> {
+    let mut candidates = select(document, "link[rel][href]")
+        .into_iter()
+        .filter_map(|element| {
+            let element = element.value();
+            let rel = element.attr("rel")?.to_ascii_lowercase();
+            let rels = rel.split_whitespace().collect::<Vec<_>>();
+            let apple = rels.iter().any(|rel| rel.starts_with("apple-touch-icon"));
+            // `mask-icon` is a monochrome pinned-tab mask, not something to show in a grid
+            if !apple && !rels.contains(&"icon") {
+                return None;
+            }
+            let href = non_empty(element.attr("href")?.into())?;
+            let size = element
+                .attr("sizes")
+                .and_then(largest_size)
+                .unwrap_or(if apple { APPLE_TOUCH_ICON_SIZE } else { 0 });
+            Some((resolve(base, &href), size))
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+
+    let mut icons = Vec::new();
+    for (url, _) in candidates {
+        if !icons.contains(&url) {
+            icons.push(url);
+        }
+    }
+    if let Some(favicon) = base.and_then(|base| base.join("/favicon.ico").ok()) {
+        let favicon = favicon.to_string();
+        if !icons.contains(&favicon) {
+            icons.push(favicon);
+        }
+    }
+    icons
+}
+
+/// The largest width in a `sizes` attribute such as `"16x16 32x32"`.
+fn largest_size(sizes: &str) -> Option<u32> {
+    sizes
+        .split_whitespace()
+        .filter_map(|size| {
+            if size.eq_ignore_ascii_case("any") {
+                return Some(SCALABLE_ICON_SIZE);
+            }
+            let (width, _) = size.split_once(['x', 'X'])?;
+            width.parse().ok()
+        })
+        .max()
+}
+
+/// `<meta>` tags keyed by their lowercased `name` or `property`, keeping the first value of each.
+struct MetaTags {
+    values: BTreeMap<String, String>,
+}
+
+impl MetaTags {
+    fn collect(document: &Html) -> Self {
+        let mut values = BTreeMap::new();
+        for element in select(document, "meta[content]") {
+            let element = element.value();
+            // Twitter tags are often published with `property` and OpenGraph ones with `name`
+            let Some(key) = element.attr("property").or_else(|| element.attr("name")) else {
+                continue;
+            };
+            let Some(content) = element
+                .attr("content")
+                .and_then(|value| non_empty(value.into()))
+            else {
+                continue;
+            };
+            values
+                .entry(key.trim().to_ascii_lowercase())
+                .or_insert(content);
+        }
+        Self { values }
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn prefixed(&self, prefix: &str) -> BTreeMap<String, String> {
+        self.values
+            .iter()
+            .filter_map(|(key, value)| {
+                let key = key.strip_prefix(prefix)?;
+                Some((key.to_string(), value.clone()))
+            })
+            .collect()
+    }
+}
+
+pub(super) fn select<'a>(document: &'a Html, selector: &str) -> Vec<ElementRef<'a>> {
+    // Selectors are literals in this module, so a parse failure is a programming error
+    let selector = Selector::parse(selector).expect("valid selector");
+    document.select(&selector).collect()
+}
+
+fn first_attr(document: &Html, selector: &str, attr: &str) -> Option<String> {
+    select(document, selector)
+        .into_iter()
+        .find_map(|element| element.value().attr(attr).and_then(|v| non_empty(v.into())))
+}
+
+fn text(element: &ElementRef) -> String {
+    element.text().collect::<String>()
+}
+
+/// Collapse runs of whitespace and drop values that end up empty.
+fn non_empty(value: String) -> Option<String> {
+    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!value.is_empty()).then_some(value)
+}
+
+pub(super) fn resolve(base: Option<&reqwest::Url>, href: &str) -> String {
+    base.and_then(|base| base.join(href).ok())
+        .map(|url| url.to_string())
+        .unwrap_or_else(|| href.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/pages")
+            .join(name);
+        std::fs::read_to_string(&path).expect("fixture")
+    }
+
+    #[test]
+    fn article_with_messy_markup() {
+        let page = parse(
+            "https://example.com/posts/42?ref=feed",
+            &fixture("article.html"),
+        );
+
+        assert_eq!(page.title.as_deref(), Some("Café & Bar: a \"field\" guide"));
+        assert_eq!(
+            page.description.as_deref(),
+            Some("Where to drink <good> coffee & eat well")
+        );
+
+        let details = page.details;
+        assert_eq!(details.lang.as_deref(), Some("en-GB"));
+        assert_eq!(details.author.as_deref(), Some("Zoë Example"));
+        assert_eq!(
+            details.published_time.as_deref(),
+            Some("2024-03-05T09:30:00Z")
+        );
+        assert_eq!(
+            details.canonical_url.as_deref(),
+            Some("https://example.com/posts/42")
+        );
+        assert_eq!(
+            details.open_graph.get("image").map(String::as_str),
+            Some("https://example.com/images/cafe.jpg")
+        );
+        assert_eq!(
+            details.open_graph.get("site_name").map(String::as_str),
+            Some("Example Eats")
+        );
+        assert_eq!(
+            details.twitter.get("card").map(String::as_str),
+            Some("summary_large_image")
+        );
+        assert_eq!(
+            details.twitter.get("creator").map(String::as_str),
+            Some("@example")
+        );
+        assert_eq!(
+            details.preview_image(),
+            Some("https://example.com/images/cafe.jpg")
+        );
+    }
+
+    #[test]
+    fn icons_are_ordered_by_size() {
+        let page = parse("https://example.com/blog/post", &fixture("icons.html"));
+
+        assert_eq!(
+            page.icons,
+            vec![
+                "https://example.com/icon.svg",
+                "https://example.com/apple-touch-icon.png",
+                "https://cdn.example.com/icon-32.png",
+                "https://example.com/blog/favicon-16.png",
+                "https://example.com/favicon.ico",
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_open_graph_and_twitter() {
+        let page = parse("https://blog.example.org/", &fixture("social_only.html"));
+
+        assert_eq!(page.title.as_deref(), Some("Only OpenGraph here"));
+        assert_eq!(page.description.as_deref(), Some("Described by Twitter"));
+        assert_eq!(
+            page.details.canonical_url.as_deref(),
+            Some("https://blog.example.org/entry")
+        );
+        assert_eq!(page.details.lang, None);
+    }
+
+    #[test]
+    fn minimal_page_has_only_a_title() {
+        let page = parse("https://example.net/", &fixture("minimal.html"));
+
+        assert_eq!(page.title.as_deref(), Some("Plain old page"));
+        assert_eq!(page.description, None);
+        assert_eq!(page.icons, vec!["https://example.net/favicon.ico"]);
+        assert_eq!(page.details, PageDetails::default());
+        assert_eq!(
+            serde_json::to_value(&page.details).expect("serialize"),
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn untitled_page_has_no_title() {
+        let page = parse("https://example.net/", &fixture("untitled.html"));
+        assert_eq!(page.title, None);
+    }
+}
@@ -0,0 +1,732 @@
+use anyhow::Result;
+use reqwest::{Client, RequestBuilder};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::time::timeout;
+
+mod assets;
+mod canonical;
+mod content;
+mod html;
+pub mod profiles;
+mod readability;
+mod snapshot;
+mod structured;
+mod throttle;
+
+pub use assets::AssetStore;
+pub use canonical::canonicalize;
+pub use html::PageDetails;
+pub use profiles::{DomainProfile, DomainProfileRequest};
+pub use structured::{OEmbed, StructuredFields, Subtype};
+pub use throttle::HostLimiter;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const USER_AGENT: &str = "Junkdrawer/0.1.0";
+/// Whole-transfer limit for captured URLs, which may be large files rather than pages.
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(300);
+/// Captures are held in memory until stored, so bigger downloads need a fetch profile that
+/// raises the limit for their domain.
+pub const MAX_CAPTURE_BYTES: usize = 32 * 1024 * 1024;
+const MAX_RETRIES: usize = 2;
+const MAX_OEMBED_BYTES: usize = 256 * 1024;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Error, Debug)]
+pub enum BookmarkError {
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error("Request timeout")]
+    Timeout,
+    #[error("No title found in page")]
+    NoTitle,
+    #[error("{0} is larger than {1} bytes")]
+    TooLarge(String, usize),
+    #[error("{0} is not an image")]
+    NotAnImage(String),
+    #[error("Failed to store asset: {0}")]
+    Storage(#[from] std::io::Error),
+    #[error("Failed to render snapshot: {0}")]
+    Snapshot(String),
+    #[error("Invalid fetch profile: {0}")]
+    Profile(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookmarkMetadata {
+    pub title: String,
+    pub description: Option<String>,
+    pub url: String,
+    /// Deduplication key, from the canonical link or the URL we were redirected to.
+    pub canonical_url: Option<String>,
+    pub favicon_url: Option<String>,
+    /// Icon links found in the page, best first; `favicon_url` is the one that downloaded.
+    #[serde(skip)]
+    pub icon_candidates: Vec<String>,
+    /// Readable text of the page's main content, which becomes the item's `content`.
+    pub article: Option<String>,
+    /// The page as fetched, kept around long enough to snapshot it.
+    #[serde(skip)]
+    pub page_html: String,
+    /// The page's oEmbed endpoint, fetched along with it.
+    #[serde(skip)]
+    pub oembed_url: Option<String>,
+    pub details: BookmarkDetails,
+}
+
+/// Everything stored in a bookmark item's `metadata` JSON column.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BookmarkDetails {
+    #[serde(flatten)]
+    pub page: PageDetails,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_image_path: Option<String>,
+    /// The page's own summary; `content` holds the article text when there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub word_count: Option<usize>,
+    /// Self-contained copy of the page with styles and images inlined.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_check: Option<crate::link_checker::LinkCheck>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh: Option<crate::refresher::RefreshStatus>,
+    /// Titles and descriptions replaced by refreshes, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<crate::refresher::MetadataRevision>,
+    /// Item fields edited by hand, which refreshes leave alone.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edited_fields: Vec<String>,
+    /// What kind of thing the page is about, from its structured data.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtype: Option<Subtype>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structured: Option<StructuredFields>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oembed: Option<OEmbed>,
+}
+
+/// What a URL turned out to hold once fetched.
+pub enum Capture {
+    Page(Box<BookmarkMetadata>),
+    File(Download),
+    Feed(FeedLink),
+}
+
+impl Capture {
+    /// The key to check for an existing item before saving this one.
+    pub fn canonical_url(&self) -> Option<&str> {
+        match self {
+            Capture::Page(metadata) => metadata.canonical_url.as_deref(),
+            Capture::File(download) => download.canonical_url.as_deref(),
+            Capture::Feed(feed) => feed.canonical_url.as_deref(),
+        }
+    }
+}
+
+/// A non-HTML response, to be stored as a file item.
+pub struct Download {
+    pub url: String,
+    pub canonical_url: Option<String>,
+    pub file_name: String,
+    pub mime_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// A feed, to be subscribed to; the subscription reads its title and entries itself.
+pub struct FeedLink {
+    pub url: String,
+    pub canonical_url: Option<String>,
+}
+
+/// Where a saved URL ended up when it was last requested.
+pub struct LinkResponse {
+    pub status: u16,
+    pub final_url: String,
+}
+
+pub struct BookmarkProcessor {
+    client: Client,
+    /// Clients for domains with a fetch profile, most specific domain first.
+    profiles: Vec<(DomainProfile, Client)>,
+}
+
+impl BookmarkProcessor {
+    pub fn new() -> Self {
+        Self::with_profiles(Vec::new())
+    }
+
+    /// A processor fetching each domain the way its profile says. A profile whose client
+    /// can't be built is skipped, falling back to the default client for its domain.
+    pub fn with_profiles(profiles: Vec<DomainProfile>) -> Self {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(USER_AGENT)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        let profiles = profiles
+            .into_iter()
+            .filter_map(|profile| match profile.client() {
+                Ok(client) => Some((profile, client)),
+                Err(e) => {
+                    eprintln!("Ignoring fetch profile for {}: {}", profile.domain, e);
+                    None
+                }
+            })
+            .collect();
+        Self { client, profiles }
+    }
+
+    /// A processor using the fetch profiles saved in `db`, or none if they can't be loaded.
+    pub async fn with_saved_profiles(db: &DatabaseConnection) -> Self {
+        match profiles::load(db).await {
+            Ok(profiles) => Self::with_profiles(profiles),
+            Err(e) => {
+                eprintln!("Failed to load fetch profiles: {}", e);
+                Self::new()
+            }
+        }
+    }
+
+    fn profile_for(&self, url: &reqwest::Url) -> Option<&(DomainProfile, Client)> {
+        let host = url.host_str()?;
+        self.profiles
+            .iter()
+            .find(|(profile, _)| profile.matches(host))
+    }
+
+    /// Start a GET for `url` with the matching profile's client and cookies, if any.
+    pub(crate) fn get(&self, url: &reqwest::Url) -> RequestBuilder {
+        match self.profile_for(url) {
+            Some((profile, client)) => {
+                let request = client.get(url.clone());
+                match profile.cookie_header(url) {
+                    Some(cookies) => request.header(reqwest::header::COOKIE, cookies),
+                    None => request,
+                }
+            }
+            None => self.client.get(url.clone()),
+        }
+    }
+
+    /// The response size limit for `url`: its profile's, or `default` without one.
+    pub(crate) fn max_bytes(&self, url: &reqwest::Url, default: usize) -> usize {
+        self.profile_for(url)
+            .and_then(|(profile, _)| profile.max_response_bytes)
+            .unwrap_or(default)
+    }
+
+    /// Fetch `url` and work out what it is: a page to bookmark, a file to keep, or a feed.
+    pub async fn capture(&self, url: &str) -> Result<Capture, BookmarkError> {
+        // Validate URL format
+        let parsed_url =
+            reqwest::Url::parse(url).map_err(|_| BookmarkError::InvalidUrl(url.to_string()))?;
+
+        with_retries(|| self.capture_attempt(&parsed_url)).await
+    }
+
+    /// Request `url` to see whether it still resolves, following redirects but not reading the
+    /// body. Any HTTP status is a successful check; only failing to get one is an error.
+    pub async fn check_link(&self, url: &str) -> Result<LinkResponse, BookmarkError> {
+        let parsed_url =
+            reqwest::Url::parse(url).map_err(|_| BookmarkError::InvalidUrl(url.to_string()))?;
+
+        with_retries(|| async {
+            let response = timeout(REQUEST_TIMEOUT, self.get(&parsed_url).send())
+                .await
+                .map_err(|_| BookmarkError::Timeout)?
+                .map_err(BookmarkError::Network)?;
+            Ok(LinkResponse {
+                status: response.status().as_u16(),
+                final_url: response.url().to_string(),
+            })
+        })
+        .await
+    }
+
+    async fn capture_attempt(&self, url: &reqwest::Url) -> Result<Capture, BookmarkError> {
+        let request = self.get(url).timeout(CAPTURE_TIMEOUT);
+        // An error page is not what the user meant to save
+        let response = timeout(REQUEST_TIMEOUT, request.send())
+            .await
+            .map_err(|_| BookmarkError::Timeout)?
+            .map_err(BookmarkError::Network)?
+            .error_for_status()
+            .map_err(BookmarkError::Network)?;
+
+        let final_url = response.url().to_string();
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let content_type = header(reqwest::header::CONTENT_TYPE);
+        let content_disposition = header(reqwest::header::CONTENT_DISPOSITION);
+        let max_bytes = self.max_bytes(url, MAX_CAPTURE_BYTES);
+        let body = self.read_body(response, url.as_str(), max_bytes).await?;
+
+        match content::classify(content_type.as_deref(), &body) {
+            content::ContentKind::Html => {
+                let html = content::decode(content_type.as_deref(), &body);
+                let mut metadata = self.parse_metadata(url.as_str(), &html)?;
+                metadata.canonical_url =
+                    canonical::page_key(&final_url, metadata.details.page.canonical_url.as_deref());
+                if let Some(oembed_url) = metadata.oembed_url.clone() {
+                    self.apply_oembed(&mut metadata, &oembed_url).await;
+                }
+                Ok(Capture::Page(Box::new(metadata)))
+            }
+            content::ContentKind::Feed => Ok(Capture::Feed(FeedLink {
+                url: url.to_string(),
+                canonical_url: canonicalize(&final_url),
+            })),
+            content::ContentKind::File { mime_type } => Ok(Capture::File(Download {
+                url: url.to_string(),
+                canonical_url: canonicalize(&final_url),
+                file_name: content::file_name(url, content_disposition.as_deref(), &mime_type),
+                mime_type,
+                bytes: body,
+            })),
+        }
+    }
+
+    fn parse_metadata(&self, url: &str, html: &str) -> Result<BookmarkMetadata, BookmarkError> {
+        let mut page = html::parse(url, html);
+        let title = page.title.ok_or(BookmarkError::NoTitle)?;
+        let article = readability::extract(html);
+        let structured = structured::extract(
+            url,
+            html,
+            page.details.open_graph.get("type").map(String::as_str),
+        );
+        if page.details.author.is_none() {
+            page.details.author = structured.fields.authors.first().cloned();
+        }
+
+        Ok(BookmarkMetadata {
+            title,
+            description: page.description.clone(),
+            url: url.to_string(),
+            canonical_url: canonicalize(url),
+            favicon_url: None,
+            icon_candidates: page.icons,
+            details: BookmarkDetails {
+                page: page.details,
+                description: page.description,
+                word_count: article.as_ref().map(|article| article.word_count),
+                subtype: structured.subtype,
+                structured: (!structured.fields.is_empty()).then_some(structured.fields),
+                ..Default::default()
+            },
+            article: article.map(|article| article.text),
+            page_html: html.to_string(),
+            oembed_url: structured.oembed_url,
+        })
+    }
+
+    /// Fetch the page's oEmbed description. Providers like video hosts say more there than in
+    /// the page, but a bookmark doesn't need it, so failures are only logged.
+    async fn apply_oembed(&self, metadata: &mut BookmarkMetadata, url: &str) {
+        let oembed = match self.download(url, MAX_OEMBED_BYTES).await {
+            Ok(bytes) => serde_json::from_slice::<OEmbed>(&bytes).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match oembed {
+            Ok(oembed) => {
+                if metadata.details.subtype.is_none() && oembed.kind == "video" {
+                    metadata.details.subtype = Some(Subtype::Video);
+                }
+                if metadata.details.page.author.is_none() {
+                    metadata.details.page.author = oembed.author_name.clone();
+                }
+                metadata.details.oembed = Some(oembed);
+            }
+            Err(e) => eprintln!("Skipping oEmbed {}: {}", url, e),
+        }
+    }
+
+    /// Download the page's icon and preview image into `store`, recording where they went.
+    /// Missing or broken images are skipped; a bookmark is still useful without them.
+    pub async fn fetch_assets(&self, metadata: &mut BookmarkMetadata, store: &AssetStore) {
+        for candidate in &metadata.icon_candidates {
+            match self
+                .download_image(candidate, assets::MAX_ICON_BYTES, store)
+                .await
+            {
+                Ok(path) => {
+                    metadata.favicon_url = Some(candidate.clone());
+                    metadata.details.favicon_path = Some(path.display().to_string());
+                    break;
+                }
+                Err(e) => eprintln!("Skipping icon {}: {}", candidate, e),
+            }
+        }
+
+        if let Some(image) = metadata.details.page.preview_image().map(str::to_string) {
+            match self
+                .download_image(&image, assets::MAX_PREVIEW_IMAGE_BYTES, store)
+                .await
+            {
+                Ok(path) => metadata.details.preview_image_path = Some(path.display().to_string()),
+                Err(e) => eprintln!("Skipping preview image {}: {}", image, e),
+            }
+        }
+    }
+
+    async fn download_image(
+        &self,
+        url: &str,
+        max_bytes: usize,
+        store: &AssetStore,
+    ) -> Result<std::path::PathBuf, BookmarkError> {
+        let bytes = self.download(url, max_bytes).await?;
+        let extension = assets::image_extension(&bytes)
+            .ok_or_else(|| BookmarkError::NotAnImage(url.to_string()))?;
+        store.store(&bytes, extension)
+    }
+
+    /// Save a self-contained copy of the page into `store` so it can still be read after the
+    /// site changes or disappears. Resources that fail to download are left out of the copy.
+    pub async fn save_snapshot(
+        &self,
+        metadata: &mut BookmarkMetadata,
+        store: &AssetStore,
+    ) -> Result<(), BookmarkError> {
+        let resources = snapshot::resources(&metadata.url, &metadata.page_html);
+        let mut inlined = HashMap::new();
+        let mut budget = snapshot::MAX_SNAPSHOT_BYTES.saturating_sub(metadata.page_html.len());
+
+        for url in resources.stylesheets {
+            match self
+                .download(&url, snapshot::MAX_STYLESHEET_BYTES.min(budget))
+                .await
+            {
+                Ok(bytes) => {
+                    budget -= bytes.len();
+                    let css = String::from_utf8_lossy(&bytes).into_owned();
+                    inlined.insert(url, snapshot::Inlined::Stylesheet(css));
+                }
+                Err(e) => eprintln!("Leaving stylesheet {} out of snapshot: {}", url, e),
+            }
+        }
+        for url in resources.images {
+            let bytes = match self.download(&url, snapshot::image_limit(budget)).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    eprintln!("Leaving image {} out of snapshot: {}", url, e);
+                    continue;
+                }
+            };
+            let Some(extension) = assets::image_extension(&bytes) else {
+                eprintln!("Leaving image {} out of snapshot: not an image", url);
+                continue;
+            };
+            budget = budget.saturating_sub(snapshot::encoded_len(bytes.len()));
+            let mime_type = assets::image_mime_type(extension).to_string();
+            inlined.insert(url, snapshot::Inlined::Image { mime_type, bytes });
+        }
+
+        let html = snapshot::render(&metadata.url, &metadata.page_html, &inlined)
+            .map_err(BookmarkError::Snapshot)?;
+        let path = store.store(html.as_bytes(), "html")?;
+        metadata.details.snapshot_path = Some(path.display().to_string());
+        Ok(())
+    }
+
+    /// Fetch `url` into memory, refusing bodies larger than `max_bytes`.
+    async fn download(&self, url: &str, max_bytes: usize) -> Result<Vec<u8>, BookmarkError> {
+        let parsed_url =
+            reqwest::Url::parse(url).map_err(|_| BookmarkError::InvalidUrl(url.to_string()))?;
+
+        // A profile's limit can only lower the caller's, which is sized for the resource
+        let max_bytes = self.max_bytes(&parsed_url, max_bytes).min(max_bytes);
+        let response = timeout(REQUEST_TIMEOUT, self.get(&parsed_url).send())
+            .await
+            .map_err(|_| BookmarkError::Timeout)?
+            .map_err(BookmarkError::Network)?
+            .error_for_status()
+            .map_err(BookmarkError::Network)?;
+
+        self.read_body(response, url, max_bytes).await
+    }
+
+    async fn read_body(
+        &self,
+        mut response: reqwest::Response,
+        url: &str,
+        max_bytes: usize,
+    ) -> Result<Vec<u8>, BookmarkError> {
+        // Trust a declared length to bail early, but count the bytes anyway since it can lie
+        if response
+            .content_length()
+            .is_some_and(|length| length > max_bytes as u64)
+        {
+            return Err(BookmarkError::TooLarge(url.to_string(), max_bytes));
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = timeout(REQUEST_TIMEOUT, response.chunk())
+            .await
+            .map_err(|_| BookmarkError::Timeout)?
+            .map_err(BookmarkError::Network)?
+        {
+            if bytes.len() + chunk.len() > max_bytes {
+                return Err(BookmarkError::TooLarge(url.to_string(), max_bytes));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Combine two `metadata` JSON objects, keeping `existing`'s value wherever both have a key.
+/// Anything that isn't a JSON object is kept as-is rather than merged.
+pub fn merge_metadata(existing: Option<&str>, fetched: Option<&str>) -> Option<String> {
+    let parse = |value: Option<&str>| {
+        value.and_then(|value| serde_json::from_str::<serde_json::Value>(value).ok())
+    };
+    match (parse(existing), parse(fetched)) {
+        (
+            Some(serde_json::Value::Object(mut existing)),
+            Some(serde_json::Value::Object(fetched)),
+        ) => {
+            for (key, value) in fetched {
+                existing.entry(key).or_insert(value);
+            }
+            Some(serde_json::Value::Object(existing).to_string())
+        }
+        _ => existing.or(fetched).map(str::to_string),
+    }
+}
+
+/// An item's `metadata` as a JSON object to add keys to, empty when there is none. `None` when
+/// it holds something else, which callers leave as it is rather than replace.
+pub fn metadata_object(
+    metadata: Option<&str>,
+) -> Option<serde_json::Map<String, serde_json::Value>> {
+    match metadata {
+        None => Some(serde_json::Map::new()),
+        Some(metadata) => match serde_json::from_str(metadata) {
+            Ok(serde_json::Value::Object(object)) => Some(object),
+            _ => None,
+        },
+    }
+}
+
+/// Retry logic with exponential backoff, shared by everything that fetches a saved URL.
+async fn with_retries<T, F, Fut>(mut attempt: F) -> Result<T, BookmarkError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, BookmarkError>>,
+{
+    let mut last_error = None;
+    for retry in 0..=MAX_RETRIES {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            // A malformed URL, a missing page or one too big to keep won't get any better by
+            // asking again
+            Err(e @ (BookmarkError::InvalidUrl(_) | BookmarkError::TooLarge(..))) => return Err(e),
+            Err(BookmarkError::Network(e)) if e.status().is_some_and(|s| s.is_client_error()) => {
+                return Err(BookmarkError::Network(e))
+            }
+            Err(e) => {
+                last_error = Some(e);
+                if retry < MAX_RETRIES {
+                    tokio::time::sleep(RETRY_DELAY * (2_u32.pow(retry as u32))).await;
+                }
+            }
+        }
+    }
+
+    Err(last_error.unwrap())
+}
+
+impl Default for BookmarkProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{HttpStub, StubResponse};
+
+    #[test]
+    fn merging_metadata_keeps_existing_values() {
+        assert_eq!(
+            merge_metadata(
+                Some(r#"{"imported_from":"browser","author":"Kept"}"#),
+                Some(r#"{"author":"Fetched","lang":"en"}"#)
+            )
+            .as_deref(),
+            Some(r#"{"author":"Kept","imported_from":"browser","lang":"en"}"#)
+        );
+        assert_eq!(
+            merge_metadata(None, Some(r#"{"lang":"en"}"#)).as_deref(),
+            Some(r#"{"lang":"en"}"#)
+        );
+        assert_eq!(
+            merge_metadata(Some("not json"), Some(r#"{"lang":"en"}"#)).as_deref(),
+            Some("not json")
+        );
+        assert_eq!(merge_metadata(None, None), None);
+    }
+
+    #[tokio::test]
+    async fn fetch_profiles_apply_to_their_domain() {
+        let page = "<html><head><title>Members only</title></head></html>";
+        let stub = HttpStub::start(vec![
+            (
+                "/members",
+                StubResponse::new(200, page).header("Content-Type", "text/html"),
+            ),
+            (
+                "/huge",
+                StubResponse::new(200, "x".repeat(2048)).header("Content-Type", "text/plain"),
+            ),
+        ])
+        .await;
+        let profile = DomainProfile {
+            domain: "127.0.0.1".to_string(),
+            headers: HashMap::from([("X-Token".to_string(), "secret".to_string())]),
+            cookies: vec![profiles::Cookie {
+                domain: "127.0.0.1".to_string(),
+                include_subdomains: false,
+                path: "/".to_string(),
+                secure: false,
+                expires: 0,
+                name: "session".to_string(),
+                value: "abc".to_string(),
+            }],
+            user_agent: Some("Custom/1.0".to_string()),
+            max_response_bytes: Some(1024),
+            ..Default::default()
+        };
+
+        let plain = BookmarkProcessor::new();
+        plain
+            .check_link(&stub.url("/members"))
+            .await
+            .expect("check");
+        assert_eq!(
+            stub.header("/members", "User-Agent").as_deref(),
+            Some(USER_AGENT)
+        );
+        assert_eq!(stub.header("/members", "Cookie"), None);
+
+        let processor = BookmarkProcessor::with_profiles(vec![profile]);
+        let Capture::Page(metadata) = processor
+            .capture(&stub.url("/members"))
+            .await
+            .expect("capture")
+        else {
+            panic!("expected a page");
+        };
+        assert_eq!(metadata.title, "Members only");
+        assert_eq!(
+            stub.header("/members", "User-Agent").as_deref(),
+            Some("Custom/1.0")
+        );
+        assert_eq!(
+            stub.header("/members", "X-Token").as_deref(),
+            Some("secret")
+        );
+        assert_eq!(
+            stub.header("/members", "Cookie").as_deref(),
+            Some("session=abc")
+        );
+
+        assert!(matches!(
+            processor.capture(&stub.url("/huge")).await,
+            Err(BookmarkError::TooLarge(_, 1024))
+        ));
+        assert!(plain.capture(&stub.url("/huge")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn pages_are_classified_with_their_oembed() {
+        let page = r#"<html><head><title>Launch talk</title>
+            <link rel="alternate" type="application/json+oembed" href="/oembed.json">
+            </head><body><p>Watch it.</p></body></html>"#;
+        let oembed = r#"{"type": "video", "version": "1.0", "title": "Launch talk",
+            "author_name": "Ada", "provider_name": "Tube",
+            "html": "<iframe src=\"https://tube.example/embed/1\"></iframe>"}"#;
+        let stub = HttpStub::start(vec![
+            (
+                "/watch",
+                StubResponse::new(200, page).header("Content-Type", "text/html"),
+            ),
+            (
+                "/oembed.json",
+                StubResponse::new(200, oembed).header("Content-Type", "application/json"),
+            ),
+        ])
+        .await;
+
+        let Capture::Page(metadata) = BookmarkProcessor::new()
+            .capture(&stub.url("/watch"))
+            .await
+            .expect("capture")
+        else {
+            panic!("expected a page");
+        };
+        let details = &metadata.details;
+        assert_eq!(details.subtype, Some(Subtype::Video));
+        assert_eq!(details.page.author.as_deref(), Some("Ada"));
+        let oembed = details.oembed.as_ref().expect("oembed");
+        assert_eq!(oembed.provider_name.as_deref(), Some("Tube"));
+        assert!(oembed
+            .html
+            .as_deref()
+            .is_some_and(|html| html.contains("<iframe")));
+
+        let json = serde_json::to_value(details).expect("serialize");
+        assert_eq!(json["subtype"], "video");
+        assert_eq!(json["oembed"]["type"], "video");
+    }
+
+    #[tokio::test]
+    async fn error_pages_are_not_captured() {
+        let stub = HttpStub::start(vec![(
+            "/gone",
+            StubResponse::new(404, "<html><title>Not found</title></html>")
+                .header("Content-Type", "text/html"),
+        )])
+        .await;
+
+        let result = BookmarkProcessor::new().capture(&stub.url("/gone")).await;
+        assert!(matches!(result, Err(BookmarkError::Network(_))));
+        // Client errors aren't retried
+        assert_eq!(stub.paths(), vec!["/gone"]);
+    }
+
+    #[tokio::test]
+    async fn json_feeds_served_as_json_are_feeds() {
+        let feed = r#"{"version": "https://jsonfeed.org/version/1.1", "title": "Notes",
+            "items": [{"id": "1", "url": "https://example.com/1"}]}"#;
+        let stub = HttpStub::start(vec![(
+            "/feed.json",
+            StubResponse::new(200, feed).header("Content-Type", "application/json"),
+        )])
+        .await;
+
+        let capture = BookmarkProcessor::new()
+            .capture(&stub.url("/feed.json"))
+            .await
+            .expect("capture");
+        assert!(matches!(capture, Capture::Feed(_)));
+    }
+}
@@ -0,0 +1,462 @@
+//! Per-domain fetch profiles: the headers, cookies, proxy, user agent, size limit and TLS
+//! settings to use for pages on one site, so pages behind a login or on an intranet come back
+//! as the pages themselves rather than a login form.
+
+use chrono::Utc;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, Proxy, Url};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+    TryIntoModel,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{BookmarkError, REQUEST_TIMEOUT, USER_AGENT};
+use crate::entities::{fetch_profile, FetchProfile, FetchProfileActiveModel, FetchProfileModel};
+
+/// One cookie from a Netscape `cookies.txt` file, the format browser extensions and `curl`
+/// export.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cookie {
+    pub domain: String,
+    pub include_subdomains: bool,
+    pub path: String,
+    pub secure: bool,
+    /// Unix time; `0` for a session cookie.
+    pub expires: i64,
+    pub name: String,
+    pub value: String,
+}
+
+impl Cookie {
+    fn applies_to(&self, url: &Url, now: i64) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let domain = self.domain.trim_start_matches('.');
+        let domain_matches = host.eq_ignore_ascii_case(domain)
+            || ((self.include_subdomains || self.domain.starts_with('.'))
+                && host
+                    .to_lowercase()
+                    .ends_with(&format!(".{}", domain.to_lowercase())));
+
+        domain_matches
+            && url.path().starts_with(&self.path)
+            && (!self.secure || url.scheme() == "https")
+            && (self.expires == 0 || self.expires > now)
+    }
+}
+
+/// Parse a `cookies.txt` file: one cookie per line as seven tab-separated fields. Comments and
+/// malformed lines are skipped, except that `#HttpOnly_` marks a cookie rather than a comment.
+pub fn parse_cookies_txt(text: &str) -> Vec<Cookie> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim_end_matches('\r');
+            let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+            if line.trim().is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [domain, include_subdomains, path, secure, expires, name, value] = fields[..]
+            else {
+                return None;
+            };
+            Some(Cookie {
+                domain: domain.trim().to_lowercase(),
+                include_subdomains: include_subdomains.eq_ignore_ascii_case("true"),
+                path: path.to_string(),
+                secure: secure.eq_ignore_ascii_case("true"),
+                expires: expires.trim().parse().unwrap_or(0),
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A saved fetch profile. Cookie values are never sent to the frontend, only their count.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DomainProfile {
+    pub id: i32,
+    /// Applies to this host and all its subdomains.
+    pub domain: String,
+    pub headers: HashMap<String, String>,
+    #[serde(skip)]
+    pub cookies: Vec<Cookie>,
+    pub cookie_count: usize,
+    pub proxy_url: Option<String>,
+    pub user_agent: Option<String>,
+    pub max_response_bytes: Option<usize>,
+    pub accept_invalid_certs: bool,
+    pub root_certificate_path: Option<String>,
+}
+
+impl DomainProfile {
+    fn from_model(model: FetchProfileModel) -> Self {
+        let cookies: Vec<Cookie> = model
+            .cookies
+            .as_deref()
+            .and_then(|cookies| serde_json::from_str(cookies).ok())
+            .unwrap_or_default();
+        Self {
+            id: model.id,
+            domain: model.domain,
+            headers: model
+                .headers
+                .as_deref()
+                .and_then(|headers| serde_json::from_str(headers).ok())
+                .unwrap_or_default(),
+            cookie_count: cookies.len(),
+            cookies,
+            proxy_url: model.proxy_url,
+            user_agent: model.user_agent,
+            max_response_bytes: model
+                .max_response_bytes
+                .and_then(|bytes| usize::try_from(bytes).ok()),
+            accept_invalid_certs: model.accept_invalid_certs,
+            root_certificate_path: model.root_certificate_path,
+        }
+    }
+
+    pub fn matches(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        host == self.domain || host.ends_with(&format!(".{}", self.domain))
+    }
+
+    /// Build the HTTP client this profile describes. Fails on anything reqwest won't take,
+    /// which is also how profiles are validated before they're saved.
+    pub fn client(&self) -> Result<Client, BookmarkError> {
+        let invalid = |message: String| BookmarkError::Profile(message);
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.trim().as_bytes())
+                .map_err(|_| invalid(format!("invalid header name \"{}\"", name)))?;
+            let value = HeaderValue::from_str(value.trim())
+                .map_err(|_| invalid(format!("invalid value for header \"{}\"", name)))?;
+            headers.insert(name, value);
+        }
+
+        let mut builder = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(self.user_agent.as_deref().unwrap_or(USER_AGENT))
+            .default_headers(headers)
+            .danger_accept_invalid_certs(self.accept_invalid_certs);
+        if let Some(proxy_url) = &self.proxy_url {
+            let proxy =
+                Proxy::all(proxy_url).map_err(|e| invalid(format!("invalid proxy URL: {}", e)))?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &self.root_certificate_path {
+            let pem = std::fs::read(path)
+                .map_err(|e| invalid(format!("can't read certificate {}: {}", path, e)))?;
+            let certificate = Certificate::from_pem(&pem)
+                .map_err(|e| invalid(format!("invalid certificate {}: {}", path, e)))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        builder.build().map_err(BookmarkError::Network)
+    }
+
+    /// The `Cookie` header to send with a request for `url`, if any cookie applies.
+    pub fn cookie_header(&self, url: &Url) -> Option<String> {
+        let now = Utc::now().timestamp();
+        let pairs = self
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.applies_to(url, now))
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<_>>();
+        (!pairs.is_empty()).then(|| pairs.join("; "))
+    }
+}
+
+/// A profile as edited in the settings; cookies are imported separately.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DomainProfileRequest {
+    pub domain: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub proxy_url: Option<String>,
+    pub user_agent: Option<String>,
+    pub max_response_bytes: Option<usize>,
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    pub root_certificate_path: Option<String>,
+}
+
+/// Lowercase `domain` and make sure it is a bare host name.
+fn normalize_domain(domain: &str) -> Result<String, BookmarkError> {
+    let domain = domain.trim().trim_start_matches('.').to_lowercase();
+    let host = Url::parse(&format!("https://{}/", domain))
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string));
+    match host {
+        Some(host) if host == domain => Ok(domain),
+        _ => Err(BookmarkError::Profile(format!(
+            "\"{}\" is not a domain name",
+            domain
+        ))),
+    }
+}
+
+fn blank_to_none(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Every saved profile, most specific domain first so `BookmarkProcessor::profile_for` can
+/// take the first match.
+pub async fn load(db: &DatabaseConnection) -> Result<Vec<DomainProfile>, DbErr> {
+    let mut profiles = FetchProfile::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(DomainProfile::from_model)
+        .collect::<Vec<_>>();
+    profiles.sort_by_key(|profile| std::cmp::Reverse(profile.domain.len()));
+    Ok(profiles)
+}
+
+/// Create or update the profile for `request.domain`, keeping any imported cookies. The
+/// profile is checked by building its client first, so a bad proxy URL is reported now rather
+/// than on every fetch.
+pub async fn save(
+    db: &DatabaseConnection,
+    request: DomainProfileRequest,
+) -> Result<DomainProfile, String> {
+    let domain = normalize_domain(&request.domain).map_err(|e| e.to_string())?;
+    let headers = request
+        .headers
+        .into_iter()
+        .filter(|(name, _)| !name.trim().is_empty())
+        .collect::<HashMap<_, _>>();
+    let candidate = DomainProfile {
+        domain: domain.clone(),
+        headers: headers.clone(),
+        proxy_url: blank_to_none(request.proxy_url),
+        user_agent: blank_to_none(request.user_agent),
+        max_response_bytes: request.max_response_bytes,
+        accept_invalid_certs: request.accept_invalid_certs,
+        root_certificate_path: blank_to_none(request.root_certificate_path),
+        ..Default::default()
+    };
+    candidate.client().map_err(|e| e.to_string())?;
+
+    let existing = find(db, &domain).await.map_err(|e| e.to_string())?;
+    let mut active: FetchProfileActiveModel = match existing {
+        Some(existing) => existing.into(),
+        None => FetchProfileActiveModel {
+            domain: Set(domain),
+            ..Default::default()
+        },
+    };
+    active.headers =
+        Set((!headers.is_empty()).then(|| serde_json::to_string(&headers).unwrap_or_default()));
+    active.proxy_url = Set(candidate.proxy_url);
+    active.user_agent = Set(candidate.user_agent);
+    active.max_response_bytes = Set(candidate
+        .max_response_bytes
+        .map(|bytes| i64::try_from(bytes).unwrap_or(i64::MAX)));
+    active.accept_invalid_certs = Set(candidate.accept_invalid_certs);
+    active.root_certificate_path = Set(candidate.root_certificate_path);
+    active.updated_at = Set(Utc::now().naive_utc());
+
+    let model = active.save(db).await.map_err(|e| e.to_string())?;
+    let model = model.try_into_model().map_err(|e| e.to_string())?;
+    Ok(DomainProfile::from_model(model))
+}
+
+/// Replace the cookies of `domain`'s profile, creating the profile if needed, with those in a
+/// `cookies.txt` export that apply to it. Cookies for other sites in the file are ignored.
+pub async fn import_cookies(
+    db: &DatabaseConnection,
+    domain: &str,
+    cookies_txt: &str,
+) -> Result<DomainProfile, String> {
+    let domain = normalize_domain(domain).map_err(|e| e.to_string())?;
+    let cookies = parse_cookies_txt(cookies_txt)
+        .into_iter()
+        .filter(|cookie| {
+            let cookie_domain = cookie.domain.trim_start_matches('.');
+            cookie_domain == domain
+                || cookie_domain.ends_with(&format!(".{}", domain))
+                || domain.ends_with(&format!(".{}", cookie_domain))
+        })
+        .collect::<Vec<_>>();
+
+    let mut active: FetchProfileActiveModel =
+        match find(db, &domain).await.map_err(|e| e.to_string())? {
+            Some(existing) => existing.into(),
+            None => FetchProfileActiveModel {
+                domain: Set(domain),
+                ..Default::default()
+            },
+        };
+    active.cookies =
+        Set((!cookies.is_empty()).then(|| serde_json::to_string(&cookies).unwrap_or_default()));
+    active.updated_at = Set(Utc::now().naive_utc());
+
+    let model = active.save(db).await.map_err(|e| e.to_string())?;
+    let model = model.try_into_model().map_err(|e| e.to_string())?;
+    Ok(DomainProfile::from_model(model))
+}
+
+pub async fn delete(db: &DatabaseConnection, id: i32) -> Result<bool, DbErr> {
+    let result = FetchProfile::delete_by_id(id).exec(db).await?;
+    Ok(result.rows_affected > 0)
+}
+
+async fn find(db: &DatabaseConnection, domain: &str) -> Result<Option<FetchProfileModel>, DbErr> {
+    FetchProfile::find()
+        .filter(fetch_profile::Column::Domain.eq(domain))
+        .one(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_database;
+
+    const COOKIES_TXT: &str = "# Netscape HTTP Cookie File\n\
+        # This is a generated file! Do not edit.\n\
+        \n\
+        .example.com\tTRUE\t/\tTRUE\t0\tsession\tabc123\n\
+        #HttpOnly_news.example.com\tFALSE\t/members\tFALSE\t4102444800\tmember\tyes\n\
+        expired.example.com\tFALSE\t/\tFALSE\t1000\told\tgone\n\
+        other.org\tFALSE\t/\tFALSE\t0\ttracker\tx\n\
+        not a cookie line\n";
+
+    #[test]
+    fn parses_cookies_txt() {
+        let cookies = parse_cookies_txt(COOKIES_TXT);
+        assert_eq!(
+            cookies.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+            vec!["session", "member", "old", "tracker"]
+        );
+        assert_eq!(cookies[1].domain, "news.example.com");
+        assert_eq!(cookies[1].path, "/members");
+        assert_eq!(cookies[1].expires, 4_102_444_800);
+    }
+
+    #[test]
+    fn sends_cookies_that_apply() {
+        let profile = DomainProfile {
+            domain: "example.com".to_string(),
+            cookies: parse_cookies_txt(COOKIES_TXT),
+            ..Default::default()
+        };
+        let header = |url: &str| profile.cookie_header(&Url::parse(url).expect("url"));
+
+        assert_eq!(
+            header("https://news.example.com/members/feed").as_deref(),
+            Some("session=abc123; member=yes")
+        );
+        // Secure cookies stay off plain HTTP, and paths must match
+        assert_eq!(header("http://news.example.com/").as_deref(), None);
+        assert_eq!(
+            header("https://example.com/").as_deref(),
+            Some("session=abc123")
+        );
+        assert_eq!(
+            header("https://expired.example.com/").as_deref(),
+            Some("session=abc123")
+        );
+
+        assert!(profile.matches("Example.com"));
+        assert!(profile.matches("news.example.com"));
+        assert!(!profile.matches("badexample.com"));
+    }
+
+    #[test]
+    fn rejects_invalid_profiles() {
+        let profile = |edit: fn(&mut DomainProfile)| {
+            let mut profile = DomainProfile {
+                domain: "example.com".to_string(),
+                ..Default::default()
+            };
+            edit(&mut profile);
+            profile.client()
+        };
+        assert!(profile(|_| {}).is_ok());
+        assert!(profile(|p| p.proxy_url = Some("not a url".to_string())).is_err());
+        assert!(profile(|p| {
+            p.headers.insert("Bad Header".to_string(), "x".to_string());
+        })
+        .is_err());
+        assert!(
+            profile(|p| p.root_certificate_path = Some("/nonexistent.pem".to_string())).is_err()
+        );
+        assert!(normalize_domain("https://example.com/path").is_err());
+        assert_eq!(
+            normalize_domain(" .Example.COM ").expect("domain"),
+            "example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn saves_profiles_and_imports_cookies() {
+        let db = test_database("fetch-profiles").await;
+
+        let saved = save(
+            &db,
+            DomainProfileRequest {
+                domain: "Example.com".to_string(),
+                headers: HashMap::from([("X-Token".to_string(), "secret".to_string())]),
+                user_agent: Some("  ".to_string()),
+                max_response_bytes: Some(1024),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("save");
+        assert_eq!(saved.domain, "example.com");
+        assert_eq!(saved.user_agent, None);
+
+        let with_cookies = import_cookies(&db, "example.com", COOKIES_TXT)
+            .await
+            .expect("import cookies");
+        assert_eq!(with_cookies.id, saved.id);
+        assert_eq!(with_cookies.cookie_count, 3);
+        assert_eq!(
+            with_cookies.headers.get("X-Token").map(String::as_str),
+            Some("secret")
+        );
+
+        // Editing the profile keeps its cookies
+        let edited = save(
+            &db,
+            DomainProfileRequest {
+                domain: "example.com".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("edit");
+        assert_eq!(edited.cookie_count, 3);
+        assert!(edited.headers.is_empty());
+
+        import_cookies(&db, "news.example.com", COOKIES_TXT)
+            .await
+            .expect("subdomain profile");
+        let profiles = load(&db).await.expect("load");
+        assert_eq!(profiles[0].domain, "news.example.com");
+        assert_eq!(profiles[1].domain, "example.com");
+
+        assert!(save(
+            &db,
+            DomainProfileRequest {
+                domain: "example.org".to_string(),
+                proxy_url: Some("::".to_string()),
+                ..Default::default()
+            }
+        )
+        .await
+        .is_err());
+        assert!(delete(&db, saved.id).await.expect("delete"));
+        assert_eq!(load(&db).await.expect("load").len(), 1);
+    }
+}
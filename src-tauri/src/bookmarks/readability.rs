@@ -0,0 +1,278 @@
+use scraper::node::Node;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+
+/// Paragraph-ish blocks shorter than this are navigation, captions or boilerplate.
+const MIN_BLOCK_CHARS: usize = 25;
+/// Class or id fragments that mark a container as chrome rather than content.
+const UNLIKELY: &[&str] = &[
+    "advert",
+    "banner",
+    "breadcrumb",
+    "comment",
+    "cookie",
+    "footer",
+    "menu",
+    "nav",
+    "popup",
+    "promo",
+    "related",
+    "share",
+    "sidebar",
+    "social",
+    "sponsor",
+    "subscribe",
+];
+const LIKELY: &[&str] = &[
+    "article", "blog", "body", "content", "entry", "main", "post", "story", "text",
+];
+/// Elements whose contents never belong in the article text.
+const SKIPPED: &[&str] = &[
+    "aside", "button", "footer", "form", "iframe", "nav", "noscript", "script", "select", "style",
+    "svg", "template",
+];
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Article {
+    /// Plain text, one paragraph per line pair.
+    pub text: String,
+    pub word_count: usize,
+}
+
+/// Find the main content of a page the way reader modes do: score containers by the
+/// paragraphs they hold, penalise link-heavy and chrome-looking ones, and keep the text of the
+/// winner.
+pub fn extract(html: &str) -> Option<Article> {
+    let document = Html::parse_document(html);
+    let root = best_candidate(&document).or_else(|| first(&document, "body"))?;
+
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+    collect_text(root, &mut paragraphs, &mut current);
+    flush(&mut paragraphs, &mut current);
+
+    let text = paragraphs.join("\n\n");
+    if text.is_empty() {
+        return None;
+    }
+    let word_count = text.split_whitespace().count();
+    Some(Article { text, word_count })
+}
+
+fn first<'a>(document: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
+    let selector = Selector::parse(selector).expect("valid selector");
+    document.select(&selector).next()
+}
+
+fn best_candidate(document: &Html) -> Option<ElementRef<'_>> {
+    let selector = Selector::parse("p, pre, td, blockquote").expect("valid selector");
+    let mut scores: HashMap<_, f64> = HashMap::new();
+    let mut elements = HashMap::new();
+
+    for block in document.select(&selector) {
+        if is_excluded(block) {
+            continue;
+        }
+        let text = normalized_text(block);
+        let length = text.chars().count();
+        if length < MIN_BLOCK_CHARS {
+            continue;
+        }
+
+        // One point for the block, one per comma, and up to three for its length
+        let score = 1.0 + text.matches(',').count() as f64 + (length / 100).min(3) as f64;
+        let parent = block.parent().and_then(ElementRef::wrap);
+        let grandparent = parent.and_then(|p| p.parent()).and_then(ElementRef::wrap);
+        for (ancestor, share) in [(parent, 1.0), (grandparent, 0.5)] {
+            if let Some(ancestor) = ancestor {
+                *scores.entry(ancestor.id()).or_insert(0.0) += score * share;
+                elements.insert(ancestor.id(), ancestor);
+            }
+        }
+    }
+
+    scores
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let element = elements.get(&id).copied()?;
+            let adjusted = (score + tag_weight(element) + class_weight(element))
+                * (1.0 - link_density(element));
+            Some((element, adjusted))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(element, _)| element)
+}
+
+fn tag_weight(element: ElementRef) -> f64 {
+    match element.value().name() {
+        "article" | "main" => 10.0,
+        "div" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "ol" | "ul" | "dl" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    }
+}
+
+fn class_weight(element: ElementRef) -> f64 {
+    let names = class_and_id(element);
+    let mut weight = 0.0;
+    if UNLIKELY.iter().any(|word| names.contains(word)) {
+        weight -= 25.0;
+    }
+    if LIKELY.iter().any(|word| names.contains(word)) {
+        weight += 25.0;
+    }
+    weight
+}
+
+fn class_and_id(element: ElementRef) -> String {
+    let value = element.value();
+    format!(
+        "{} {}",
+        value.attr("class").unwrap_or_default(),
+        value.attr("id").unwrap_or_default()
+    )
+    .to_ascii_lowercase()
+}
+
+/// Share of the element's text that sits inside links.
+fn link_density(element: ElementRef) -> f64 {
+    let total = normalized_text(element).chars().count();
+    if total == 0 {
+        return 0.0;
+    }
+    let selector = Selector::parse("a").expect("valid selector");
+    let linked: usize = element
+        .select(&selector)
+        .map(|link| normalized_text(link).chars().count())
+        .sum();
+    (linked as f64 / total as f64).min(1.0)
+}
+
+/// Whether the block sits in something we never treat as content.
+fn is_excluded(element: ElementRef) -> bool {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| SKIPPED.contains(&ancestor.value().name()) || is_unlikely(ancestor))
+}
+
+fn is_unlikely(element: ElementRef) -> bool {
+    let names = class_and_id(element);
+    UNLIKELY.iter().any(|word| names.contains(word))
+        && !LIKELY.iter().any(|word| names.contains(word))
+        && !matches!(element.value().name(), "body" | "article" | "main")
+}
+
+fn normalized_text(element: ElementRef) -> String {
+    element
+        .text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn collect_text(element: ElementRef, paragraphs: &mut Vec<String>, current: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => current.push_str(text),
+            Node::Element(value) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                let name = value.name();
+                if SKIPPED.contains(&name) || is_unlikely(child) {
+                    continue;
+                }
+                let block = BLOCKS.contains(&name);
+                if block {
+                    flush(paragraphs, current);
+                }
+                collect_text(child, paragraphs, current);
+                if block {
+                    flush(paragraphs, current);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn flush(paragraphs: &mut Vec<String>, current: &mut String) {
+    let paragraph = current.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+    current.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/pages")
+            .join(name);
+        std::fs::read_to_string(&path).expect("fixture")
+    }
+
+    #[test]
+    fn extracts_the_article_body() {
+        let article = extract(&fixture("readable.html")).expect("article");
+
+        assert!(article.text.starts_with("Sourdough Without the Fuss"));
+        assert!(article
+            .text
+            .contains("Feed the starter once a day, at roughly the same time"));
+        assert!(article.text.contains("Bake at 250°C for twenty minutes"));
+        // Navigation, sidebars, comments and scripts are left behind
+        for chrome in [
+            "Home",
+            "Subscribe to our newsletter",
+            "Great post!",
+            "trackPageView",
+            "Related recipes",
+        ] {
+            assert!(!article.text.contains(chrome), "kept {:?}", chrome);
+        }
+        assert!(article.text.contains("\n\n"));
+        assert_eq!(article.word_count, article.text.split_whitespace().count());
+    }
+
+    #[test]
+    fn empty_page_has_no_article() {
+        assert_eq!(extract("<html><body><nav>Home</nav></body></html>"), None);
+    }
+}
@@ -0,0 +1,256 @@
+use base64::Engine;
+use lol_html::html_content::ContentType;
+use lol_html::{element, rewrite_str, RewriteStrSettings};
+use scraper::{Html, Selector};
+use std::collections::HashMap;
+
+/// Stylesheets larger than this are left out of the snapshot.
+pub const MAX_STYLESHEET_BYTES: usize = 2 * 1024 * 1024;
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+/// Total budget for everything inlined into one snapshot.
+pub const MAX_SNAPSHOT_BYTES: usize = 25 * 1024 * 1024;
+
+/// Blocks every network request from the saved page, so opening it offline neither hangs on
+/// missing resources nor phones home.
+const OFFLINE_POLICY: &str = "default-src 'none'; img-src data:; style-src 'unsafe-inline'; \
+                              font-src data:; media-src data:";
+
+/// Size of `bytes` once base64-encoded into a data URL.
+pub fn encoded_len(bytes: usize) -> usize {
+    bytes.div_ceil(3) * 4
+}
+
+/// The largest image whose encoding still fits in `budget`.
+pub fn image_limit(budget: usize) -> usize {
+    MAX_IMAGE_BYTES.min(budget / 4 * 3)
+}
+
+/// Absolute URLs of the resources a snapshot wants to inline, in document order.
+#[derive(Debug, Default, PartialEq)]
+pub struct Resources {
+    pub stylesheets: Vec<String>,
+    pub images: Vec<String>,
+}
+
+/// A fetched resource, ready to be embedded.
+pub enum Inlined {
+    Stylesheet(String),
+    Image { mime_type: String, bytes: Vec<u8> },
+}
+
+pub fn resources(page_url: &str, html: &str) -> Resources {
+    let document = Html::parse_document(html);
+    let base = reqwest::Url::parse(page_url).ok();
+    let collect = |selector: &str, attr: &str| {
+        let selector = Selector::parse(selector).expect("valid selector");
+        let mut urls = Vec::new();
+        for element in document.select(&selector) {
+            let Some(url) = element
+                .value()
+                .attr(attr)
+                .and_then(|value| absolute(base.as_ref(), value))
+            else {
+                continue;
+            };
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        urls
+    };
+
+    Resources {
+        stylesheets: collect("link[rel~=stylesheet][href]", "href"),
+        images: collect("img[src]", "src"),
+    }
+}
+
+/// Rewrite the page into a single self-contained HTML document: scripts and event handlers
+/// are dropped, fetched stylesheets become `<style>` blocks, fetched images become `data:`
+/// URIs and everything else is made absolute so links still point somewhere sensible.
+pub fn render(
+    page_url: &str,
+    html: &str,
+    inlined: &HashMap<String, Inlined>,
+) -> Result<String, String> {
+    let base = reqwest::Url::parse(page_url).ok();
+    let base = base.as_ref();
+
+    let settings = RewriteStrSettings::new()
+        .append_element_content_handler(element!("script, noscript, iframe, object, embed, base", |el| {
+            el.remove();
+            Ok(())
+        }))
+        .append_element_content_handler(element!(
+            "link[rel~=preload], link[rel~=modulepreload], link[rel~=prefetch], link[rel~=manifest]",
+            |el| {
+                el.remove();
+                Ok(())
+            }
+        ))
+        .append_element_content_handler(element!("meta[http-equiv]", |el| {
+            // A refresh would navigate away from the snapshot; an old CSP would fight ours
+            el.remove();
+            Ok(())
+        }))
+        .append_element_content_handler(element!("*", |el| {
+            let handlers = el
+                .attributes()
+                .iter()
+                .map(|attr| attr.name())
+                .filter(|name| name.starts_with("on"))
+                .collect::<Vec<_>>();
+            for name in handlers {
+                el.remove_attribute(&name);
+            }
+            Ok(())
+        }))
+        .append_element_content_handler(element!("head", |el| {
+            el.prepend(
+                &format!(
+                    "<meta charset=\"utf-8\"><meta http-equiv=\"Content-Security-Policy\" content=\"{}\">",
+                    OFFLINE_POLICY
+                ),
+                ContentType::Html,
+            );
+            Ok(())
+        }))
+        .append_element_content_handler(element!("link[rel~=stylesheet][href]", |el| {
+            let href = el.get_attribute("href").unwrap_or_default();
+            match absolute(base, &href).and_then(|url| inlined.get(&url)) {
+                Some(Inlined::Stylesheet(css)) => {
+                    let media = el
+                        .get_attribute("media")
+                        .map(|media| format!(" media=\"{}\"", escape_attribute(&media)))
+                        .unwrap_or_default();
+                    el.replace(
+                        &format!("<style{}>{}</style>", media, escape_style(css)),
+                        ContentType::Html,
+                    );
+                }
+                _ => el.remove(),
+            }
+            Ok(())
+        }))
+        .append_element_content_handler(element!("img", |el| {
+            el.remove_attribute("srcset");
+            el.remove_attribute("loading");
+            let src = el.get_attribute("src").unwrap_or_default();
+            if let Some(Inlined::Image { mime_type, bytes }) =
+                absolute(base, &src).and_then(|url| inlined.get(&url))
+            {
+                let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+                el.set_attribute("src", &format!("data:{};base64,{}", mime_type, data))?;
+            }
+            Ok(())
+        }))
+        .append_element_content_handler(element!("source[srcset]", |el| {
+            el.remove();
+            Ok(())
+        }))
+        .append_element_content_handler(element!("a[href]", |el| {
+            let href = el.get_attribute("href").unwrap_or_default();
+            if !href.starts_with('#') {
+                if let Some(url) = absolute(base, &href) {
+                    el.set_attribute("href", &url)?;
+                }
+            }
+            Ok(())
+        }));
+
+    rewrite_str(html, settings.with_strict(false)).map_err(|e| e.to_string())
+}
+
+fn absolute(base: Option<&reqwest::Url>, value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() || value.starts_with("data:") {
+        return None;
+    }
+    let url = match base {
+        Some(base) => base.join(value).ok()?,
+        None => reqwest::Url::parse(value).ok()?,
+    };
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
+/// Keep a stylesheet from closing its own `<style>` element early.
+fn escape_style(css: &str) -> String {
+    css.replace("</style", "<\\/style")
+        .replace("</STYLE", "<\\/STYLE")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE_URL: &str = "https://example.com/recipes/sourdough";
+
+    fn fixture() -> String {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/pages/readable.html");
+        std::fs::read_to_string(path).expect("fixture")
+    }
+
+    #[test]
+    fn images_within_the_limit_fit_the_budget() {
+        for budget in 0..64 {
+            assert!(encoded_len(image_limit(budget)) <= budget);
+        }
+        assert_eq!(image_limit(2), 0);
+        assert_eq!(image_limit(8), 6);
+        assert_eq!(image_limit(usize::MAX), MAX_IMAGE_BYTES);
+    }
+
+    #[test]
+    fn lists_resources_as_absolute_urls() {
+        assert_eq!(
+            resources(PAGE_URL, &fixture()),
+            Resources {
+                stylesheets: vec!["https://example.com/css/site.css".to_string()],
+                images: vec!["https://example.com/img/loaf.jpg".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn inlines_fetched_resources_and_strips_scripts() {
+        let inlined = HashMap::from([
+            (
+                "https://example.com/css/site.css".to_string(),
+                Inlined::Stylesheet("h1 { color: brown; }".to_string()),
+            ),
+            (
+                "https://example.com/img/loaf.jpg".to_string(),
+                Inlined::Image {
+                    mime_type: "image/jpeg".to_string(),
+                    bytes: vec![0xff, 0xd8, 0xff],
+                },
+            ),
+        ]);
+
+        let snapshot = render(PAGE_URL, &fixture(), &inlined).expect("render");
+
+        assert!(snapshot.contains("<style>h1 { color: brown; }</style>"));
+        assert!(snapshot.contains("src=\"data:image/jpeg;base64,/9j/\""));
+        assert!(snapshot.contains("Content-Security-Policy"));
+        assert!(snapshot.contains("href=\"https://example.com/tags/bread\""));
+        assert!(!snapshot.contains("<script"));
+        assert!(!snapshot.contains("trackPageView"));
+        assert!(!snapshot.contains("/css/site.css"));
+    }
+
+    #[test]
+    fn missing_resources_are_dropped_or_left_absolute() {
+        let snapshot = render(PAGE_URL, &fixture(), &HashMap::new()).expect("render");
+
+        assert!(!snapshot.contains("rel=\"stylesheet\""));
+        assert!(snapshot.contains("src=\"/img/loaf.jpg\""));
+    }
+}
@@ -0,0 +1,712 @@
+//! What a page says it is about in machine-readable form: schema.org JSON-LD and microdata,
+//! Highwire `citation_*` tags on paper landing pages, and the oEmbed endpoint it advertises.
+//! From these a bookmark gets a subtype and the fields that matter for it, like a recipe's
+//! ingredients or a product's price.
+
+use scraper::{ElementRef, Html};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::html::{resolve, select};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Subtype {
+    Article,
+    Video,
+    Product,
+    Recipe,
+    Event,
+    CodeRepository,
+    ScholarlyArticle,
+}
+
+impl Subtype {
+    fn from_schema_type(name: &str) -> Option<Self> {
+        match name {
+            "Recipe" => Some(Self::Recipe),
+            "Product" | "ProductGroup" | "ProductModel" | "IndividualProduct" => {
+                Some(Self::Product)
+            }
+            "VideoObject" | "Movie" | "TVEpisode" | "Clip" => Some(Self::Video),
+            "SoftwareSourceCode" => Some(Self::CodeRepository),
+            "ScholarlyArticle" | "MedicalScholarlyArticle" => Some(Self::ScholarlyArticle),
+            "Article"
+            | "NewsArticle"
+            | "BlogPosting"
+            | "TechArticle"
+            | "Report"
+            | "LiveBlogPosting"
+            | "OpinionNewsArticle"
+            | "AnalysisNewsArticle"
+            | "ReportageNewsArticle"
+            | "SocialMediaPosting"
+            | "DiscussionForumPosting" => Some(Self::Article),
+            // MusicEvent, SportsEvent, BusinessEvent and the rest
+            name if name.ends_with("Event") => Some(Self::Event),
+            _ => None,
+        }
+    }
+
+    /// When a page declares several things, the lowest value is what it is about. Specific
+    /// kinds beat articles, since recipe and product pages usually declare both, but a video
+    /// embedded in an article doesn't make it a video page.
+    fn priority(self) -> u8 {
+        match self {
+            Self::ScholarlyArticle => 0,
+            Self::Recipe => 1,
+            Self::Product => 2,
+            Self::Event => 3,
+            Self::CodeRepository => 4,
+            Self::Article => 5,
+            Self::Video => 6,
+        }
+    }
+}
+
+/// Typed fields from the page's main structured-data entity. Durations and dates are kept as
+/// the ISO 8601 strings the page gave.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StructuredFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_published: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_currency: Option<String>,
+    /// E.g. `InStock`, without the schema.org URL prefix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub availability: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brand: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating_count: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embed_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ingredients: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instructions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipe_yield: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prep_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cook_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doi: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub journal: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub programming_language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_repository: Option<String>,
+}
+
+impl StructuredFields {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The parts of an oEmbed response worth keeping; `html` is the provider's embed code.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OEmbed {
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StructuredData {
+    pub subtype: Option<Subtype>,
+    pub fields: StructuredFields,
+    /// JSON oEmbed endpoint from the page's discovery link.
+    pub oembed_url: Option<String>,
+}
+
+/// Hosts whose `/owner/name` pages are source repositories.
+const CODE_HOSTS: &[&str] = &["github.com", "gitlab.com", "codeberg.org", "bitbucket.org"];
+/// First path segments on those hosts that are site pages rather than owners.
+const CODE_HOST_PAGES: &[&str] = &[
+    "about",
+    "explore",
+    "features",
+    "marketplace",
+    "orgs",
+    "settings",
+    "topics",
+    "users",
+];
+
+/// Read the structured data in `html` fetched from `page_url`. `og_type` is the page's
+/// OpenGraph type, the fallback when nothing more specific is declared.
+pub fn extract(page_url: &str, html: &str, og_type: Option<&str>) -> StructuredData {
+    let document = Html::parse_document(html);
+    let base = reqwest::Url::parse(page_url).ok();
+
+    let mut nodes = json_ld(&document);
+    nodes.extend(microdata(&document, base.as_ref()));
+    let main = nodes
+        .iter()
+        .filter_map(|node| Some((node_subtype(node)?, node)))
+        .min_by_key(|(subtype, _)| subtype.priority());
+
+    let mut data = StructuredData {
+        subtype: main.map(|(subtype, _)| subtype),
+        fields: main.map(|(_, node)| fields(node)).unwrap_or_default(),
+        oembed_url: select(&document, "link[type='application/json+oembed'][href]")
+            .first()
+            .and_then(|link| link.value().attr("href"))
+            .map(|href| resolve(base.as_ref(), href.trim())),
+    };
+
+    apply_citation_tags(&document, &mut data);
+
+    if data.subtype.is_none() {
+        data.subtype = match og_type.map(str::to_ascii_lowercase).as_deref() {
+            Some(kind) if kind.starts_with("video") => Some(Subtype::Video),
+            Some("article") => Some(Subtype::Article),
+            Some("product" | "og:product") => Some(Subtype::Product),
+            _ => None,
+        };
+    }
+    if data.subtype.is_none() && base.as_ref().is_some_and(is_code_repository) {
+        data.subtype = Some(Subtype::CodeRepository);
+        data.fields.code_repository = Some(page_url.to_string());
+    }
+    data
+}
+
+/// Scholarly publishers mark up landing pages with Highwire Press `citation_*` tags, which
+/// Google Scholar reads, more often than with schema.org.
+fn apply_citation_tags(document: &Html, data: &mut StructuredData) {
+    let mut doi = None;
+    let mut journal = None;
+    let mut authors = Vec::new();
+    for meta in select(document, "meta[name][content]") {
+        let name = meta
+            .value()
+            .attr("name")
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let content = collapse(meta.value().attr("content").unwrap_or_default());
+        if content.is_empty() {
+            continue;
+        }
+        match name.as_str() {
+            "citation_doi" | "dc.identifier" if doi.is_none() => doi = parse_doi(&content),
+            "citation_journal_title" if journal.is_none() => journal = Some(content),
+            "citation_author" => authors.push(content),
+            _ => {}
+        }
+    }
+
+    if doi.is_some()
+        && data
+            .subtype
+            .is_none_or(|subtype| subtype == Subtype::Article)
+    {
+        data.subtype = Some(Subtype::ScholarlyArticle);
+    }
+    if data.subtype == Some(Subtype::ScholarlyArticle) {
+        data.fields.doi = data.fields.doi.take().or(doi);
+        data.fields.journal = data.fields.journal.take().or(journal);
+        if data.fields.authors.is_empty() {
+            data.fields.authors = authors;
+        }
+    }
+}
+
+fn is_code_repository(url: &reqwest::Url) -> bool {
+    let host = url
+        .host_str()
+        .unwrap_or_default()
+        .trim_start_matches("www.");
+    let segments = url
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    CODE_HOSTS.contains(&host) && segments.len() == 2 && !CODE_HOST_PAGES.contains(&segments[0])
+}
+
+/// Top-level JSON-LD nodes, with `@graph` containers and arrays unwrapped.
+fn json_ld(document: &Html) -> Vec<Value> {
+    let mut nodes = Vec::new();
+    for script in select(document, "script[type='application/ld+json']") {
+        let text = script.text().collect::<String>();
+        // Invalid blocks are common enough that one shouldn't spoil the rest
+        let Ok(value) = serde_json::from_str::<Value>(text.trim()) else {
+            continue;
+        };
+        unwrap_graph(value, &mut nodes);
+    }
+    nodes
+}
+
+fn unwrap_graph(value: Value, nodes: &mut Vec<Value>) {
+    match value {
+        Value::Array(values) => values
+            .into_iter()
+            .for_each(|value| unwrap_graph(value, nodes)),
+        Value::Object(mut object) => match object.remove("@graph") {
+            Some(graph) => unwrap_graph(graph, nodes),
+            None => nodes.push(Value::Object(object)),
+        },
+        _ => {}
+    }
+}
+
+/// Top-level microdata items as JSON-LD-shaped objects, so both are read the same way.
+fn microdata(document: &Html, base: Option<&reqwest::Url>) -> Vec<Value> {
+    select(document, "[itemscope][itemtype]:not([itemprop])")
+        .into_iter()
+        .map(|scope| microdata_item(scope, base))
+        .collect()
+}
+
+fn microdata_item(scope: ElementRef, base: Option<&reqwest::Url>) -> Value {
+    let mut object = Map::new();
+    if let Some(item_type) = scope
+        .value()
+        .attr("itemtype")
+        .and_then(|types| types.split_whitespace().next())
+    {
+        object.insert("@type".to_string(), Value::String(item_type.to_string()));
+    }
+
+    for property in properties(scope) {
+        let value = if property.value().attr("itemscope").is_some() {
+            microdata_item(property, base)
+        } else {
+            Value::String(property_value(property, base))
+        };
+        let names = property.value().attr("itemprop").unwrap_or_default();
+        for name in names.split_whitespace() {
+            match object.get_mut(name) {
+                Some(Value::Array(values)) => values.push(value.clone()),
+                Some(existing) => *existing = Value::Array(vec![existing.clone(), value.clone()]),
+                None => {
+                    object.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+    }
+    Value::Object(object)
+}
+
+/// Elements carrying properties of `scope`: descendants with `itemprop`, not looking inside
+/// nested items, whose properties are their own.
+fn properties(scope: ElementRef) -> Vec<ElementRef> {
+    let mut found = Vec::new();
+    let mut pending = scope
+        .children()
+        .filter_map(ElementRef::wrap)
+        .collect::<Vec<_>>();
+    pending.reverse();
+    while let Some(element) = pending.pop() {
+        if element.value().attr("itemprop").is_some() {
+            found.push(element);
+        }
+        if element.value().attr("itemscope").is_none() {
+            let mut children = element
+                .children()
+                .filter_map(ElementRef::wrap)
+                .collect::<Vec<_>>();
+            children.reverse();
+            pending.extend(children);
+        }
+    }
+    found
+}
+
+fn property_value(element: ElementRef, base: Option<&reqwest::Url>) -> String {
+    let value = element.value();
+    if let Some(content) = value.attr("content") {
+        return collapse(content);
+    }
+    let url_attr = match value.name() {
+        "a" | "area" | "link" => Some("href"),
+        "img" | "audio" | "video" | "source" | "embed" | "iframe" | "track" => Some("src"),
+        "object" => Some("data"),
+        _ => None,
+    };
+    if let Some(url) = url_attr.and_then(|attr| value.attr(attr)) {
+        return resolve(base, url.trim());
+    }
+    let attr = match value.name() {
+        "time" => value.attr("datetime"),
+        "data" | "meter" => value.attr("value"),
+        _ => None,
+    };
+    attr.map(collapse)
+        .unwrap_or_else(|| collapse(&element.text().collect::<String>()))
+}
+
+/// The subtype of a node, from its `@type` or any of them when it has several.
+fn node_subtype(node: &Value) -> Option<Subtype> {
+    let types = match node.get("@type")? {
+        Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+        Value::String(name) => vec![name.as_str()],
+        _ => Vec::new(),
+    };
+    types
+        .into_iter()
+        .filter_map(|name| {
+            // `http://schema.org/Recipe` in microdata, sometimes `schema:Recipe` in JSON-LD
+            let name = name.rsplit(['/', ':']).next().unwrap_or(name);
+            Subtype::from_schema_type(name)
+        })
+        .min_by_key(|subtype| subtype.priority())
+}
+
+fn fields(node: &Value) -> StructuredFields {
+    let get = |key: &str| node.get(key);
+    let offer = get("offers").map(first);
+    let rating = get("aggregateRating");
+
+    StructuredFields {
+        name: get("headline").or(get("name")).and_then(text),
+        authors: get("author").map(texts).unwrap_or_default(),
+        date_published: get("datePublished").and_then(text),
+        price: offer
+            .and_then(|offer| offer.get("price").or(offer.get("lowPrice")))
+            .and_then(text),
+        price_currency: offer
+            .and_then(|offer| offer.get("priceCurrency"))
+            .and_then(text),
+        availability: offer
+            .and_then(|offer| offer.get("availability"))
+            .and_then(text)
+            .map(|value| value.rsplit('/').next().unwrap_or(&value).to_string()),
+        brand: get("brand").and_then(text),
+        rating: rating
+            .and_then(|rating| rating.get("ratingValue"))
+            .and_then(text),
+        rating_count: rating
+            .and_then(|rating| rating.get("ratingCount").or(rating.get("reviewCount")))
+            .and_then(text),
+        duration: get("duration").and_then(text),
+        upload_date: get("uploadDate").and_then(text),
+        embed_url: get("embedUrl").and_then(url),
+        thumbnail_url: get("thumbnailUrl").and_then(url),
+        ingredients: get("recipeIngredient")
+            .or(get("ingredients"))
+            .map(texts)
+            .unwrap_or_default(),
+        instructions: get("recipeInstructions").map(steps).unwrap_or_default(),
+        recipe_yield: get("recipeYield").and_then(text),
+        prep_time: get("prepTime").and_then(text),
+        cook_time: get("cookTime").and_then(text),
+        total_time: get("totalTime").and_then(text),
+        start_date: get("startDate").and_then(text),
+        end_date: get("endDate").and_then(text),
+        location: get("location").and_then(location),
+        doi: get("doi")
+            .into_iter()
+            .chain(get("identifier"))
+            .chain(get("sameAs"))
+            .find_map(doi),
+        journal: get("isPartOf").and_then(journal),
+        programming_language: get("programmingLanguage").and_then(text),
+        code_repository: get("codeRepository").and_then(url),
+    }
+}
+
+/// The first element of an array, or the value itself.
+fn first(value: &Value) -> &Value {
+    match value {
+        Value::Array(values) => values.first().unwrap_or(value),
+        _ => value,
+    }
+}
+
+/// A value as display text: strings and numbers as they are, things by their name.
+fn text(value: &Value) -> Option<String> {
+    let text = match first(value) {
+        Value::String(text) => collapse(text),
+        Value::Number(number) => number.to_string(),
+        Value::Object(object) => return object.get("name").or(object.get("@value")).and_then(text),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+fn texts(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(values) => values.iter().filter_map(text).collect(),
+        value => text(value).into_iter().collect(),
+    }
+}
+
+fn url(value: &Value) -> Option<String> {
+    match first(value) {
+        Value::Object(object) => object
+            .get("url")
+            .or(object.get("contentUrl"))
+            .or(object.get("@id"))
+            .and_then(text),
+        value => text(value),
+    }
+}
+
+/// Recipe steps: plain strings, `HowToStep`s, or `HowToSection`s of steps.
+fn steps(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(values) => values.iter().flat_map(steps).collect(),
+        Value::Object(object) => match object.get("itemListElement") {
+            Some(items) => steps(items),
+            None => object
+                .get("text")
+                .or(object.get("name"))
+                .and_then(text)
+                .into_iter()
+                .collect(),
+        },
+        // Some sites put every step in one string, one per line
+        Value::String(text) => text
+            .lines()
+            .map(collapse)
+            .filter(|line| !line.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A place's name, or its address spelled out; a virtual location's URL.
+fn location(value: &Value) -> Option<String> {
+    let value = first(value);
+    let Value::Object(place) = value else {
+        return text(value);
+    };
+    if let Some(name) = place.get("name").and_then(text) {
+        return Some(name);
+    }
+    match place.get("address") {
+        Some(Value::Object(address)) => {
+            let parts = [
+                "streetAddress",
+                "addressLocality",
+                "addressRegion",
+                "addressCountry",
+            ]
+            .iter()
+            .filter_map(|key| address.get(*key).and_then(text))
+            .collect::<Vec<_>>();
+            (!parts.is_empty()).then(|| parts.join(", "))
+        }
+        Some(address) => text(address),
+        None => place.get("url").and_then(text),
+    }
+}
+
+/// The periodical an article appeared in, through any issue or volume in between.
+fn journal(value: &Value) -> Option<String> {
+    let value = first(value);
+    match value.get("@type").and_then(Value::as_str) {
+        Some("PublicationIssue" | "PublicationVolume") => value
+            .get("isPartOf")
+            .and_then(journal)
+            .or_else(|| value.get("name").and_then(text)),
+        _ => text(value),
+    }
+}
+
+/// A DOI from a plain string, a `doi.org` URL, or a `PropertyValue` identifier.
+fn doi(value: &Value) -> Option<String> {
+    match value {
+        Value::Array(values) => values.iter().find_map(doi),
+        Value::Object(object) => {
+            let is_doi = object
+                .get("propertyID")
+                .and_then(Value::as_str)
+                .is_some_and(|id| id.eq_ignore_ascii_case("doi"));
+            let value = object.get("value").and_then(text)?;
+            if is_doi {
+                Some(value)
+            } else {
+                parse_doi(&value)
+            }
+        }
+        value => parse_doi(&text(value)?),
+    }
+}
+
+fn parse_doi(value: &str) -> Option<String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix("https://doi.org/")
+        .or_else(|| value.strip_prefix("http://dx.doi.org/"))
+        .or_else(|| value.strip_prefix("https://dx.doi.org/"))
+        .or_else(|| value.strip_prefix("doi:"))
+        .unwrap_or(value);
+    value.starts_with("10.").then(|| value.to_string())
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recipes_beat_the_article_around_them() {
+        let html = r#"<html><head>
+            <script type="application/ld+json">{ not json }</script>
+            <script type="application/ld+json">
+            {"@context": "https://schema.org", "@graph": [
+                {"@type": "WebSite", "name": "Cooking"},
+                {"@type": "BlogPosting", "headline": "My week"},
+                {"@type": "Recipe", "name": "Pancakes",
+                 "author": {"@type": "Person", "name": "Ada"},
+                 "recipeIngredient": ["2 eggs", "200 g  flour"],
+                 "recipeInstructions": [
+                    {"@type": "HowToSection", "itemListElement": [
+                        {"@type": "HowToStep", "text": "Whisk."},
+                        {"@type": "HowToStep", "text": "Fry."}]}],
+                 "recipeYield": 4, "totalTime": "PT20M",
+                 "aggregateRating": {"ratingValue": "4.5", "ratingCount": 12}}
+            ]}
+            </script></head></html>"#;
+        let data = extract("https://food.example/pancakes", html, Some("article"));
+
+        assert_eq!(data.subtype, Some(Subtype::Recipe));
+        assert_eq!(data.fields.name.as_deref(), Some("Pancakes"));
+        assert_eq!(data.fields.authors, vec!["Ada"]);
+        assert_eq!(data.fields.ingredients, vec!["2 eggs", "200 g flour"]);
+        assert_eq!(data.fields.instructions, vec!["Whisk.", "Fry."]);
+        assert_eq!(data.fields.recipe_yield.as_deref(), Some("4"));
+        assert_eq!(data.fields.total_time.as_deref(), Some("PT20M"));
+        assert_eq!(data.fields.rating.as_deref(), Some("4.5"));
+        assert_eq!(data.fields.rating_count.as_deref(), Some("12"));
+    }
+
+    #[test]
+    fn reads_microdata() {
+        let html = r#"<html><body>
+            <div itemscope itemtype="http://schema.org/Product">
+              <h1 itemprop="name">Kettle</h1>
+              <div itemprop="brand" itemscope itemtype="http://schema.org/Brand">
+                <span itemprop="name">Boilco</span>
+              </div>
+              <div itemprop="offers" itemscope itemtype="http://schema.org/Offer">
+                <span itemprop="price" content="39.90">€39,90</span>
+                <meta itemprop="priceCurrency" content="EUR">
+                <link itemprop="availability" href="https://schema.org/InStock">
+              </div>
+            </div></body></html>"#;
+        let data = extract("https://shop.example/kettle", html, None);
+
+        assert_eq!(data.subtype, Some(Subtype::Product));
+        assert_eq!(data.fields.name.as_deref(), Some("Kettle"));
+        assert_eq!(data.fields.brand.as_deref(), Some("Boilco"));
+        assert_eq!(data.fields.price.as_deref(), Some("39.90"));
+        assert_eq!(data.fields.price_currency.as_deref(), Some("EUR"));
+        assert_eq!(data.fields.availability.as_deref(), Some("InStock"));
+    }
+
+    #[test]
+    fn reads_events_and_videos() {
+        let event = r#"<script type="application/ld+json">
+            [{"@type": "MusicEvent", "name": "Gig", "startDate": "2026-11-01T20:00",
+              "location": {"@type": "Place",
+                           "address": {"addressLocality": "Leeds", "addressCountry": "UK"}}},
+             {"@type": "VideoObject", "name": "Teaser"}]</script>"#;
+        let data = extract("https://venue.example/gig", event, None);
+        assert_eq!(data.subtype, Some(Subtype::Event));
+        assert_eq!(data.fields.start_date.as_deref(), Some("2026-11-01T20:00"));
+        assert_eq!(data.fields.location.as_deref(), Some("Leeds, UK"));
+
+        let video = r#"<head><link rel="alternate" type="application/json+oembed"
+                href="/oembed?url=x&amp;format=json">
+            <script type="application/ld+json">
+            {"@type": "VideoObject", "name": "Talk", "duration": "PT1H2M",
+             "uploadDate": "2024-01-02", "thumbnailUrl": ["https://i.example/1.jpg"],
+             "embedUrl": "https://video.example/embed/1"}</script></head>"#;
+        let data = extract("https://video.example/watch/1", video, None);
+        assert_eq!(data.subtype, Some(Subtype::Video));
+        assert_eq!(data.fields.duration.as_deref(), Some("PT1H2M"));
+        assert_eq!(
+            data.fields.thumbnail_url.as_deref(),
+            Some("https://i.example/1.jpg")
+        );
+        assert_eq!(
+            data.oembed_url.as_deref(),
+            Some("https://video.example/oembed?url=x&format=json")
+        );
+    }
+
+    #[test]
+    fn recognizes_papers_and_repositories() {
+        let paper = r#"<head>
+            <meta name="citation_title" content="On Things">
+            <meta name="citation_author" content="Lovelace, Ada">
+            <meta name="citation_author" content="Babbage, Charles">
+            <meta name="citation_journal_title" content="Journal of Things">
+            <meta name="citation_doi" content="doi:10.1234/things.5">
+            <meta property="og:type" content="article"></head>"#;
+        let data = extract("https://journal.example/things", paper, Some("article"));
+        assert_eq!(data.subtype, Some(Subtype::ScholarlyArticle));
+        assert_eq!(data.fields.doi.as_deref(), Some("10.1234/things.5"));
+        assert_eq!(data.fields.journal.as_deref(), Some("Journal of Things"));
+        assert_eq!(
+            data.fields.authors,
+            vec!["Lovelace, Ada", "Babbage, Charles"]
+        );
+
+        let ld = r#"<script type="application/ld+json">
+            {"@type": "ScholarlyArticle", "headline": "Results",
+             "identifier": {"@type": "PropertyValue", "propertyID": "DOI", "value": "10.5555/r"},
+             "isPartOf": {"@type": "PublicationIssue",
+                          "isPartOf": {"@type": "Periodical", "name": "Annals"}}}</script>"#;
+        let data = extract("https://journal.example/results", ld, None);
+        assert_eq!(data.fields.doi.as_deref(), Some("10.5555/r"));
+        assert_eq!(data.fields.journal.as_deref(), Some("Annals"));
+
+        let repo = extract(
+            "https://github.com/rust-lang/rust",
+            "<title>rust</title>",
+            Some("object"),
+        );
+        assert_eq!(repo.subtype, Some(Subtype::CodeRepository));
+        let topics = extract("https://github.com/topics/rust", "", Some("object"));
+        assert_eq!(topics.subtype, None);
+        assert_eq!(
+            extract("https://example.com/", "<p>Hi</p>", None),
+            StructuredData::default()
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Hands out request slots per host at least `interval` apart, so background jobs that fetch
+/// many saved URLs never hammer one site.
+pub struct HostLimiter {
+    interval: Duration,
+    next_slot: Mutex<HashMap<String, Instant>>,
+}
+
+impl HostLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_slot: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for our turn to talk to `host`.
+    pub async fn wait(&self, host: &str) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let now = Instant::now();
+            let slot = next_slot.get(host).copied().unwrap_or(now).max(now);
+            next_slot.insert(host.to_string(), slot + self.interval);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+
+    /// Wait for the host `url` points at; unparseable URLs share one slot.
+    pub async fn wait_for_url(&self, url: &str) {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        self.wait(&host).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn spaces_requests_to_the_same_host() {
+        let limiter = HostLimiter::new(Duration::from_millis(50));
+        let start = Instant::now();
+
+        limiter.wait("a.example").await;
+        limiter.wait("b.example").await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        limiter.wait("a.example").await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+}
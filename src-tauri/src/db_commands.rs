@@ -1,13 +1,34 @@
-use crate::bookmarks::BookmarkProcessor;
+use crate::blobs::{self, StoredBlob};
+use crate::bookmarks::{
+    self, canonicalize, merge_metadata, AssetStore, BookmarkError, BookmarkMetadata,
+    BookmarkProcessor, Capture, DomainProfile, DomainProfileRequest, Download,
+};
 use crate::database::DatabaseState;
-use crate::entities::{Item, ItemActiveModel, ItemModel};
-use crate::files::{FileOperationRequest, FileProcessor};
-use crate::typesense;
-use chrono::Utc;
-use sea_orm::{ActiveModelTrait, EntityTrait, QueryOrder, Set};
+use crate::directory_import::{
+    self, DirectoryImportReport, DirectoryImportRequest, DirectoryImports, FileOutcome,
+};
+use crate::entities::{
+    Feed, FeedModel, Item, ItemActiveModel, ItemModel, WatchedPath, WatchedPathModel,
+};
+use crate::feeds::{self, FeedFetcher, FeedFilters};
+use crate::files::{self, FileOperationRequest, FileProcessor};
+use crate::importers::{self, ImportFormat, ImportReport};
+use crate::indexer::{self, IndexConsistencyReport, IndexOperation, IndexerState};
+use crate::link_checker::{self, LinkCheck, LinkCheckedEvent};
+use crate::linked_files;
+use crate::refresher;
+use crate::search::{self, FacetCount, FullReindexReport, SearchHighlight, SearchQuery};
+use crate::watcher::{self, SyncReport, WatchOperation, WatcherState};
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set, TransactionTrait,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::Emitter;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateItemRequest {
     pub title: String,
     pub content: Option<String>,
@@ -15,6 +36,11 @@ pub struct CreateItemRequest {
     pub tags: Option<String>,
     pub source_type: Option<String>,
     pub source_url: Option<String>,
+    /// When the item was originally saved, for imports; defaults to now.
+    #[serde(default)]
+    pub created_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub metadata: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -28,30 +54,61 @@ pub struct UpdateItemRequest {
     pub source_url: Option<String>,
 }
 
+#[derive(Serialize)]
+pub struct SearchItemHit {
+    pub item: ItemModel,
+    pub highlights: Vec<SearchHighlight>,
+}
+
+#[derive(Serialize)]
+pub struct SearchItemsResponse {
+    pub hits: Vec<SearchItemHit>,
+    pub found: u64,
+    pub page: u32,
+    pub facets: Vec<FacetCount>,
+    /// Which backend answered, so the UI can say when it is on the fallback.
+    pub backend: String,
+}
+
+/// Insert an item and queue it for indexing in a single transaction.
+pub async fn insert_item(
+    db: &DatabaseConnection,
+    item: ItemActiveModel,
+) -> Result<ItemModel, DbErr> {
+    let txn = db.begin().await?;
+    let item = item.insert(&txn).await?;
+    indexer::enqueue(&txn, item.id, IndexOperation::Upsert).await?;
+    txn.commit().await?;
+    Ok(item)
+}
+
 #[tauri::command]
 pub async fn create_item(
     request: CreateItemRequest,
     state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
 ) -> Result<ItemModel, String> {
     let db = state
         .get_connection()
         .await
         .ok_or("Database not connected")?;
 
-    let item = ItemActiveModel {
+    let mut item = ItemActiveModel {
         title: Set(request.title),
         content: Set(request.content),
         item_type: Set(request.item_type),
         tags: Set(request.tags),
         source_type: Set(request.source_type),
         source_url: Set(request.source_url),
+        metadata: Set(request.metadata),
         ..Default::default()
     };
+    if let Some(created_at) = request.created_at {
+        item.created_at = Set(created_at);
+    }
 
-    let item = item.insert(&db).await.map_err(|e| e.to_string())?;
-    typesense::upsert_item_document(&item)
-        .await
-        .map_err(|e| e.to_string())?;
+    let item = insert_item(&db, item).await.map_err(|e| e.to_string())?;
+    indexer.wake();
     Ok(item)
 }
 
@@ -90,26 +147,50 @@ pub async fn get_item_by_id(
 }
 
 #[tauri::command]
-pub async fn delete_item(id: i32, state: tauri::State<'_, DatabaseState>) -> Result<(), String> {
+pub async fn delete_item(
+    id: i32,
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
     let db = state
         .get_connection()
         .await
         .ok_or("Database not connected")?;
 
+    let txn = db.begin().await.map_err(|e| e.to_string())?;
     Item::delete_by_id(id)
-        .exec(&db)
+        .exec(&txn)
         .await
         .map_err(|e| e.to_string())?;
-    typesense::delete_item_document(id)
+    indexer::enqueue(&txn, id, IndexOperation::Delete)
         .await
         .map_err(|e| e.to_string())?;
+    txn.commit().await.map_err(|e| e.to_string())?;
+
+    indexer.wake();
+    collect_blobs(&db, &app_handle).await;
     Ok(())
 }
 
+/// Remove stored files no item uses any more. Failing here only leaves them for next time.
+async fn collect_blobs(db: &DatabaseConnection, app_handle: &tauri::AppHandle) {
+    let collected = match FileProcessor::blob_store(app_handle) {
+        Ok(store) => blobs::collect_garbage(db, &store)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = collected {
+        eprintln!("Failed to remove unused files: {}", e);
+    }
+}
+
 #[tauri::command]
 pub async fn update_item(
     request: UpdateItemRequest,
     state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
 ) -> Result<ItemModel, String> {
     let db = state
         .get_connection()
@@ -122,102 +203,861 @@ pub async fn update_item(
         .map_err(|e| e.to_string())?
         .ok_or("Item not found")?;
 
+    // Keep a captured item's dedup key in step with its URL
+    let canonical_url = match &existing.canonical_url {
+        Some(_) if existing.source_url != request.source_url => {
+            request.source_url.as_deref().and_then(canonicalize)
+        }
+        current => current.clone(),
+    };
+    if canonical_url != existing.canonical_url {
+        if let Some(other) = find_by_canonical_url(&db, canonical_url.as_deref()).await? {
+            return Err(format!("That URL is already saved as item {}", other.id));
+        }
+    }
+
+    // Remember hand edits to a bookmark so metadata refreshes don't undo them
+    let metadata = if existing.item_type == "bookmark" {
+        let edited = [
+            ("title", existing.title != request.title),
+            ("content", existing.content != request.content),
+        ]
+        .into_iter()
+        .filter_map(|(field, changed)| changed.then_some(field))
+        .collect::<Vec<_>>();
+        refresher::mark_edited(existing.metadata.as_deref(), &edited)
+    } else {
+        existing.metadata.clone()
+    };
+
     let mut active: ItemActiveModel = existing.into();
     active.title = Set(request.title);
     active.content = Set(request.content);
+    active.metadata = Set(metadata);
     active.item_type = Set(request.item_type);
     active.tags = Set(request.tags);
     active.source_type = Set(request.source_type);
     active.source_url = Set(request.source_url);
+    active.canonical_url = Set(canonical_url);
     active.updated_at = Set(Utc::now().naive_utc());
 
-    let updated = active.update(&db).await.map_err(|e| e.to_string())?;
-
-    typesense::upsert_item_document(&updated)
+    let txn = db.begin().await.map_err(|e| e.to_string())?;
+    let updated = active.update(&txn).await.map_err(|e| e.to_string())?;
+    indexer::enqueue(&txn, updated.id, IndexOperation::Upsert)
         .await
         .map_err(|e| e.to_string())?;
+    txn.commit().await.map_err(|e| e.to_string())?;
 
+    indexer.wake();
     Ok(updated)
 }
 
+/// What `create_bookmark` made of a URL, tagged with `kind`.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SavedUrl {
+    Item(ItemModel),
+    Feed(FeedModel),
+}
+
+/// Save whatever a URL points at: pages become bookmarks and PDFs, images and other downloads
+/// become file items, with the URL kept in `source_url`. Feeds are subscribed to instead.
 #[tauri::command]
 pub async fn create_bookmark(
     url: String,
     state: tauri::State<'_, DatabaseState>,
-) -> Result<ItemModel, String> {
-    let processor = BookmarkProcessor::new();
-    
-    // Fetch metadata from URL
-    let metadata = processor
-        .fetch_metadata(&url)
-        .await
-        .map_err(|e| format!("Failed to fetch bookmark metadata: {}", e))?;
-    
+    indexer: tauri::State<'_, IndexerState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SavedUrl, String> {
     let db = state
         .get_connection()
         .await
         .ok_or("Database not connected")?;
 
+    // Saving a URL we already have shouldn't even need the network
+    if let Some(existing) = find_by_canonical_url(&db, canonicalize(&url).as_deref()).await? {
+        return Ok(SavedUrl::Item(existing));
+    }
+
+    let processor = BookmarkProcessor::with_saved_profiles(&db).await;
+
+    let capture = match processor.capture(&url).await {
+        Ok(capture) => capture,
+        // A missing page won't fill in later, unlike one that is unreachable right now
+        Err(BookmarkError::Network(e)) if e.status().is_some_and(|s| s.is_client_error()) => {
+            return Err(format!("Failed to fetch bookmark: {}", e));
+        }
+        // Offline or the site is down: save it now and let the refresher fill it in later
+        Err(e @ (BookmarkError::Network(_) | BookmarkError::Timeout)) => {
+            let item = refresher::placeholder(&url, &e);
+            let canonical_url = canonicalize(&url);
+            let item = match insert_item(&db, item).await {
+                Ok(item) => item,
+                Err(e) => find_by_canonical_url(&db, canonical_url.as_deref())
+                    .await?
+                    .ok_or(e.to_string())?,
+            };
+            indexer.wake();
+            return Ok(SavedUrl::Item(item));
+        }
+        Err(e) => return Err(format!("Failed to fetch bookmark metadata: {}", e)),
+    };
+
+    // The page may still turn out to be one we have, via a redirect or its canonical link
+    let canonical_url = capture.canonical_url().map(str::to_string);
+    let existing = find_by_canonical_url(&db, canonical_url.as_deref()).await?;
+    if let Some(existing) = existing.clone() {
+        if existing.item_type != "bookmark" || existing.metadata.is_some() {
+            return Ok(SavedUrl::Item(existing));
+        }
+    }
+
+    // A download already stored must still be there when its item is saved
+    let _storing = match capture {
+        Capture::File(_) => Some(blobs::hold().await),
+        _ => None,
+    };
+    let (mut item, download) = match capture {
+        Capture::Page(metadata) => (
+            bookmark_item(&processor, *metadata, &app_handle).await?,
+            None,
+        ),
+        Capture::File(download) => {
+            let (item, blob) = downloaded_file_item(download, &app_handle).await?;
+            (item, Some(blob))
+        }
+        Capture::Feed(feed) => {
+            let fetcher = FeedFetcher::with_processor(processor);
+            let (feed, polled) = feeds::subscribe(&db, &fetcher, &feed.url, FeedFilters::default())
+                .await
+                .map_err(|e| format!("Failed to subscribe to feed: {}", e))?;
+            indexer.wake();
+            let _ = app_handle.emit(feeds::FEED_POLLED_EVENT, &polled);
+            return Ok(SavedUrl::Feed(feed));
+        }
+    };
+
+    // A stored download is only held by an item if a new one is saved for it
+    let item = match existing {
+        Some(existing) => {
+            release_download(&db, download).await;
+            merge_capture(&db, existing, item).await?
+        }
+        None => {
+            item.canonical_url = Set(canonical_url.clone());
+            match insert_item(&db, item).await {
+                Ok(item) => item,
+                // Someone saved the same page while we were fetching it
+                Err(e) => {
+                    release_download(&db, download).await;
+                    find_by_canonical_url(&db, canonical_url.as_deref())
+                        .await?
+                        .ok_or(e.to_string())?
+                }
+            }
+        }
+    };
+    indexer.wake();
+
+    Ok(SavedUrl::Item(item))
+}
+
+async fn find_by_canonical_url(
+    db: &DatabaseConnection,
+    canonical_url: Option<&str>,
+) -> Result<Option<ItemModel>, String> {
+    let Some(canonical_url) = canonical_url else {
+        return Ok(None);
+    };
+    Item::find()
+        .filter(crate::entities::item::Column::CanonicalUrl.eq(canonical_url))
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Fill in what an existing item is missing from a fresh capture of the same URL, leaving
+/// anything already there, and possibly edited, alone.
+async fn merge_capture(
+    db: &DatabaseConnection,
+    existing: ItemModel,
+    mut fetched: ItemActiveModel,
+) -> Result<ItemModel, String> {
+    let content = existing
+        .content
+        .clone()
+        .or_else(|| fetched.content.take().flatten());
+    let metadata = merge_metadata(
+        existing.metadata.as_deref(),
+        fetched.metadata.take().flatten().as_deref(),
+    );
+
+    let mut active: ItemActiveModel = existing.into();
+    active.content = Set(content);
+    active.metadata = Set(metadata);
+    active.updated_at = Set(Utc::now().naive_utc());
+
+    let txn = db.begin().await.map_err(|e| e.to_string())?;
+    let updated = active.update(&txn).await.map_err(|e| e.to_string())?;
+    indexer::enqueue(&txn, updated.id, IndexOperation::Upsert)
+        .await
+        .map_err(|e| e.to_string())?;
+    txn.commit().await.map_err(|e| e.to_string())?;
+    Ok(updated)
+}
+
+async fn bookmark_item(
+    processor: &BookmarkProcessor,
+    mut metadata: BookmarkMetadata,
+    app_handle: &tauri::AppHandle,
+) -> Result<ItemActiveModel, String> {
+    match AssetStore::open(app_handle) {
+        Ok(store) => processor.fetch_assets(&mut metadata, &store).await,
+        Err(e) => eprintln!("Bookmark images will not be saved: {}", e),
+    }
+    match AssetStore::open_snapshots(app_handle) {
+        Ok(store) => {
+            if let Err(e) = processor.save_snapshot(&mut metadata, &store).await {
+                eprintln!("No snapshot saved for {}: {}", metadata.url, e);
+            }
+        }
+        Err(e) => eprintln!("Bookmark snapshots will not be saved: {}", e),
+    }
+
     // Create bookmark item with fetched metadata
-    let item = ItemActiveModel {
+    Ok(ItemActiveModel {
         title: Set(metadata.title),
-        content: Set(metadata.description),
+        // Index the article itself when we found one, otherwise the page's own summary
+        content: Set(metadata.article.or(metadata.description)),
         item_type: Set("bookmark".to_string()),
         tags: Set(None),
         source_type: Set(Some("bookmark".to_string())),
         source_url: Set(Some(metadata.url)),
+        metadata: Set(Some(
+            serde_json::to_string(&metadata.details).map_err(|e| e.to_string())?,
+        )),
         ..Default::default()
-    };
+    })
+}
+
+/// Leave a stored download no item took to be collected. Only logged, since the item the
+/// user asked for is saved either way.
+async fn release_download(db: &DatabaseConnection, blob: Option<StoredBlob>) {
+    if let Some(blob) = blob {
+        if let Err(e) = blobs::release(db, &blob).await {
+            eprintln!("Failed to mark {} unused: {}", blob.path.display(), e);
+        }
+    }
+}
 
-    let item = item.insert(&db).await.map_err(|e| e.to_string())?;
-    
-    // Add to search index
-    typesense::upsert_item_document(&item)
+/// A file item for a download, with the blob it was stored as. Unlike files added from disk,
+/// `source_url` is where it came from and the stored copy's path goes in `metadata.file_path`.
+async fn downloaded_file_item(
+    download: Download,
+    app_handle: &tauri::AppHandle,
+) -> Result<(ItemActiveModel, StoredBlob), String> {
+    let (metadata, blob) = FileProcessor::new()
+        .save_download(
+            download.bytes,
+            &download.file_name,
+            &download.mime_type,
+            app_handle,
+        )
         .await
-        .map_err(|e| e.to_string())?;
-    
-    Ok(item)
+        .map_err(|e| format!("Failed to save download: {}", e))?;
+
+    let item = ItemActiveModel {
+        title: Set(metadata.title),
+        content: Set(None),
+        item_type: Set("file".to_string()),
+        tags: Set(None),
+        source_type: Set(Some("url".to_string())),
+        source_url: Set(Some(download.url)),
+        mime_type: Set(metadata.mime_type),
+        file_size: Set(Some(metadata.file_size as i64)),
+        file_modified_at: Set(Some(metadata.file_modified_at)),
+        metadata: Set(Some(
+            serde_json::json!({ "file_path": metadata.final_path }).to_string(),
+        )),
+        content_hash: Set(Some(metadata.content_hash)),
+        original_filename: Set(Some(metadata.original_filename)),
+        ..Default::default()
+    };
+    Ok((item, blob))
 }
 
+/// Store a file by its contents, or with the `"link"` operation index it where it is. When the
+/// same contents are already stored for another item, or the same path already linked, that
+/// item is returned unless `on_duplicate` asks to keep both.
 #[tauri::command]
 pub async fn create_file_item(
     request: FileOperationRequest,
     state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
     app_handle: tauri::AppHandle,
 ) -> Result<ItemModel, String> {
-    let processor = FileProcessor::new();
-    
-    // Process file (copy or move)
-    let metadata = processor
-        .process_file(request, &app_handle)
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    let store = FileProcessor::blob_store(&app_handle)
         .map_err(|e| format!("Failed to process file: {}", e))?;
-    
+    let (item, _) = files::import_file(&db, &store, &request).await?;
+    indexer.wake();
+
+    Ok(item)
+}
+
+/// Import every file in a folder that passes the filters, reporting progress as
+/// `DIRECTORY_IMPORT_PROGRESS_EVENT`. Stop it with `cancel_directory_import` and the same
+/// `import_id`.
+#[tauri::command]
+pub async fn import_directory(
+    request: DirectoryImportRequest,
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+    imports: tauri::State<'_, DirectoryImports>,
+    app_handle: tauri::AppHandle,
+) -> Result<DirectoryImportReport, String> {
     let db = state
         .get_connection()
         .await
         .ok_or("Database not connected")?;
 
-    // Create file item with extracted metadata
-    let item = ItemActiveModel {
-        title: Set(metadata.title),
-        content: Set(None), // Files don't have content, just metadata
-        item_type: Set("file".to_string()),
-        tags: Set(None),
-        source_type: Set(Some("file".to_string())),
-        source_url: Set(Some(metadata.final_path)),
-        mime_type: Set(metadata.mime_type),
-        file_size: Set(Some(metadata.file_size as i64)),
-        file_modified_at: Set(Some(metadata.file_modified_at)),
-        metadata: Set(None), // Can be used for additional file metadata in the future
-        ..Default::default()
-    };
+    let store = FileProcessor::blob_store(&app_handle)
+        .map_err(|e| format!("Failed to process file: {}", e))?;
+    let report = directory_import::import_directory(&db, &store, &imports, request, |progress| {
+        // Index files as they arrive rather than all at the end
+        if matches!(progress.outcome, Some(FileOutcome::Imported { .. })) {
+            indexer.wake();
+        }
+        let _ = app_handle.emit(directory_import::DIRECTORY_IMPORT_PROGRESS_EVENT, progress);
+    })
+    .await?;
+
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn cancel_directory_import(
+    import_id: String,
+    imports: tauri::State<'_, DirectoryImports>,
+) -> Result<(), String> {
+    if imports.cancel(&import_id) {
+        Ok(())
+    } else {
+        Err("No import running with this id".to_string())
+    }
+}
+
+/// The item already storing the same contents as the file at `path`, if any, so the UI can
+/// offer to link to it before importing.
+#[tauri::command]
+pub async fn find_duplicate_file(
+    path: String,
+    state: tauri::State<'_, DatabaseState>,
+) -> Result<Option<ItemModel>, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    // Hashing reads the whole file, so it happens on the blocking pool
+    let file = std::path::PathBuf::from(&path);
+    let (hash, _) = tokio::task::spawn_blocking(move || blobs::hash_file(&file))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    blobs::find_duplicate(&db, &hash)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Import a browser, Pocket, Pinboard, Raindrop or Instapaper export, detecting which when
+/// `format` is not given. A dry run only reports what would be created and skipped. With
+/// `enrich`, page metadata for the new bookmarks is fetched afterwards in the background,
+/// reporting progress as `bookmark-import-progress` events.
+#[tauri::command]
+pub async fn import_bookmarks(
+    path: String,
+    format: Option<ImportFormat>,
+    dry_run: bool,
+    enrich: bool,
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+    app_handle: tauri::AppHandle,
+) -> Result<ImportReport, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
 
-    let item = item.insert(&db).await.map_err(|e| e.to_string())?;
-    
-    // Add to search index
-    typesense::upsert_item_document(&item)
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let contents = String::from_utf8_lossy(&bytes);
+    let format = format
+        .or_else(|| ImportFormat::detect(&contents))
+        .ok_or_else(|| importers::ImportError::UnknownFormat.to_string())?;
+    let entries = importers::parse(format, &contents).map_err(|e| e.to_string())?;
+    let mut report = importers::import(&db, entries, dry_run)
         .await
         .map_err(|e| e.to_string())?;
-    
+    report.format = Some(format);
+    if dry_run {
+        return Ok(report);
+    }
+    indexer.wake();
+
+    if enrich && !report.created_ids.is_empty() {
+        report.enriching = true;
+        importers::start_enrichment(
+            app_handle,
+            db,
+            indexer.inner().clone(),
+            report.created_ids.clone(),
+        );
+    }
+    Ok(report)
+}
+
+/// Import a browser's exported `bookmarks.html`; `import_bookmarks` handles it and the other
+/// formats.
+#[tauri::command]
+pub async fn import_bookmarks_html(
+    path: String,
+    enrich: bool,
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+    app_handle: tauri::AppHandle,
+) -> Result<ImportReport, String> {
+    import_bookmarks(
+        path,
+        Some(ImportFormat::Browser),
+        false,
+        enrich,
+        state,
+        indexer,
+        app_handle,
+    )
+    .await
+}
+
+/// Re-request a saved URL now and record whether it still resolves.
+#[tauri::command]
+pub async fn check_bookmark_link(
+    id: i32,
+    state: tauri::State<'_, DatabaseState>,
+    app_handle: tauri::AppHandle,
+) -> Result<LinkCheck, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    let processor = BookmarkProcessor::with_saved_profiles(&db).await;
+    let link_check = link_checker::check_item(&db, &processor, id).await?;
+    let _ = app_handle.emit(
+        link_checker::LINK_CHECKED_EVENT,
+        &LinkCheckedEvent {
+            item_id: id,
+            link_check: link_check.clone(),
+        },
+    );
+    Ok(link_check)
+}
+
+/// Find a linked file that has gone missing by searching `root` for its contents, and point
+/// the item at where it is now.
+#[tauri::command]
+pub async fn relink_file(
+    id: i32,
+    root: String,
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+) -> Result<ItemModel, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    let item = linked_files::relink(&db, id, root.into()).await?;
+    indexer.wake();
     Ok(item)
 }
+
+/// Fetch a bookmark's page again now and update its title, content and metadata, keeping
+/// anything edited by hand. Saves made offline are filled in this way too.
+#[tauri::command]
+pub async fn refresh_bookmark(
+    id: i32,
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+    app_handle: tauri::AppHandle,
+) -> Result<ItemModel, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    let item = Item::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Item not found")?;
+    if item.item_type != "bookmark" {
+        return Err("Not a bookmark".to_string());
+    }
+
+    let processor = BookmarkProcessor::with_saved_profiles(&db).await;
+    let stores = refresher::Stores::open(&app_handle);
+    let event = refresher::refresh_item(&db, &processor, &stores, id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Bookmark not found, has no URL, or has metadata that can't be updated")?;
+    let _ = app_handle.emit(refresher::BOOKMARK_REFRESHED_EVENT, &event);
+    if let Some(error) = event.refresh.error {
+        return Err(format!("Failed to refresh bookmark: {}", error));
+    }
+    indexer.wake();
+
+    // A bookmark saved offline may have turned out to be one already saved, and gone
+    Item::find_by_id(event.merged_into.unwrap_or(id))
+        .one(&db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Item not found".to_string())
+}
+
+/// Subscribe to an RSS, Atom or JSON feed and save the entries it lists now; later ones are
+/// picked up by the background poller. Only entries passing the keyword filters are saved.
+#[tauri::command]
+pub async fn subscribe_feed(
+    url: String,
+    include_keywords: Option<Vec<String>>,
+    exclude_keywords: Option<Vec<String>>,
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+    app_handle: tauri::AppHandle,
+) -> Result<FeedModel, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    let filters = FeedFilters::new(include_keywords, exclude_keywords);
+    let fetcher = FeedFetcher::with_saved_profiles(&db).await;
+    let (feed, polled) = feeds::subscribe(&db, &fetcher, url.trim(), filters)
+        .await
+        .map_err(|e| format!("Failed to subscribe to feed: {}", e))?;
+    indexer.wake();
+    let _ = app_handle.emit(feeds::FEED_POLLED_EVENT, &polled);
+    Ok(feed)
+}
+
+#[tauri::command]
+pub async fn list_feeds(state: tauri::State<'_, DatabaseState>) -> Result<Vec<FeedModel>, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    Feed::find()
+        .order_by_asc(crate::entities::feed::Column::Title)
+        .all(&db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_feed_filters(
+    id: i32,
+    include_keywords: Option<Vec<String>>,
+    exclude_keywords: Option<Vec<String>>,
+    state: tauri::State<'_, DatabaseState>,
+) -> Result<FeedModel, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    feeds::set_filters(
+        &db,
+        id,
+        FeedFilters::new(include_keywords, exclude_keywords),
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Feed not found".to_string())
+}
+
+/// Stop polling a feed. Items it already saved are kept.
+#[tauri::command]
+pub async fn unsubscribe_feed(
+    id: i32,
+    state: tauri::State<'_, DatabaseState>,
+) -> Result<(), String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    if feeds::unsubscribe(&db, id)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Ok(())
+    } else {
+        Err("Feed not found".to_string())
+    }
+}
+
+/// Watch a folder and import its files, by default linking them where they are. Patterns are
+/// globs relative to the folder; `.gitignore` and `.junkdrawerignore` files inside it are
+/// honoured too.
+#[tauri::command]
+pub async fn add_watched_path(
+    path: String,
+    recursive: Option<bool>,
+    include_patterns: Option<Vec<String>>,
+    exclude_patterns: Option<Vec<String>>,
+    operation: Option<WatchOperation>,
+    state: tauri::State<'_, DatabaseState>,
+    watcher: tauri::State<'_, WatcherState>,
+) -> Result<WatchedPathModel, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    let watched = watcher::add(
+        &db,
+        &path,
+        recursive.unwrap_or(true),
+        include_patterns.unwrap_or_default(),
+        exclude_patterns.unwrap_or_default(),
+        operation.unwrap_or_default(),
+    )
+    .await
+    .map_err(|e| format!("Failed to watch folder: {}", e))?;
+    watcher.reload();
+    Ok(watched)
+}
+
+#[tauri::command]
+pub async fn list_watched_paths(
+    state: tauri::State<'_, DatabaseState>,
+) -> Result<Vec<WatchedPathModel>, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    watcher::list(&db).await.map_err(|e| e.to_string())
+}
+
+/// Stop watching a folder. Items made from its files are kept.
+#[tauri::command]
+pub async fn remove_watched_path(
+    id: i32,
+    state: tauri::State<'_, DatabaseState>,
+    watcher: tauri::State<'_, WatcherState>,
+) -> Result<(), String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    if !watcher::remove(&db, id).await.map_err(|e| e.to_string())? {
+        return Err("Watched folder not found".to_string());
+    }
+    watcher.reload();
+    Ok(())
+}
+
+/// Compare a watched folder with what was imported from it right away.
+#[tauri::command]
+pub async fn rescan_watched_path(
+    id: i32,
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SyncReport, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    let watched = WatchedPath::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Watched folder not found")?;
+    let store = FileProcessor::blob_store(&app_handle).map_err(|e| e.to_string())?;
+    let report = watcher::scan(&db, &store, &watched)
+        .await
+        .map_err(|e| e.to_string())?;
+    indexer.wake();
+    let _ = app_handle.emit(watcher::WATCHED_FILES_SYNCED_EVENT, &report);
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn list_fetch_profiles(
+    state: tauri::State<'_, DatabaseState>,
+) -> Result<Vec<DomainProfile>, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    let mut profiles = bookmarks::profiles::load(&db)
+        .await
+        .map_err(|e| e.to_string())?;
+    profiles.sort_by(|a, b| a.domain.cmp(&b.domain));
+    Ok(profiles)
+}
+
+/// Create or update how pages on a domain are fetched, from bookmark captures to link
+/// checks. Cookies already imported for the domain are kept.
+#[tauri::command]
+pub async fn save_fetch_profile(
+    request: DomainProfileRequest,
+    state: tauri::State<'_, DatabaseState>,
+) -> Result<DomainProfile, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    bookmarks::profiles::save(&db, request).await
+}
+
+/// Replace a domain's cookies with the ones for it in a Netscape `cookies.txt` export.
+#[tauri::command]
+pub async fn import_fetch_cookies(
+    domain: String,
+    path: String,
+    state: tauri::State<'_, DatabaseState>,
+) -> Result<DomainProfile, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    let cookies_txt = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    bookmarks::profiles::import_cookies(&db, &domain, &cookies_txt).await
+}
+
+#[tauri::command]
+pub async fn delete_fetch_profile(
+    id: i32,
+    state: tauri::State<'_, DatabaseState>,
+) -> Result<(), String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    if bookmarks::profiles::delete(&db, id)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Ok(())
+    } else {
+        Err("Fetch profile not found".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn search_items(
+    request: SearchQuery,
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+) -> Result<SearchItemsResponse, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    let backend = search::select_backend(&db, &indexer);
+    let results = backend.search(&request).await.map_err(|e| e.to_string())?;
+
+    let ids = results.hits.iter().map(|hit| hit.id).collect::<Vec<_>>();
+    let mut items = Item::find()
+        .filter(crate::entities::item::Column::Id.is_in(ids))
+        .all(&db)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|item| (item.id, item))
+        .collect::<HashMap<_, _>>();
+
+    // Keep the backend's ranking; hits whose rows have since been deleted are dropped.
+    let hits = results
+        .hits
+        .into_iter()
+        .filter_map(|hit| {
+            items.remove(&hit.id).map(|item| SearchItemHit {
+                item,
+                highlights: hit.highlights,
+            })
+        })
+        .collect();
+
+    Ok(SearchItemsResponse {
+        hits,
+        found: results.found,
+        page: results.page,
+        facets: results.facets,
+        backend: backend.name().to_string(),
+    })
+}
+
+#[tauri::command]
+pub async fn search_facets(
+    request: SearchQuery,
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+) -> Result<Vec<FacetCount>, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    search::select_backend(&db, &indexer)
+        .facets(&request)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn reindex_all(
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+) -> Result<FullReindexReport, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    search::reindex_all(&db, &indexer)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn verify_index(
+    state: tauri::State<'_, DatabaseState>,
+    indexer: tauri::State<'_, IndexerState>,
+) -> Result<IndexConsistencyReport, String> {
+    let db = state
+        .get_connection()
+        .await
+        .ok_or("Database not connected")?;
+
+    indexer::verify_index(&db, &indexer).await
+}
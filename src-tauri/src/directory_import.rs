@@ -0,0 +1,599 @@
+//! Importing a whole folder at once. Files are found first, then imported a few at a time on
+//! the blocking pool, with progress reported after each one and a way to stop partway.
+
+use futures::stream::{self, StreamExt};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::blobs::BlobStore;
+use crate::files::{self, DuplicateAction, FileOperationRequest};
+use crate::watcher::Rules;
+
+/// Files imported at once. Each one is mostly disk work, so a few keep the disk busy without
+/// the import crowding out everything else.
+const CONCURRENT_IMPORTS: usize = 4;
+
+pub const DIRECTORY_IMPORT_PROGRESS_EVENT: &str = "directory-import-progress";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryImportRequest {
+    pub path: String,
+    /// Chosen by the caller, so it can cancel the import while it runs.
+    pub import_id: String,
+    #[serde(default = "default_recursive")]
+    pub recursive: bool,
+    /// Globs relative to the folder; when given, only matching files are imported.
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// Bytes.
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// "copy" (the default), "move" or "link", as for a single file.
+    #[serde(default = "default_operation")]
+    pub operation: String,
+    #[serde(default)]
+    pub on_duplicate: DuplicateAction,
+}
+
+fn default_recursive() -> bool {
+    true
+}
+
+fn default_operation() -> String {
+    "copy".to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileSkipReason {
+    /// Left out by the patterns, an ignore file, or for being hidden.
+    Excluded,
+    TooSmall,
+    TooLarge,
+    /// The same contents, or for links the same path, are already an item.
+    AlreadyImported,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum FileOutcome {
+    Imported {
+        item_id: i32,
+    },
+    Skipped {
+        reason: FileSkipReason,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        item_id: Option<i32>,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportedFile {
+    pub path: String,
+    pub item_id: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: FileSkipReason,
+    /// The existing item, for files already imported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FailedFile {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DirectoryImportReport {
+    pub import_id: String,
+    /// Files found in the folder, including those skipped.
+    pub found: usize,
+    pub imported: Vec<ImportedFile>,
+    pub skipped: Vec<SkippedFile>,
+    pub failed: Vec<FailedFile>,
+    pub cancelled: bool,
+    /// Files a cancelled import never got to.
+    pub not_started: usize,
+}
+
+/// Sent as `DIRECTORY_IMPORT_PROGRESS_EVENT` once files are found, after each one, and when
+/// the import ends. Files the filters leave out are only in the report.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DirectoryImportProgress {
+    pub import_id: String,
+    /// Files to import.
+    pub total: usize,
+    pub processed: usize,
+    pub imported: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// The file just processed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<FileOutcome>,
+    pub finished: bool,
+    pub cancelled: bool,
+}
+
+/// Imports that are running, by id, so they can be cancelled.
+#[derive(Clone, Default)]
+pub struct DirectoryImports {
+    running: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+impl DirectoryImports {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop an import after the files it is in the middle of. Returns false if no import with
+    /// that id is running.
+    pub fn cancel(&self, import_id: &str) -> bool {
+        match self.lock().get(import_id) {
+            Some(cancelled) => {
+                cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn start(&self, import_id: &str) -> Option<Running<'_>> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut running = self.lock();
+        if running.contains_key(import_id) {
+            return None;
+        }
+        running.insert(import_id.to_string(), cancelled.clone());
+        Some(Running {
+            imports: self,
+            import_id: import_id.to_string(),
+            cancelled,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<AtomicBool>>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// An import in progress; it is forgotten when this is dropped, however the import ends.
+struct Running<'a> {
+    imports: &'a DirectoryImports,
+    import_id: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Running<'_> {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.imports.lock().remove(&self.import_id);
+    }
+}
+
+/// Import every file under `request.path` that the filters allow, calling `on_progress` as
+/// described on `DirectoryImportProgress`. A cancelled import finishes the files it started,
+/// each of which is imported completely or not at all.
+pub async fn import_directory(
+    db: &DatabaseConnection,
+    store: &BlobStore,
+    imports: &DirectoryImports,
+    request: DirectoryImportRequest,
+    on_progress: impl Fn(&DirectoryImportProgress),
+) -> Result<DirectoryImportReport, String> {
+    let root = fs::canonicalize(&request.path)
+        .ok()
+        .filter(|root| root.is_dir())
+        .ok_or_else(|| format!("Not a folder: {}", request.path))?;
+    if !matches!(request.operation.as_str(), "copy" | "move" | "link") {
+        return Err(format!("Invalid operation: {}", request.operation));
+    }
+    let rules = Rules::new(
+        &root,
+        request.recursive,
+        &request.include_patterns,
+        &request.exclude_patterns,
+    )
+    .map_err(|e| format!("Invalid pattern: {}", e))?;
+    let running = imports
+        .start(&request.import_id)
+        .ok_or_else(|| format!("Import {} is already running", request.import_id))?;
+
+    let sizes = (request.min_size, request.max_size);
+    let (candidates, skipped, failed) = tokio::task::spawn_blocking(move || find(&rules, sizes))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut report = DirectoryImportReport {
+        import_id: request.import_id.clone(),
+        found: candidates.len() + skipped.len() + failed.len(),
+        skipped,
+        failed,
+        ..Default::default()
+    };
+    let mut progress = DirectoryImportProgress {
+        import_id: request.import_id.clone(),
+        total: candidates.len(),
+        ..Default::default()
+    };
+    on_progress(&progress);
+
+    let mut results = stream::iter(candidates)
+        .map(|path| {
+            let running = &running;
+            let request = FileOperationRequest {
+                file_path: path.display().to_string(),
+                operation: request.operation.clone(),
+                on_duplicate: request.on_duplicate,
+            };
+            async move {
+                // Checked as each file comes up, so a cancel stops the rest straight away
+                if running.is_cancelled() {
+                    return (request.file_path, None);
+                }
+                let outcome = match files::import_file(db, store, &request).await {
+                    Ok((item, false)) => FileOutcome::Imported { item_id: item.id },
+                    Ok((item, true)) => FileOutcome::Skipped {
+                        reason: FileSkipReason::AlreadyImported,
+                        item_id: Some(item.id),
+                    },
+                    Err(error) => FileOutcome::Failed { error },
+                };
+                (request.file_path, Some(outcome))
+            }
+        })
+        .buffer_unordered(CONCURRENT_IMPORTS);
+
+    while let Some((path, outcome)) = results.next().await {
+        let Some(outcome) = outcome else {
+            report.not_started += 1;
+            continue;
+        };
+        progress.processed += 1;
+        match &outcome {
+            FileOutcome::Imported { item_id } => {
+                progress.imported += 1;
+                report.imported.push(ImportedFile {
+                    path: path.clone(),
+                    item_id: *item_id,
+                });
+            }
+            FileOutcome::Skipped { reason, item_id } => {
+                progress.skipped += 1;
+                report.skipped.push(SkippedFile {
+                    path: path.clone(),
+                    reason: *reason,
+                    item_id: *item_id,
+                });
+            }
+            FileOutcome::Failed { error } => {
+                progress.failed += 1;
+                report.failed.push(FailedFile {
+                    path: path.clone(),
+                    error: error.clone(),
+                });
+            }
+        }
+        progress.path = Some(path);
+        progress.outcome = Some(outcome);
+        on_progress(&progress);
+    }
+
+    report.cancelled = running.is_cancelled();
+    progress.path = None;
+    progress.outcome = None;
+    progress.finished = true;
+    progress.cancelled = report.cancelled;
+    on_progress(&progress);
+    Ok(report)
+}
+
+/// Files under the rules' root to import, and those skipped or unreadable along the way.
+/// Folders the rules leave out aren't looked into at all.
+fn find(
+    rules: &Rules,
+    (min_size, max_size): (Option<u64>, Option<u64>),
+) -> (Vec<PathBuf>, Vec<SkippedFile>, Vec<FailedFile>) {
+    let (mut candidates, mut skipped, mut failed) = (Vec::new(), Vec::new(), Vec::new());
+    let entries = walkdir::WalkDir::new(rules.root())
+        .max_depth(if rules.recursive() { usize::MAX } else { 1 })
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| !entry.file_type().is_dir() || rules.enters(entry.path()));
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                failed.push(FailedFile {
+                    path: e
+                        .path()
+                        .map(|path| path.display().to_string())
+                        .unwrap_or_default(),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path().display().to_string();
+        let size = entry
+            .metadata()
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+        let reason = if !rules.allows(entry.path()) {
+            Some(FileSkipReason::Excluded)
+        } else if min_size.is_some_and(|min| size < min) {
+            Some(FileSkipReason::TooSmall)
+        } else if max_size.is_some_and(|max| size > max) {
+            Some(FileSkipReason::TooLarge)
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => skipped.push(SkippedFile {
+                path,
+                reason,
+                item_id: None,
+            }),
+            None => candidates.push(entry.into_path()),
+        }
+    }
+    (candidates, skipped, failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, test_database};
+    use sea_orm::EntityTrait;
+
+    fn request(root: &std::path::Path, import_id: &str) -> DirectoryImportRequest {
+        serde_json::from_value(serde_json::json!({
+            "path": root.display().to_string(),
+            "importId": import_id,
+        }))
+        .expect("request")
+    }
+
+    #[tokio::test]
+    async fn folders_are_imported_with_filters_and_a_report() {
+        let db = test_database("directory-import").await;
+        let store = BlobStore::at(temp_dir("directory-import-blobs")).expect("store");
+        let root = temp_dir("directory-import-files");
+        fs::create_dir_all(root.join("photos/raw")).expect("dirs");
+        fs::write(root.join("notes.md"), "some notes").expect("write");
+        fs::write(root.join("photos/beach.jpg"), "a beach photo").expect("write");
+        fs::write(root.join("photos/raw/beach.cr3"), "x".repeat(2048)).expect("write");
+        fs::write(root.join("empty.txt"), "").expect("write");
+        fs::write(root.join("install.log"), "noise").expect("write");
+
+        let imports = DirectoryImports::new();
+        let events = Mutex::new(Vec::new());
+        let report = import_directory(
+            &db,
+            &store,
+            &imports,
+            DirectoryImportRequest {
+                exclude_patterns: vec!["*.log".to_string()],
+                min_size: Some(1),
+                max_size: Some(1024),
+                ..request(&root, "first")
+            },
+            |progress| events.lock().expect("events").push(progress.clone()),
+        )
+        .await
+        .expect("import");
+
+        assert_eq!(report.found, 5);
+        let mut imported = report
+            .imported
+            .iter()
+            .map(|file| {
+                PathBuf::from(&file.path)
+                    .strip_prefix(root.canonicalize().expect("root"))
+                    .expect("under root")
+                    .to_path_buf()
+            })
+            .collect::<Vec<_>>();
+        imported.sort();
+        assert_eq!(
+            imported,
+            vec![PathBuf::from("notes.md"), PathBuf::from("photos/beach.jpg")]
+        );
+        let reasons = report
+            .skipped
+            .iter()
+            .map(|file| file.reason)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            vec![
+                FileSkipReason::TooSmall,
+                FileSkipReason::Excluded,
+                FileSkipReason::TooLarge
+            ]
+        );
+        assert!(report.failed.is_empty());
+        assert!(!report.cancelled);
+
+        let events = events.into_inner().expect("events");
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].total, 2);
+        assert_eq!(events[0].processed, 0);
+        assert!(matches!(
+            events[1].outcome,
+            Some(FileOutcome::Imported { .. })
+        ));
+        let last = events.last().expect("last");
+        assert!(last.finished);
+        assert_eq!((last.processed, last.imported), (2, 2));
+        // Nothing is left running
+        assert!(!imports.cancel("first"));
+
+        // Importing again finds everything already there
+        let again = import_directory(
+            &db,
+            &store,
+            &imports,
+            DirectoryImportRequest {
+                max_size: Some(1024),
+                exclude_patterns: vec!["*.log".to_string()],
+                min_size: Some(1),
+                ..request(&root, "again")
+            },
+            |_| {},
+        )
+        .await
+        .expect("import");
+        assert!(again.imported.is_empty());
+        let already = again
+            .skipped
+            .iter()
+            .filter(|file| file.reason == FileSkipReason::AlreadyImported)
+            .count();
+        assert_eq!(already, 2);
+
+        // Moving what is already stored leaves the files where they are
+        let moved = import_directory(
+            &db,
+            &store,
+            &imports,
+            DirectoryImportRequest {
+                max_size: Some(1024),
+                exclude_patterns: vec!["*.log".to_string()],
+                min_size: Some(1),
+                operation: "move".to_string(),
+                ..request(&root, "move")
+            },
+            |_| {},
+        )
+        .await
+        .expect("import");
+        assert!(moved.imported.is_empty());
+        assert_eq!(moved.skipped.len(), again.skipped.len());
+        assert!(root.join("notes.md").exists());
+        assert!(root.join("photos/beach.jpg").exists());
+    }
+
+    #[tokio::test]
+    async fn cancelled_imports_stop_partway() {
+        let db = test_database("directory-import-cancel").await;
+        let store = BlobStore::at(temp_dir("directory-import-cancel-blobs")).expect("store");
+        let root = temp_dir("directory-import-cancel-files");
+        for n in 0..20 {
+            fs::write(root.join(format!("file-{n:02}.txt")), format!("file {n}")).expect("write");
+        }
+
+        let imports = DirectoryImports::new();
+        let report = import_directory(
+            &db,
+            &store,
+            &imports,
+            request(&root, "cancel"),
+            |progress| {
+                if progress.processed == 1 {
+                    assert!(imports.cancel(&progress.import_id));
+                }
+            },
+        )
+        .await
+        .expect("import");
+
+        assert!(report.cancelled);
+        assert!(report.not_started > 0);
+        assert_eq!(report.imported.len() + report.not_started, 20);
+        // Every file started was imported in full
+        assert_eq!(
+            crate::entities::Item::find()
+                .all(&db)
+                .await
+                .expect("items")
+                .len(),
+            report.imported.len()
+        );
+
+        assert!(
+            import_directory(&db, &store, &imports, request(&root, "cancel"), |_| {})
+                .await
+                .is_ok()
+        );
+        assert!(import_directory(
+            &db,
+            &store,
+            &imports,
+            DirectoryImportRequest {
+                operation: "shred".to_string(),
+                ..request(&root, "bad")
+            },
+            |_| {}
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn moving_duplicates_at_once_keeps_every_stored_file() {
+        let db = test_database("directory-import-duplicates").await;
+        let store = BlobStore::at(temp_dir("directory-import-duplicates-blobs")).expect("store");
+        let root = temp_dir("directory-import-duplicates-files");
+        for n in 0..8 {
+            fs::write(root.join(format!("copy-{n}.txt")), "the same").expect("write");
+        }
+
+        let report = import_directory(
+            &db,
+            &store,
+            &DirectoryImports::new(),
+            DirectoryImportRequest {
+                operation: "move".to_string(),
+                ..request(&root, "duplicates")
+            },
+            |_| {},
+        )
+        .await
+        .expect("import");
+
+        assert!(report.failed.is_empty());
+        assert!(!report.imported.is_empty());
+        assert_eq!(report.imported.len() + report.skipped.len(), 8);
+        // Whichever imports lost the race, what the saved items point at is still there
+        for imported in &report.imported {
+            let item = crate::entities::Item::find_by_id(imported.item_id)
+                .one(&db)
+                .await
+                .expect("query")
+                .expect("item");
+            let stored = item.source_url.expect("stored");
+            assert_eq!(fs::read(stored).expect("read"), b"the same");
+            assert!(!std::path::Path::new(&imported.path).exists());
+        }
+        for skipped in &report.skipped {
+            assert!(std::path::Path::new(&skipped.path).exists());
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A stored file's contents, shared by every item with the same `content_hash`. The count is
+/// maintained by triggers on `items`; a blob at zero is waiting to be collected.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "blobs")]
+pub struct Model {
+    /// Hex SHA-256 of the contents.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub hash: String,
+    pub size: Option<i64>,
+    pub ref_count: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::Set;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "feed_entries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub feed_id: i32,
+    /// The entry's id in the feed, or its link when the feed gives none.
+    pub guid: String,
+    /// The item captured for the entry; empty when a filter skipped it or its link was
+    /// already saved.
+    pub item_id: Option<i32>,
+    pub seen_at: chrono::NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            seen_at: Set(chrono::Utc::now().naive_utc()),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
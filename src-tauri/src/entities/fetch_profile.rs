@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::Set;
+use serde::{Deserialize, Serialize};
+
+/// How to fetch pages on one domain and its subdomains.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "fetch_profiles")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub domain: String,
+    /// JSON object of extra request headers.
+    pub headers: Option<String>,
+    /// JSON array of cookies imported from a `cookies.txt` file.
+    pub cookies: Option<String>,
+    pub proxy_url: Option<String>,
+    pub user_agent: Option<String>,
+    pub max_response_bytes: Option<i64>,
+    pub accept_invalid_certs: bool,
+    /// PEM file with an extra CA to trust, e.g. for an intranet's own certificates.
+    pub root_certificate_path: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            accept_invalid_certs: Set(false),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            updated_at: Set(chrono::Utc::now().naive_utc()),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::Set;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "index_outbox")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub item_id: i32,
+    pub operation: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// Set when the entry can never be applied; the worker skips it from then on.
+    pub failed_at: Option<chrono::NaiveDateTime>,
+    pub available_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        let now = chrono::Utc::now().naive_utc();
+        Self {
+            attempts: Set(0),
+            available_at: Set(now),
+            created_at: Set(now),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
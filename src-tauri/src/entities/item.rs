@@ -17,6 +17,13 @@ pub struct Model {
     pub file_size: Option<i64>,
     pub file_modified_at: Option<chrono::NaiveDateTime>,
     pub metadata: Option<String>,
+    /// Normalized URL for bookmarks and other captured links; unique when set.
+    pub canonical_url: Option<String>,
+    /// Hex SHA-256 of a stored file's contents, naming its blob; NULL for other items and
+    /// files stored before blobs.
+    pub content_hash: Option<String>,
+    /// The file's name where it came from, since the stored blob is named by its hash.
+    pub original_filename: Option<String>,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
 }
@@ -1,3 +1,25 @@
+pub mod blob;
+pub mod feed;
+pub mod feed_entry;
+pub mod fetch_profile;
+pub mod index_outbox;
 pub mod item;
+pub mod watched_file;
+pub mod watched_path;
 
+pub use blob::{ActiveModel as BlobActiveModel, Entity as Blob};
+pub use feed::{ActiveModel as FeedActiveModel, Entity as Feed, Model as FeedModel};
+pub use feed_entry::{ActiveModel as FeedEntryActiveModel, Entity as FeedEntry};
+pub use fetch_profile::{
+    ActiveModel as FetchProfileActiveModel, Entity as FetchProfile, Model as FetchProfileModel,
+};
+pub use index_outbox::{
+    ActiveModel as IndexOutboxActiveModel, Entity as IndexOutbox, Model as IndexOutboxModel,
+};
 pub use item::{ActiveModel as ItemActiveModel, Entity as Item, Model as ItemModel};
+pub use watched_file::{
+    ActiveModel as WatchedFileActiveModel, Entity as WatchedFile, Model as WatchedFileModel,
+};
+pub use watched_path::{
+    ActiveModel as WatchedPathActiveModel, Entity as WatchedPath, Model as WatchedPathModel,
+};
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::Set;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "watched_files")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub watched_path_id: i32,
+    pub path: String,
+    /// The item the file became; it is not imported again once that item is deleted.
+    pub item_id: Option<i32>,
+    pub size: i64,
+    /// Milliseconds since the Unix epoch.
+    pub modified_ms: Option<i64>,
+    /// Set while the file is gone from the folder; its item is kept and flagged.
+    pub missing_since: Option<chrono::NaiveDateTime>,
+    pub seen_at: chrono::NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            seen_at: Set(chrono::Utc::now().naive_utc()),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::Set;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "watched_paths")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    /// Absolute and canonical, so it lines up with the paths the watcher reports.
+    pub path: String,
+    pub recursive: bool,
+    /// One glob per line, since globs may contain commas; when set, only matching files are
+    /// imported.
+    pub include_patterns: Option<String>,
+    /// One glob per line; matching files and folders are never imported.
+    pub exclude_patterns: Option<String>,
+    /// How new files become items: `"link"` leaves them where they are, `"copy"` stores them.
+    pub operation: String,
+    /// When the whole folder was last compared with what was imported from it.
+    pub last_scan: Option<chrono::NaiveDateTime>,
+    pub last_error: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            updated_at: Set(chrono::Utc::now().naive_utc()),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
@@ -0,0 +1,743 @@
+//! RSS, Atom and JSON Feed subscriptions. A background worker polls each feed with conditional
+//! requests and saves entries it hasn't seen before as bookmarks, after the feed's keyword
+//! filters have had their say.
+
+use chrono::Utc;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::Emitter;
+use thiserror::Error;
+
+use crate::bookmarks::{canonicalize, BookmarkProcessor};
+use crate::entities::{
+    feed, feed_entry, item, Feed, FeedActiveModel, FeedEntry, FeedEntryActiveModel, FeedModel,
+    Item, ItemActiveModel,
+};
+use crate::indexer::{self, IndexOperation, IndexerState};
+
+const STARTUP_DELAY: Duration = Duration::from_secs(90);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// How often one feed is requested; most publish a few times a day at most.
+const REFRESH_AFTER_MINUTES: i64 = 60;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_FEED_BYTES: usize = 10 * 1024 * 1024;
+
+pub const FEED_POLLED_EVENT: &str = "feed-polled";
+
+#[derive(Error, Debug)]
+pub enum FeedError {
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error("Server responded with status {0}")]
+    Status(u16),
+    #[error("Feed is larger than {0} bytes")]
+    TooLarge(usize),
+    #[error("Not a feed: {0}")]
+    Parse(String),
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+/// What a poll found, sent as `FEED_POLLED_EVENT`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FeedPolledEvent {
+    pub feed_id: i32,
+    /// Items created for new entries.
+    pub created: Vec<i32>,
+    /// New entries the feed's keyword filters turned away.
+    pub filtered: usize,
+    /// The server answered 304: nothing changed since the last poll.
+    pub not_modified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Keyword filters of one feed. Keywords match whole words, ignoring case, anywhere in an
+/// entry's title or summary.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FeedFilters {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl FeedFilters {
+    pub fn new(include: Option<Vec<String>>, exclude: Option<Vec<String>>) -> Self {
+        let clean = |keywords: Option<Vec<String>>| {
+            keywords
+                .unwrap_or_default()
+                .iter()
+                .map(|keyword| words(keyword))
+                .filter(|keyword| !keyword.is_empty())
+                .collect()
+        };
+        Self {
+            include: clean(include),
+            exclude: clean(exclude),
+        }
+    }
+
+    fn of(feed: &FeedModel) -> Self {
+        let split = |keywords: &Option<String>| {
+            keywords
+                .as_deref()
+                .map(|keywords| keywords.split(',').map(str::to_string).collect())
+        };
+        Self::new(split(&feed.include_keywords), split(&feed.exclude_keywords))
+    }
+
+    pub fn allows(&self, text: &str) -> bool {
+        let text = format!(" {} ", words(text));
+        let mentions = |keyword: &String| text.contains(&format!(" {} ", keyword));
+        (self.include.is_empty() || self.include.iter().any(mentions))
+            && !self.exclude.iter().any(mentions)
+    }
+
+    /// The filters as stored in the `include_keywords` and `exclude_keywords` columns.
+    fn columns(&self) -> (Option<String>, Option<String>) {
+        let join = |keywords: &Vec<String>| (!keywords.is_empty()).then(|| keywords.join(", "));
+        (join(&self.include), join(&self.exclude))
+    }
+}
+
+/// Lowercase words of `text` separated by single spaces, for whole-word matching.
+fn words(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+enum Fetched {
+    NotModified,
+    Feed {
+        feed: Box<feed_rs::model::Feed>,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+/// Fetches feeds with the same clients `BookmarkProcessor` fetches pages with, so a domain's
+/// fetch profile applies to its feeds too.
+pub struct FeedFetcher {
+    processor: BookmarkProcessor,
+}
+
+impl Default for FeedFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FeedFetcher {
+    pub fn new() -> Self {
+        Self::with_processor(BookmarkProcessor::new())
+    }
+
+    pub fn with_processor(processor: BookmarkProcessor) -> Self {
+        Self { processor }
+    }
+
+    /// A fetcher using the fetch profiles saved in `db`, or none if they can't be loaded.
+    pub async fn with_saved_profiles(db: &DatabaseConnection) -> Self {
+        Self::with_processor(BookmarkProcessor::with_saved_profiles(db).await)
+    }
+
+    /// Request a feed, passing the validators from the last response so an unchanged feed
+    /// comes back as a bodiless 304.
+    async fn fetch(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<Fetched, FeedError> {
+        let parsed_url =
+            reqwest::Url::parse(url).map_err(|_| FeedError::InvalidUrl(url.to_string()))?;
+        let max_bytes = self.processor.max_bytes(&parsed_url, MAX_FEED_BYTES);
+        let mut request = self.processor.get(&parsed_url).timeout(REQUEST_TIMEOUT);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let mut response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Fetched::NotModified);
+        }
+        if !response.status().is_success() {
+            return Err(FeedError::Status(response.status().as_u16()));
+        }
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() > max_bytes {
+                return Err(FeedError::TooLarge(max_bytes));
+            }
+        }
+
+        // The feed's URL resolves relative links in entries
+        let feed = feed_rs::parser::Builder::new()
+            .base_uri(Some(url))
+            .build()
+            .parse(body.as_slice())
+            .map_err(|e| FeedError::Parse(e.to_string()))?;
+        Ok(Fetched::Feed {
+            feed: Box::new(feed),
+            etag,
+            last_modified,
+        })
+    }
+}
+
+/// Subscribe to the feed at `url` and save what it currently lists. Subscribing twice returns
+/// the existing subscription.
+pub async fn subscribe(
+    db: &DatabaseConnection,
+    fetcher: &FeedFetcher,
+    url: &str,
+    filters: FeedFilters,
+) -> Result<(FeedModel, FeedPolledEvent), FeedError> {
+    if let Some(existing) = Feed::find()
+        .filter(feed::Column::Url.eq(url))
+        .one(db)
+        .await?
+    {
+        return Ok((
+            existing.clone(),
+            FeedPolledEvent {
+                feed_id: existing.id,
+                ..Default::default()
+            },
+        ));
+    }
+
+    let Fetched::Feed {
+        feed,
+        etag,
+        last_modified,
+    } = fetcher.fetch(url, None, None).await?
+    else {
+        return Err(FeedError::Status(StatusCode::NOT_MODIFIED.as_u16()));
+    };
+
+    let title = feed
+        .title
+        .as_ref()
+        .map(|title| plain_text(&title.content))
+        .filter(|title| !title.is_empty())
+        .or_else(|| {
+            reqwest::Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+        })
+        .unwrap_or_else(|| url.to_string());
+    let (include_keywords, exclude_keywords) = filters.columns();
+
+    let subscription = FeedActiveModel {
+        url: Set(url.to_string()),
+        title: Set(title),
+        include_keywords: Set(include_keywords),
+        exclude_keywords: Set(exclude_keywords),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    let polled = store_entries(db, &subscription, &feed).await?;
+    let subscription = polled_now(db, subscription, Some((etag, last_modified)), None).await?;
+    Ok((subscription, polled))
+}
+
+/// Poll one feed and save its new entries. Failures are recorded on the feed as well as
+/// returned in the event.
+pub async fn poll(
+    db: &DatabaseConnection,
+    fetcher: &FeedFetcher,
+    subscription: FeedModel,
+) -> Result<FeedPolledEvent, DbErr> {
+    let mut event = FeedPolledEvent {
+        feed_id: subscription.id,
+        ..Default::default()
+    };
+
+    let fetched = fetcher
+        .fetch(
+            &subscription.url,
+            subscription.etag.as_deref(),
+            subscription.last_modified.as_deref(),
+        )
+        .await;
+    match fetched {
+        Ok(Fetched::NotModified) => {
+            event.not_modified = true;
+            polled_now(db, subscription, None, None).await?;
+        }
+        Ok(Fetched::Feed {
+            feed,
+            etag,
+            last_modified,
+        }) => {
+            event = store_entries(db, &subscription, &feed).await?;
+            polled_now(db, subscription, Some((etag, last_modified)), None).await?;
+        }
+        Err(FeedError::Database(e)) => return Err(e),
+        Err(e) => {
+            event.error = Some(e.to_string());
+            polled_now(db, subscription, None, Some(e.to_string())).await?;
+        }
+    }
+    Ok(event)
+}
+
+/// Poll every feed not requested within `REFRESH_AFTER_MINUTES`, oldest first.
+pub async fn poll_due(
+    db: &DatabaseConnection,
+    fetcher: &FeedFetcher,
+    on_polled: impl Fn(&FeedPolledEvent),
+) -> Result<usize, DbErr> {
+    let cutoff = Utc::now().naive_utc() - chrono::Duration::minutes(REFRESH_AFTER_MINUTES);
+    let due = Feed::find()
+        .filter(
+            Condition::any()
+                .add(feed::Column::LastPolledAt.is_null())
+                .add(feed::Column::LastPolledAt.lt(cutoff)),
+        )
+        .order_by_asc(feed::Column::LastPolledAt)
+        .all(db)
+        .await?;
+
+    let count = due.len();
+    for subscription in due {
+        let event = poll(db, fetcher, subscription).await?;
+        on_polled(&event);
+    }
+    Ok(count)
+}
+
+pub fn start_worker(app: tauri::AppHandle, db: DatabaseConnection, indexer: IndexerState) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        loop {
+            // Rebuilt each sweep so edited fetch profiles take effect
+            let fetcher = FeedFetcher::with_saved_profiles(&db).await;
+            let polled = poll_due(&db, &fetcher, |event| {
+                if !event.created.is_empty() {
+                    indexer.wake();
+                }
+                let _ = app.emit(FEED_POLLED_EVENT, event);
+            })
+            .await;
+            if let Err(e) = polled {
+                eprintln!("Failed to poll feeds: {}", e);
+            }
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+        }
+    });
+}
+
+/// Save the entries of `feed` that `subscription` hasn't seen yet as bookmarks tagged with the
+/// feed's title, all in one transaction with their outbox entries.
+async fn store_entries(
+    db: &DatabaseConnection,
+    subscription: &FeedModel,
+    feed: &feed_rs::model::Feed,
+) -> Result<FeedPolledEvent, DbErr> {
+    let filters = FeedFilters::of(subscription);
+    let mut event = FeedPolledEvent {
+        feed_id: subscription.id,
+        ..Default::default()
+    };
+
+    let txn = db.begin().await?;
+    for entry in &feed.entries {
+        let Some(link) = entry_link(entry) else {
+            continue;
+        };
+        let guid = if entry.id.trim().is_empty() {
+            link.clone()
+        } else {
+            entry.id.clone()
+        };
+        let seen = FeedEntry::find()
+            .filter(feed_entry::Column::FeedId.eq(subscription.id))
+            .filter(feed_entry::Column::Guid.eq(guid.as_str()))
+            .one(&txn)
+            .await?;
+        if seen.is_some() {
+            continue;
+        }
+
+        let title = entry
+            .title
+            .as_ref()
+            .map(|title| plain_text(&title.content))
+            .filter(|title| !title.is_empty());
+        let summary = entry
+            .summary
+            .as_ref()
+            .map(|summary| summary.content.clone())
+            .or_else(|| {
+                entry
+                    .content
+                    .as_ref()
+                    .and_then(|content| content.body.clone())
+            })
+            .map(|summary| plain_text(&summary))
+            .filter(|summary| !summary.is_empty());
+
+        let mut item_id = None;
+        let text = format!(
+            "{} {}",
+            title.as_deref().unwrap_or_default(),
+            summary.as_deref().unwrap_or_default()
+        );
+        if !filters.allows(&text) {
+            event.filtered += 1;
+        } else if let Some(canonical_url) = canonicalize(&link) {
+            let saved = Item::find()
+                .filter(item::Column::CanonicalUrl.eq(canonical_url.as_str()))
+                .one(&txn)
+                .await?;
+            if saved.is_none() {
+                let published = entry.published.or(entry.updated);
+                let item = ItemActiveModel {
+                    title: Set(title.unwrap_or_else(|| link.clone())),
+                    content: Set(summary),
+                    item_type: Set("bookmark".to_string()),
+                    tags: Set(Some(subscription.title.replace(',', " "))),
+                    source_type: Set(Some("feed".to_string())),
+                    source_url: Set(Some(link.clone())),
+                    canonical_url: Set(Some(canonical_url)),
+                    metadata: Set(Some(
+                        serde_json::json!({
+                            "feed_id": subscription.id,
+                            "published_at": published.map(|date| date.naive_utc()),
+                        })
+                        .to_string(),
+                    )),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await?;
+                indexer::enqueue(&txn, item.id, IndexOperation::Upsert).await?;
+                event.created.push(item.id);
+                item_id = Some(item.id);
+            }
+        }
+
+        FeedEntryActiveModel {
+            feed_id: Set(subscription.id),
+            guid: Set(guid),
+            item_id: Set(item_id),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+    Ok(event)
+}
+
+/// Stamp a poll on the feed. Validators are replaced only when a full response came back.
+async fn polled_now(
+    db: &DatabaseConnection,
+    subscription: FeedModel,
+    validators: Option<(Option<String>, Option<String>)>,
+    error: Option<String>,
+) -> Result<FeedModel, DbErr> {
+    let now = Utc::now().naive_utc();
+    let mut active: FeedActiveModel = subscription.into();
+    if let Some((etag, last_modified)) = validators {
+        active.etag = Set(etag);
+        active.last_modified = Set(last_modified);
+    }
+    active.last_polled_at = Set(Some(now));
+    active.last_error = Set(error);
+    active.update(db).await
+}
+
+/// Update a feed's keyword filters; they apply to entries seen from now on.
+pub async fn set_filters(
+    db: &DatabaseConnection,
+    id: i32,
+    filters: FeedFilters,
+) -> Result<Option<FeedModel>, DbErr> {
+    let Some(subscription) = Feed::find_by_id(id).one(db).await? else {
+        return Ok(None);
+    };
+    let (include_keywords, exclude_keywords) = filters.columns();
+    let mut active: FeedActiveModel = subscription.into();
+    active.include_keywords = Set(include_keywords);
+    active.exclude_keywords = Set(exclude_keywords);
+    active.updated_at = Set(Utc::now().naive_utc());
+    active.update(db).await.map(Some)
+}
+
+/// Drop a subscription and its record of seen entries. Items it already saved stay.
+pub async fn unsubscribe(db: &DatabaseConnection, id: i32) -> Result<bool, DbErr> {
+    let txn = db.begin().await?;
+    FeedEntry::delete_many()
+        .filter(feed_entry::Column::FeedId.eq(id))
+        .exec(&txn)
+        .await?;
+    let deleted = Feed::delete_by_id(id).exec(&txn).await?.rows_affected > 0;
+    txn.commit().await?;
+    Ok(deleted)
+}
+
+/// The page an entry is about: its alternate link, not an enclosure or a comments feed.
+fn entry_link(entry: &feed_rs::model::Entry) -> Option<String> {
+    entry
+        .links
+        .iter()
+        .find(|link| matches!(link.rel.as_deref(), None | Some("alternate")))
+        .map(|link| link.href.trim().to_string())
+        .filter(|href| !href.is_empty())
+}
+
+/// Text of a feed field that may hold HTML, with whitespace collapsed.
+fn plain_text(html: &str) -> String {
+    let fragment = scraper::Html::parse_fragment(html);
+    let text = fragment.root_element().text().collect::<String>();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{test_database, HttpStub, StubResponse};
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel>
+  <title>Rust Blog</title>
+  <link>https://blog.example.com/</link>
+  <item>
+    <title>Announcing Rust 2.0</title>
+    <link>https://blog.example.com/rust-2?utm_source=rss</link>
+    <guid>rust-2</guid>
+    <description>&lt;p&gt;A &lt;b&gt;big&lt;/b&gt; release&lt;/p&gt;</description>
+    <pubDate>Sun, 13 Sep 2020 12:26:40 GMT</pubDate>
+  </item>
+  <item>
+    <title>Sponsored: buy things</title>
+    <link>https://blog.example.com/ad</link>
+    <guid>ad</guid>
+  </item>
+  <item>
+    <title>Already saved</title>
+    <link>https://blog.example.com/saved</link>
+    <guid>saved</guid>
+  </item>
+</channel></rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom Notes</title>
+  <id>urn:notes</id>
+  <updated>2020-09-13T12:26:40Z</updated>
+  <entry>
+    <title>First note</title>
+    <id>urn:notes:1</id>
+    <link rel="alternate" href="https://notes.example.com/1"/>
+    <link rel="enclosure" href="https://notes.example.com/1.mp3"/>
+    <updated>2020-09-13T12:26:40Z</updated>
+    <summary>Short</summary>
+  </entry>
+</feed>"#;
+
+    const JSON_FEED: &str = r#"{
+  "version": "https://jsonfeed.org/version/1.1",
+  "title": "Json Log",
+  "items": [
+    { "id": "1", "url": "https://json.example.com/1", "title": "From JSON", "content_text": "Hello" }
+  ]
+}"#;
+
+    #[test]
+    fn filters_match_whole_words() {
+        let filters = FeedFilters::new(
+            Some(vec!["Rust".to_string(), "web assembly".to_string()]),
+            Some(vec!["sponsored".to_string(), " ".to_string()]),
+        );
+        assert_eq!(filters.exclude, vec!["sponsored"]);
+        assert!(filters.allows("Announcing RUST 2.0"));
+        assert!(filters.allows("All about Web-Assembly"));
+        assert!(!filters.allows("A matter of trust"));
+        assert!(!filters.allows("Sponsored: Rust jobs"));
+        assert!(FeedFilters::default().allows("anything"));
+    }
+
+    #[tokio::test]
+    async fn subscribing_saves_new_entries_once() {
+        let stub = HttpStub::start(vec![
+            (
+                "/rss",
+                StubResponse::new(200, RSS)
+                    .header("Content-Type", "application/rss+xml")
+                    .header("ETag", "\"v1\""),
+            ),
+            ("/atom", StubResponse::new(200, ATOM)),
+            ("/json", StubResponse::new(200, JSON_FEED)),
+            ("/page", StubResponse::new(200, "<html>Not a feed</html>")),
+        ])
+        .await;
+        let db = test_database("feeds-subscribe").await;
+        ItemActiveModel {
+            title: Set("Saved by hand".to_string()),
+            item_type: Set("bookmark".to_string()),
+            source_url: Set(Some("https://blog.example.com/saved".to_string())),
+            canonical_url: Set(canonicalize("https://blog.example.com/saved")),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .expect("existing bookmark");
+        let fetcher = FeedFetcher::new();
+
+        let (rss, polled) = subscribe(
+            &db,
+            &fetcher,
+            &stub.url("/rss"),
+            FeedFilters::new(None, Some(vec!["sponsored".to_string()])),
+        )
+        .await
+        .expect("subscribe");
+        assert_eq!(rss.title, "Rust Blog");
+        assert_eq!(rss.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(rss.exclude_keywords.as_deref(), Some("sponsored"));
+        assert!(rss.last_polled_at.is_some());
+        assert_eq!(polled.created.len(), 1);
+        assert_eq!(polled.filtered, 1);
+
+        let item = Item::find_by_id(polled.created[0])
+            .one(&db)
+            .await
+            .expect("query")
+            .expect("item");
+        assert_eq!(item.title, "Announcing Rust 2.0");
+        assert_eq!(item.content.as_deref(), Some("A big release"));
+        assert_eq!(item.tags.as_deref(), Some("Rust Blog"));
+        assert_eq!(item.source_type.as_deref(), Some("feed"));
+        assert_eq!(
+            item.canonical_url.as_deref(),
+            Some("https://blog.example.com/rust-2")
+        );
+
+        // The same entries on the next poll are not new any more
+        let again = poll(&db, &fetcher, rss.clone()).await.expect("poll");
+        assert_eq!(
+            again,
+            FeedPolledEvent {
+                feed_id: rss.id,
+                ..Default::default()
+            }
+        );
+
+        let (atom, polled) = subscribe(&db, &fetcher, &stub.url("/atom"), FeedFilters::default())
+            .await
+            .expect("atom");
+        assert_eq!(atom.title, "Atom Notes");
+        assert_eq!(polled.created.len(), 1);
+        let (_, polled) = subscribe(&db, &fetcher, &stub.url("/json"), FeedFilters::default())
+            .await
+            .expect("json feed");
+        assert_eq!(polled.created.len(), 1);
+        let urls = Item::find()
+            .all(&db)
+            .await
+            .expect("items")
+            .into_iter()
+            .filter_map(|item| item.source_url)
+            .collect::<Vec<_>>();
+        assert!(urls.contains(&"https://notes.example.com/1".to_string()));
+        assert!(urls.contains(&"https://json.example.com/1".to_string()));
+
+        assert!(matches!(
+            subscribe(&db, &fetcher, &stub.url("/page"), FeedFilters::default()).await,
+            Err(FeedError::Parse(_))
+        ));
+
+        assert!(unsubscribe(&db, rss.id).await.expect("unsubscribe"));
+        assert_eq!(Item::find().all(&db).await.expect("items").len(), 4);
+        assert!(FeedEntry::find()
+            .filter(feed_entry::Column::FeedId.eq(rss.id))
+            .all(&db)
+            .await
+            .expect("entries")
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn polling_records_not_modified_and_errors() {
+        let stub = HttpStub::start(vec![
+            ("/unchanged", StubResponse::new(304, "")),
+            ("/broken", StubResponse::new(500, "oops")),
+        ])
+        .await;
+        let db = test_database("feeds-poll").await;
+        let fetcher = FeedFetcher::new();
+        let subscription = |url: String| FeedActiveModel {
+            url: Set(url.clone()),
+            title: Set(url),
+            etag: Set(Some("\"v1\"".to_string())),
+            ..Default::default()
+        };
+        let unchanged = subscription(stub.url("/unchanged"))
+            .insert(&db)
+            .await
+            .expect("feed");
+        let broken = subscription(stub.url("/broken"))
+            .insert(&db)
+            .await
+            .expect("feed");
+
+        let events = std::sync::Mutex::new(Vec::new());
+        let polled = poll_due(&db, &fetcher, |event| {
+            events.lock().expect("events").push(event.clone())
+        })
+        .await
+        .expect("poll");
+        assert_eq!(polled, 2);
+
+        let events = events.into_inner().expect("events");
+        assert!(events
+            .iter()
+            .any(|event| event.feed_id == unchanged.id && event.not_modified));
+        assert!(events
+            .iter()
+            .any(|event| event.feed_id == broken.id && event.error.is_some()));
+
+        let broken = Feed::find_by_id(broken.id)
+            .one(&db)
+            .await
+            .expect("query")
+            .expect("feed");
+        assert_eq!(broken.etag.as_deref(), Some("\"v1\""));
+        assert!(broken.last_error.is_some());
+
+        // Both were just polled, so neither is due
+        assert_eq!(poll_due(&db, &fetcher, |_| {}).await.expect("poll"), 0);
+    }
+}
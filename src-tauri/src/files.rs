@@ -1,10 +1,16 @@
 use anyhow::{Context, Result as AnyhowResult};
+use sea_orm::{DatabaseConnection, Set};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 use tauri::Manager;
 use thiserror::Error;
 
+use crate::blobs::{self, BlobStore, Staged, StoredBlob};
+use crate::db_commands::insert_item;
+use crate::entities::{ItemActiveModel, ItemModel};
+use crate::linked_files::{self, LinkedFile};
+
 #[derive(Debug, Error)]
 pub enum FileError {
     #[error("Failed to access documents directory")]
@@ -26,12 +32,70 @@ pub struct FileMetadata {
     pub file_size: u64,
     pub file_modified_at: chrono::NaiveDateTime,
     pub final_path: String,
+    /// Hex SHA-256 of the contents, which names the stored blob.
+    pub content_hash: String,
+    pub original_filename: String,
+}
+
+impl FileMetadata {
+    /// A new item for a file in the blob store
+    fn stored_item(self) -> ItemActiveModel {
+        ItemActiveModel {
+            title: Set(self.title),
+            content: Set(None), // Files don't have content, just metadata
+            item_type: Set("file".to_string()),
+            tags: Set(None),
+            source_type: Set(Some("file".to_string())),
+            source_url: Set(Some(self.final_path)),
+            mime_type: Set(self.mime_type),
+            file_size: Set(Some(self.file_size as i64)),
+            file_modified_at: Set(Some(self.file_modified_at)),
+            metadata: Set(None), // Can be used for additional file metadata in the future
+            content_hash: Set(Some(self.content_hash)),
+            original_filename: Set(Some(self.original_filename)),
+            ..Default::default()
+        }
+    }
+
+    /// A new item for a file left where it is
+    fn linked_item(self, linked: &LinkedFile) -> ItemActiveModel {
+        ItemActiveModel {
+            title: Set(self.title),
+            item_type: Set("file".to_string()),
+            source_type: Set(Some(linked_files::LINK_SOURCE_TYPE.to_string())),
+            source_url: Set(Some(self.final_path)),
+            mime_type: Set(self.mime_type),
+            file_size: Set(Some(self.file_size as i64)),
+            file_modified_at: Set(Some(self.file_modified_at)),
+            metadata: Set(Some(
+                serde_json::json!({ "linked_file": linked }).to_string(),
+            )),
+            original_filename: Set(Some(self.original_filename)),
+            ..Default::default()
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
+/// Sent by the frontend as `{ filePath, operation, onDuplicate }`. Tauri only renames a
+/// command's own arguments, not the fields of a struct passed as one, so the casing is ours.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FileOperationRequest {
     pub file_path: String,
-    pub operation: String, // "copy" or "move"
+    pub operation: String, // "copy", "move" or "link"
+    #[serde(default)]
+    pub on_duplicate: DuplicateAction,
+}
+
+/// What to do with a file whose contents are already stored for another item.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateAction {
+    /// Return the existing item instead of creating another.
+    #[default]
+    Link,
+    /// Create a new item sharing the stored contents.
+    KeepBoth,
 }
 
 pub struct FileProcessor;
@@ -60,8 +124,14 @@ impl FileProcessor {
         Ok(junkdrawer_files)
     }
 
-    /// Extract basic file metadata
-    fn extract_metadata(&self, source_path: &Path, final_path: &Path) -> AnyhowResult<FileMetadata> {
+    /// The content-addressed store under the files directory
+    pub fn blob_store(app_handle: &tauri::AppHandle) -> AnyhowResult<BlobStore> {
+        let dir = Self::get_files_directory(app_handle)?.join("blobs");
+        BlobStore::at(dir).context("Failed to create blob directory")
+    }
+
+    /// Extract basic file metadata; where it is stored is filled in by the caller
+    fn extract_metadata(&self, source_path: &Path) -> AnyhowResult<FileMetadata> {
         let metadata = fs::metadata(source_path)
             .context("Failed to read file metadata")?;
         
@@ -90,66 +160,88 @@ impl FileProcessor {
             mime_type,
             file_size: metadata.len(),
             file_modified_at,
-            final_path: final_path.display().to_string(),
+            final_path: String::new(),
+            content_hash: String::new(),
+            original_filename: file_name.to_string(),
         })
     }
 
-    /// Generate a unique filename if a file with the same name already exists
-    fn generate_unique_filename(&self, target_dir: &Path, filename: &str) -> String {
-        let mut final_name = filename.to_string();
-        let mut counter = 1;
-        
-        while target_dir.join(&final_name).exists() {
-            if let Some(stem) = Path::new(filename).file_stem().and_then(|s| s.to_str()) {
-                if let Some(ext) = Path::new(filename).extension().and_then(|s| s.to_str()) {
-                    final_name = format!("{} ({}).{}", stem, counter, ext);
-                } else {
-                    final_name = format!("{} ({})", stem, counter);
-                }
-            } else {
-                final_name = format!("{} ({})", filename, counter);
-            }
-            counter += 1;
-        }
-        
-        final_name
-    }
-
-    /// Process a file operation (copy or move)
-    pub fn process_file(&self, request: FileOperationRequest, app_handle: &tauri::AppHandle) -> AnyhowResult<FileMetadata> {
+    /// Process a file operation (copy or move) into the blob store. Contents already stored
+    /// aren't written again. The returned `Staged` must be committed once the item is saved,
+    /// which is when a move lets go of the source, or rolled back if it can't be.
+    pub fn process_file(
+        &self,
+        request: &FileOperationRequest,
+        store: &BlobStore,
+    ) -> AnyhowResult<(FileMetadata, Staged)> {
         let source_path = Path::new(&request.file_path);
         
         if !source_path.exists() {
-            return Err(FileError::FileNotFound(request.file_path).into());
+            return Err(FileError::FileNotFound(request.file_path.clone()).into());
         }
         
-        let files_dir = Self::get_files_directory(app_handle)?;
-        
-        let filename = source_path
-            .file_name()
-            .and_then(|name| name.to_str())
-            .ok_or_else(|| FileError::InvalidPath(request.file_path.clone()))?;
-        
-        let unique_filename = self.generate_unique_filename(&files_dir, filename);
-        let target_path = files_dir.join(&unique_filename);
+        // Read what we need from the source before a move takes it away
+        let mut metadata = self.extract_metadata(source_path)?;
         
-        match request.operation.as_str() {
-            "copy" => {
-                fs::copy(source_path, &target_path)
-                    .context("Failed to copy file")?;
-            }
-            "move" => {
-                fs::rename(source_path, &target_path)
-                    .context("Failed to move file")?;
-            }
+        let staged = match request.operation.as_str() {
+            "copy" => store.copy_in(source_path).context("Failed to copy file")?,
+            "move" => store.move_in(source_path).context("Failed to move file")?,
             _ => {
                 return Err(FileError::FileOperationError(
                     format!("Invalid operation: {}", request.operation)
                 ).into());
             }
-        }
+        };
         
-        self.extract_metadata(source_path, &target_path)
+        metadata.file_size = staged.blob.size;
+        metadata.final_path = staged.blob.path.display().to_string();
+        metadata.content_hash = staged.blob.hash.clone();
+        Ok((metadata, staged))
+    }
+
+    /// Index a file where it is, without storing it (the "link" operation). Its hash and
+    /// fingerprint are returned so it can be checked on and found again if it moves.
+    pub fn link_file(
+        &self,
+        request: &FileOperationRequest,
+    ) -> AnyhowResult<(FileMetadata, LinkedFile)> {
+        let source_path = fs::canonicalize(&request.file_path)
+            .map_err(|_| FileError::FileNotFound(request.file_path.clone()))?;
+        let mut metadata = self.extract_metadata(&source_path)?;
+        let linked = linked_files::link(&source_path).context("Failed to read file")?;
+        metadata.final_path = source_path.display().to_string();
+        metadata.content_hash = linked.hash.clone();
+        Ok((metadata, linked))
+    }
+
+    /// Store downloaded bytes in the blob store, keeping `filename` as the original name. The
+    /// write happens on the blocking pool, since a download can be large. The blob is returned
+    /// too, to be released if no item ends up holding it.
+    pub async fn save_download(
+        &self,
+        bytes: Vec<u8>,
+        filename: &str,
+        mime_type: &str,
+        app_handle: &tauri::AppHandle,
+    ) -> AnyhowResult<(FileMetadata, StoredBlob)> {
+        let store = Self::blob_store(app_handle)?;
+
+        let name = filename.to_string();
+        let blob = tokio::task::spawn_blocking(move || store.put_bytes(&bytes, &name))
+            .await
+            .context("File operation was interrupted")?
+            .context("Failed to write downloaded file")?;
+
+        let metadata = FileMetadata {
+            title: filename.to_string(),
+            mime_type: Some(mime_type.to_string()),
+            file_size: blob.size,
+            file_modified_at: chrono::Utc::now().naive_utc(),
+            final_path: blob.path.display().to_string(),
+            content_hash: blob.hash.clone(),
+            original_filename: filename.to_string(),
+        };
+        Ok((metadata, blob))
     }
 }
 
@@ -157,4 +249,98 @@ impl Default for FileProcessor {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+/// Turn a file into an item: stored in `store` for "copy" and "move", left where it is for
+/// "link". With `DuplicateAction::Link`, contents already stored, or a path already linked,
+/// return the existing item instead, with `true` to say so, and a move leaves its source where
+/// it was. Otherwise a move lets go of its source only once the item is saved, and is put back
+/// if it can't be. Reading and copying the file happen on the blocking pool, off the async
+/// runtime.
+pub async fn import_file(
+    db: &DatabaseConnection,
+    store: &BlobStore,
+    request: &FileOperationRequest,
+) -> Result<(ItemModel, bool), String> {
+    if request.operation == "link" {
+        let (metadata, linked) = blocking(request, store, |request, _| {
+            FileProcessor::new().link_file(request)
+        })
+        .await
+        .map_err(|e| format!("Failed to link file: {}", e))?;
+        if request.on_duplicate == DuplicateAction::Link {
+            if let Some(existing) = linked_files::find_linked(db, &metadata.final_path)
+                .await
+                .map_err(|e| e.to_string())?
+            {
+                return Ok((existing, true));
+            }
+        }
+        return insert_item(db, metadata.linked_item(&linked))
+            .await
+            .map(|item| (item, false))
+            .map_err(|e| e.to_string());
+    }
+
+    // Process file (copy or move); a move keeps its source until the item is saved
+    let _storing = blobs::hold().await;
+    let (metadata, staged) = blocking(request, store, |request, store| {
+        FileProcessor::new().process_file(request, store)
+    })
+    .await
+    .map_err(|e| format!("Failed to process file: {}", e))?;
+
+    if request.on_duplicate == DuplicateAction::Link {
+        if let Some(existing) = blobs::find_duplicate(db, &metadata.content_hash)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            // Nothing was imported, so the file stays where it was
+            undo_staged(db, staged).await;
+            return Ok((existing, true));
+        }
+    }
+
+    match insert_item(db, metadata.stored_item()).await {
+        Ok(item) => {
+            finish_staged(staged);
+            Ok((item, false))
+        }
+        Err(e) => {
+            undo_staged(db, staged).await;
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Run a file operation on the blocking pool.
+pub async fn blocking<T: Send + 'static>(
+    request: &FileOperationRequest,
+    store: &BlobStore,
+    operation: impl FnOnce(&FileOperationRequest, &BlobStore) -> AnyhowResult<T> + Send + 'static,
+) -> AnyhowResult<T> {
+    let (request, store) = (request.clone(), store.clone());
+    tokio::task::spawn_blocking(move || operation(&request, &store))
+        .await
+        .context("File operation was interrupted")?
+}
+
+/// Put a moved file back, and leave contents no item points at to be collected. Only logged,
+/// since the caller is already reporting what went wrong.
+pub async fn undo_staged(db: &DatabaseConnection, staged: Staged) {
+    let blob = staged.blob.clone();
+    if let Err(e) = staged.rollback() {
+        eprintln!("Failed to roll back stored file: {}", e);
+    }
+    if let Err(e) = blobs::release(db, &blob).await {
+        eprintln!("Failed to mark {} unused: {}", blob.path.display(), e);
+    }
+}
+
+/// Let go of a move's source now that an item holds its contents. The item is saved either
+/// way, so a source that can't be removed is only logged.
+fn finish_staged(staged: Staged) {
+    if let Err(e) = staged.commit() {
+        eprintln!("Failed to remove moved file's source: {}", e);
+    }
+}
@@ -0,0 +1,102 @@
+//! Instapaper's CSV export: `URL`, `Title`, `Selection` (a highlight, often empty), `Folder` and
+//! a Unix `Timestamp`, plus a `Tags` column holding a JSON array in newer exports. "Unread",
+//! "Archive" and "Starred" are built-in folders; anything else is one the user made.
+
+use super::{bookmark, read_csv, timestamp, ImportError, ImportFormat, ReadingState};
+use crate::db_commands::CreateItemRequest;
+
+pub fn parse(csv: &str) -> Result<Vec<CreateItemRequest>, ImportError> {
+    Ok(read_csv(csv, "url")?
+        .iter()
+        .map(|row| {
+            let folder = row.get("folder").unwrap_or_default();
+            let mut tags = row
+                .get("tags")
+                .and_then(|tags| serde_json::from_str::<Vec<String>>(tags).ok())
+                .unwrap_or_default();
+            let state = match folder.to_lowercase().as_str() {
+                "unread" => ReadingState {
+                    archived: Some(false),
+                    favorite: None,
+                },
+                "archive" => ReadingState {
+                    archived: Some(true),
+                    favorite: None,
+                },
+                "starred" => ReadingState {
+                    archived: None,
+                    favorite: Some(true),
+                },
+                _ => {
+                    tags.insert(0, folder.to_string());
+                    ReadingState {
+                        archived: Some(false),
+                        favorite: None,
+                    }
+                }
+            };
+
+            bookmark(
+                ImportFormat::Instapaper,
+                row.get("url").unwrap_or_default(),
+                row.get("title"),
+                row.get("selection"),
+                tags,
+                row.get("timestamp").and_then(timestamp),
+                state,
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::import_fixture;
+
+    #[test]
+    fn reads_the_csv_export() {
+        let entries = parse(&import_fixture("instapaper.csv")).expect("parse");
+        let summary = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.title.as_str(),
+                    entry.tags.as_deref(),
+                    entry.metadata.as_deref().unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "Saved for later",
+                    Some("reading"),
+                    r#"{"archived":false,"edited_fields":["title","content"],"imported_from":"instapaper"}"#
+                ),
+                (
+                    "Starred one",
+                    None,
+                    r#"{"edited_fields":["title"],"favorite":true,"imported_from":"instapaper"}"#
+                ),
+                (
+                    "Archived one",
+                    None,
+                    r#"{"archived":true,"edited_fields":["title"],"imported_from":"instapaper"}"#
+                ),
+                (
+                    "In a folder",
+                    Some("Cooking"),
+                    r#"{"archived":false,"edited_fields":["title"],"imported_from":"instapaper"}"#
+                ),
+            ]
+        );
+        assert_eq!(entries[0].content.as_deref(), Some("A highlighted bit"));
+        assert_eq!(
+            entries[0].created_at,
+            chrono::DateTime::from_timestamp(1_600_000_000, 0).map(|date| date.naive_utc())
+        );
+    }
+}
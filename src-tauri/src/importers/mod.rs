@@ -0,0 +1,769 @@
+//! Bringing bookmarks in from other tools. Each format has a parser that turns an export into
+//! `CreateItemRequest`s; everything after that, from deduplication to enrichment, is shared.
+
+use chrono::{NaiveDateTime, Utc};
+use futures::stream::{self, StreamExt};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tauri::Emitter;
+use thiserror::Error;
+
+use crate::bookmarks::{canonicalize, merge_metadata, BookmarkProcessor, Capture, HostLimiter};
+use crate::db_commands::CreateItemRequest;
+use crate::entities::{item, Item, ItemActiveModel};
+use crate::indexer::{self, IndexOperation, IndexerState};
+use crate::refresher;
+
+mod instapaper;
+mod netscape;
+mod pinboard;
+mod pocket;
+mod raindrop;
+
+/// Rows per transaction; big enough to be fast, small enough not to hold the write lock long.
+const INSERT_BATCH_SIZE: usize = 200;
+const CONCURRENT_ENRICHMENTS: usize = 2;
+/// Gap between enrichment requests to one host; an import is mostly a handful of big sites.
+const ENRICH_HOST_INTERVAL: Duration = Duration::from_secs(1);
+
+pub const IMPORT_PROGRESS_EVENT: &str = "bookmark-import-progress";
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("Unrecognized export format")]
+    UnknownFormat,
+    #[error("Export is missing the \"{0}\" column")]
+    MissingColumn(&'static str),
+    #[error("Invalid CSV: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// `bookmarks.html` from any browser.
+    Browser,
+    /// Pocket's original `ril_export.html`.
+    PocketHtml,
+    /// Pocket's later `part_000000.csv`.
+    PocketCsv,
+    /// Pinboard's JSON backup.
+    Pinboard,
+    Raindrop,
+    Instapaper,
+}
+
+impl ImportFormat {
+    /// Tell the format from the export itself, since services don't agree on file names.
+    pub fn detect(contents: &str) -> Option<Self> {
+        let contents = contents.trim_start_matches('\u{feff}').trim_start();
+        if contents.starts_with('[') {
+            return Some(Self::Pinboard);
+        }
+        if contents.starts_with('<') {
+            let head = contents
+                .chars()
+                .take(1024)
+                .collect::<String>()
+                .to_lowercase();
+            return Some(if head.contains("<title>pocket export</title>") {
+                Self::PocketHtml
+            } else {
+                Self::Browser
+            });
+        }
+
+        let header = contents.lines().next()?.to_lowercase();
+        let columns = header
+            .split(',')
+            .map(|column| column.trim().trim_matches('"'))
+            .collect::<HashSet<_>>();
+        if columns.contains("time_added") {
+            Some(Self::PocketCsv)
+        } else if columns.contains("excerpt") && columns.contains("folder") {
+            Some(Self::Raindrop)
+        } else if columns.contains("selection") && columns.contains("folder") {
+            Some(Self::Instapaper)
+        } else {
+            None
+        }
+    }
+
+    /// Name stored as `imported_from` in the metadata of items this format brings in.
+    fn source(self) -> &'static str {
+        match self {
+            Self::Browser => "browser",
+            Self::PocketHtml | Self::PocketCsv => "pocket",
+            Self::Pinboard => "pinboard",
+            Self::Raindrop => "raindrop",
+            Self::Instapaper => "instapaper",
+        }
+    }
+}
+
+/// Turn an export into entries ready for `import`.
+pub fn parse(format: ImportFormat, contents: &str) -> Result<Vec<CreateItemRequest>, ImportError> {
+    let contents = contents.trim_start_matches('\u{feff}');
+    match format {
+        ImportFormat::Browser => Ok(netscape::parse(contents)),
+        ImportFormat::PocketHtml => Ok(pocket::parse_html(contents)),
+        ImportFormat::PocketCsv => pocket::parse_csv(contents),
+        ImportFormat::Pinboard => pinboard::parse(contents),
+        ImportFormat::Raindrop => raindrop::parse(contents),
+        ImportFormat::Instapaper => instapaper::parse(contents),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Not an `http(s)` URL, e.g. a bookmarklet or a browser-internal page.
+    UnsupportedUrl,
+    AlreadySaved,
+    DuplicateInImport,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkippedEntry {
+    pub title: String,
+    pub url: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportedEntry {
+    pub title: String,
+    pub url: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub format: Option<ImportFormat>,
+    /// Nothing was saved; `created` and `added` say what would have been.
+    pub dry_run: bool,
+    /// Entries found in the file.
+    pub found: usize,
+    pub created: usize,
+    pub added: Vec<ImportedEntry>,
+    pub skipped: Vec<SkippedEntry>,
+    /// Whether metadata is being fetched for the new bookmarks in the background.
+    pub enriching: bool,
+    #[serde(skip)]
+    pub created_ids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EnrichmentProgress {
+    pub total: usize,
+    pub processed: usize,
+    pub enriched: usize,
+    pub failed: usize,
+    pub finished: bool,
+}
+
+/// Save parsed entries as new items, skipping URLs already in the library or repeated in the
+/// import. Items are queued for indexing in the same transaction as their batch. A dry run
+/// reports the same outcome without writing anything.
+pub async fn import(
+    db: &DatabaseConnection,
+    entries: Vec<CreateItemRequest>,
+    dry_run: bool,
+) -> Result<ImportReport, DbErr> {
+    let mut report = ImportReport {
+        dry_run,
+        found: entries.len(),
+        ..Default::default()
+    };
+    let mut seen = HashSet::new();
+
+    for batch in entries.chunks(INSERT_BATCH_SIZE) {
+        let keyed = batch
+            .iter()
+            .map(|entry| {
+                let key = entry.source_url.as_deref().and_then(canonicalize);
+                (entry, key)
+            })
+            .collect::<Vec<_>>();
+
+        let existing = Item::find()
+            .filter(
+                item::Column::CanonicalUrl.is_in(keyed.iter().filter_map(|(_, key)| key.clone())),
+            )
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|item| item.canonical_url)
+            .collect::<HashSet<_>>();
+
+        let mut fresh = Vec::new();
+        for (entry, key) in keyed {
+            let skip = |reason| SkippedEntry {
+                title: entry.title.clone(),
+                url: entry.source_url.clone().unwrap_or_default(),
+                reason,
+            };
+            let Some(key) = key else {
+                report.skipped.push(skip(SkipReason::UnsupportedUrl));
+                continue;
+            };
+            if existing.contains(&key) {
+                report.skipped.push(skip(SkipReason::AlreadySaved));
+                continue;
+            }
+            if !seen.insert(key.clone()) {
+                report.skipped.push(skip(SkipReason::DuplicateInImport));
+                continue;
+            }
+
+            report.added.push(ImportedEntry {
+                title: entry.title.clone(),
+                url: entry.source_url.clone().unwrap_or_default(),
+            });
+            fresh.push((entry, key));
+        }
+
+        if dry_run || fresh.is_empty() {
+            continue;
+        }
+        let now = Utc::now().naive_utc();
+        let txn = db.begin().await?;
+        for (entry, key) in fresh {
+            let item = new_item(entry.clone(), Some(key), now).insert(&txn).await?;
+            indexer::enqueue(&txn, item.id, IndexOperation::Upsert).await?;
+            report.created_ids.push(item.id);
+        }
+        txn.commit().await?;
+    }
+
+    report.created = report.added.len();
+    Ok(report)
+}
+
+fn new_item(
+    entry: CreateItemRequest,
+    canonical_url: Option<String>,
+    now: NaiveDateTime,
+) -> ItemActiveModel {
+    // Refreshes count from the import, not from when the entry was first saved elsewhere
+    let metadata = refresher::mark_imported(entry.metadata.as_deref(), now);
+    let mut item = ItemActiveModel {
+        title: Set(entry.title),
+        content: Set(entry.content),
+        item_type: Set(entry.item_type),
+        tags: Set(entry.tags),
+        source_type: Set(entry.source_type),
+        source_url: Set(entry.source_url),
+        metadata: Set(metadata),
+        canonical_url: Set(canonical_url),
+        ..Default::default()
+    };
+    if let Some(created_at) = entry.created_at {
+        item.created_at = Set(created_at);
+        item.updated_at = Set(created_at);
+    }
+    item
+}
+
+/// Fetch page metadata for freshly imported bookmarks in the background, emitting
+/// `IMPORT_PROGRESS_EVENT` after each one.
+pub fn start_enrichment(
+    app: tauri::AppHandle,
+    db: DatabaseConnection,
+    indexer: IndexerState,
+    ids: Vec<i32>,
+) {
+    tauri::async_runtime::spawn(async move {
+        let processor = BookmarkProcessor::with_saved_profiles(&db).await;
+        let limiter = HostLimiter::new(ENRICH_HOST_INTERVAL);
+        enrich(&db, &processor, &limiter, ids, |progress| {
+            indexer.wake();
+            let _ = app.emit(IMPORT_PROGRESS_EVENT, progress);
+        })
+        .await;
+    });
+}
+
+pub async fn enrich(
+    db: &DatabaseConnection,
+    processor: &BookmarkProcessor,
+    limiter: &HostLimiter,
+    ids: Vec<i32>,
+    on_progress: impl Fn(&EnrichmentProgress),
+) -> EnrichmentProgress {
+    let mut progress = EnrichmentProgress {
+        total: ids.len(),
+        ..Default::default()
+    };
+
+    let mut results = stream::iter(ids)
+        .map(|id| enrich_one(db, processor, limiter, id))
+        .buffer_unordered(CONCURRENT_ENRICHMENTS);
+    while let Some(result) = results.next().await {
+        progress.processed += 1;
+        match result {
+            Ok(true) => progress.enriched += 1,
+            Ok(false) => {}
+            Err(e) => {
+                progress.failed += 1;
+                eprintln!("Failed to enrich imported bookmark: {}", e);
+            }
+        }
+        on_progress(&progress);
+    }
+
+    progress.finished = true;
+    on_progress(&progress);
+    progress
+}
+
+/// Fill in an imported bookmark from its page. What the import brought along, like the title
+/// someone gave it in their browser, takes precedence over what the page says.
+async fn enrich_one(
+    db: &DatabaseConnection,
+    processor: &BookmarkProcessor,
+    limiter: &HostLimiter,
+    id: i32,
+) -> Result<bool, String> {
+    let Some(item) = Item::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(false);
+    };
+    let Some(url) = item.source_url.clone() else {
+        return Ok(false);
+    };
+
+    limiter.wait_for_url(&url).await;
+    let Capture::Page(page) = processor.capture(&url).await.map_err(|e| e.to_string())? else {
+        return Ok(false);
+    };
+
+    let details = serde_json::to_string(&page.details).map_err(|e| e.to_string())?;
+    let title = if item.title.trim().is_empty() || item.title == url {
+        page.title
+    } else {
+        item.title.clone()
+    };
+    let content = item.content.clone().or(page.article).or(page.description);
+    let now = Utc::now().naive_utc();
+    let metadata = merge_metadata(item.metadata.as_deref(), Some(&details));
+    let metadata = refresher::mark_refreshed(metadata.as_deref(), now);
+
+    let mut active: ItemActiveModel = item.into();
+    active.title = Set(title);
+    active.content = Set(content);
+    active.metadata = Set(metadata);
+    active.updated_at = Set(now);
+
+    let txn = db.begin().await.map_err(|e| e.to_string())?;
+    let updated = active.update(&txn).await.map_err(|e| e.to_string())?;
+    indexer::enqueue(&txn, updated.id, IndexOperation::Upsert)
+        .await
+        .map_err(|e| e.to_string())?;
+    txn.commit().await.map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// A bookmark entry as every importer produces it; a missing title falls back to the URL. The
+/// title and content an export supplies are marked as edited so refreshes keep them.
+fn bookmark(
+    format: ImportFormat,
+    url: &str,
+    title: Option<&str>,
+    content: Option<&str>,
+    tags: Vec<String>,
+    created_at: Option<NaiveDateTime>,
+    state: ReadingState,
+) -> CreateItemRequest {
+    let url = url.trim().to_string();
+    let title = title
+        .map(|title| title.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|title| !title.is_empty());
+    let content = content
+        .map(str::trim)
+        .filter(|content| !content.is_empty())
+        .map(str::to_string);
+    let mut supplied = Vec::new();
+    if title.is_some() {
+        supplied.push("title");
+    }
+    if content.is_some() {
+        supplied.push("content");
+    }
+
+    let mut metadata = serde_json::Map::new();
+    metadata.insert("imported_from".to_string(), format.source().into());
+    if let Some(archived) = state.archived {
+        metadata.insert("archived".to_string(), archived.into());
+    }
+    if let Some(favorite) = state.favorite {
+        metadata.insert("favorite".to_string(), favorite.into());
+    }
+
+    CreateItemRequest {
+        title: title.unwrap_or_else(|| url.clone()),
+        content,
+        item_type: "bookmark".to_string(),
+        tags: join_tags(tags),
+        source_type: Some("bookmark".to_string()),
+        source_url: Some(url),
+        created_at,
+        metadata: refresher::mark_edited(
+            Some(&serde_json::Value::Object(metadata).to_string()),
+            &supplied,
+        ),
+    }
+}
+
+/// Where an entry stood in a read-later service. `archived` means it has been read and filed
+/// away; services that have no such notion leave both unknown.
+#[derive(Debug, Default, Clone, Copy)]
+struct ReadingState {
+    archived: Option<bool>,
+    favorite: Option<bool>,
+}
+
+/// A CSV row keyed by lowercased column name.
+struct CsvRow(HashMap<String, String>);
+
+impl CsvRow {
+    /// The trimmed value of `column`, if present and not blank.
+    fn get(&self, column: &str) -> Option<&str> {
+        self.0
+            .get(column)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Read a CSV export with a header row, insisting on a `url_column`.
+fn read_csv(contents: &str, url_column: &'static str) -> Result<Vec<CsvRow>, ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+    let columns = reader
+        .headers()?
+        .iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    if !columns.iter().any(|column| column == url_column) {
+        return Err(ImportError::MissingColumn(url_column));
+    }
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            Ok(CsvRow(
+                columns
+                    .iter()
+                    .cloned()
+                    .zip(record.iter().map(str::to_string))
+                    .collect(),
+            ))
+        })
+        .collect()
+}
+
+/// Join tag names into the comma-separated `tags` column, dropping blanks and repeats.
+fn join_tags(tags: impl IntoIterator<Item = String>) -> Option<String> {
+    let mut seen = HashSet::new();
+    let tags = tags
+        .into_iter()
+        // A comma would split one tag into two
+        .map(|tag| {
+            tag.replace(',', " ")
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.to_lowercase()))
+        .collect::<Vec<_>>();
+    (!tags.is_empty()).then(|| tags.join(", "))
+}
+
+/// A Unix timestamp in seconds, milliseconds or microseconds, as exporters disagree.
+fn timestamp(value: &str) -> Option<NaiveDateTime> {
+    let value = value
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .filter(|value| *value > 0)?;
+    let seconds = match value {
+        // Past the year 5000 in seconds, so it must be a finer unit
+        0..=99_999_999_999 => value,
+        100_000_000_000..=99_999_999_999_999 => value / 1_000,
+        _ => value / 1_000_000,
+    };
+    chrono::DateTime::from_timestamp(seconds, 0).map(|date| date.naive_utc())
+}
+
+/// A Unix timestamp or an RFC 3339 date, the two ways exports write times.
+fn datetime(value: &str) -> Option<NaiveDateTime> {
+    timestamp(value).or_else(|| {
+        chrono::DateTime::parse_from_rfc3339(value.trim())
+            .ok()
+            .map(|date| date.naive_utc())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{import_fixture, test_database, HttpStub, StubResponse};
+
+    fn entry(title: &str, url: &str) -> CreateItemRequest {
+        CreateItemRequest {
+            title: title.to_string(),
+            content: None,
+            item_type: "bookmark".to_string(),
+            tags: None,
+            source_type: Some("bookmark".to_string()),
+            source_url: Some(url.to_string()),
+            created_at: None,
+            metadata: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn imports_a_browser_export() {
+        let db = test_database("import-netscape").await;
+        ItemActiveModel {
+            title: Set("Already here".to_string()),
+            item_type: Set("bookmark".to_string()),
+            source_url: Set(Some("https://example.com/café".to_string())),
+            canonical_url: Set(canonicalize("https://example.com/café")),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .expect("existing bookmark");
+
+        let entries =
+            parse(ImportFormat::Browser, &import_fixture("bookmarks.html")).expect("parse");
+        let report = import(&db, entries, false).await.expect("import");
+
+        assert_eq!(report.found, 7);
+        assert_eq!(report.created, 4);
+        assert_eq!(
+            report
+                .skipped
+                .iter()
+                .map(|skipped| (skipped.title.as_str(), skipped.reason))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Bookmarklet", SkipReason::UnsupportedUrl),
+                ("Rust again", SkipReason::DuplicateInImport),
+                ("Café & bar", SkipReason::AlreadySaved),
+            ]
+        );
+
+        let rust = Item::find_by_id(report.created_ids[0])
+            .one(&db)
+            .await
+            .expect("query")
+            .expect("item");
+        assert_eq!(rust.title, "Rust Programming Language");
+        assert_eq!(
+            rust.created_at,
+            chrono::DateTime::from_timestamp(1_600_000_100, 0)
+                .expect("timestamp")
+                .naive_utc()
+        );
+        assert_eq!(
+            rust.canonical_url.as_deref(),
+            Some("https://rust-lang.org/")
+        );
+
+        // Importing the same file again finds nothing new
+        let report = import(&db, vec![entry("Rust", "https://rust-lang.org")], false)
+            .await
+            .expect("import again");
+        assert_eq!(report.created, 0);
+        assert_eq!(report.skipped[0].reason, SkipReason::AlreadySaved);
+    }
+
+    #[tokio::test]
+    async fn enrichment_fills_in_from_the_page() {
+        let stub = HttpStub::start(vec![
+            (
+                "/article",
+                StubResponse::new(
+                    200,
+                    "<html><head><title>From the page</title>\
+                     <meta name=\"description\" content=\"Page summary\"></head></html>",
+                )
+                .header("Content-Type", "text/html"),
+            ),
+            (
+                "/untitled",
+                StubResponse::new(200, "<title>From the page</title>")
+                    .header("Content-Type", "text/html"),
+            ),
+            ("/missing", StubResponse::new(500, "oops")),
+        ])
+        .await;
+        let db = test_database("import-enrich").await;
+        let mut titled = entry("My own title", &stub.url("/article"));
+        titled.metadata = Some(r#"{"imported_from":"browser"}"#.to_string());
+        let untitled = entry(&stub.url("/untitled"), &stub.url("/untitled"));
+        let broken = entry("Broken", &stub.url("/missing"));
+        let report = import(&db, vec![titled, untitled, broken], false)
+            .await
+            .expect("import");
+
+        let events = std::sync::Mutex::new(Vec::new());
+        let progress = enrich(
+            &db,
+            &BookmarkProcessor::new(),
+            &HostLimiter::new(Duration::from_millis(1)),
+            report.created_ids.clone(),
+            |progress| events.lock().expect("events").push(progress.clone()),
+        )
+        .await;
+
+        assert_eq!(progress.total, 3);
+        assert_eq!(progress.processed, 3);
+        assert!(progress.finished);
+        let events = events.into_inner().expect("events");
+        assert_eq!(events.len(), 4);
+        assert_eq!(events.last(), Some(&progress));
+
+        let items = Item::find().all(&db).await.expect("items");
+        let titled = &items[0];
+        assert_eq!(titled.title, "My own title");
+        assert_eq!(titled.content.as_deref(), Some("Page summary"));
+        let metadata: serde_json::Value =
+            serde_json::from_str(titled.metadata.as_deref().expect("metadata")).expect("json");
+        assert_eq!(metadata["imported_from"], "browser");
+        assert_eq!(metadata["description"], "Page summary");
+        assert_eq!(items[1].title, "From the page");
+        assert_eq!(items[2].content, None);
+    }
+
+    #[tokio::test]
+    async fn refreshes_wait_for_imports_and_keep_what_they_brought() {
+        let stub = HttpStub::start(vec![(
+            "/old",
+            StubResponse::new(
+                200,
+                "<html><head><title>From the page</title>\
+                 <meta name=\"description\" content=\"Page summary\"></head></html>",
+            )
+            .header("Content-Type", "text/html"),
+        )])
+        .await;
+        let db = test_database("import-refresh").await;
+        let export = format!(
+            "<DL><p><DT><A HREF=\"{}\" ADD_DATE=\"1262304000\">My own title</A>\
+             <DD>My notes</DL>",
+            stub.url("/old")
+        );
+        let entries = parse(ImportFormat::Browser, &export).expect("parse");
+        let report = import(&db, entries, false).await.expect("import");
+
+        // Saved in 2010, but only just imported
+        let processor = BookmarkProcessor::new();
+        let stores = refresher::Stores::default();
+        let attempted = refresher::sweep(
+            &db,
+            &processor,
+            &stores,
+            &HostLimiter::new(Duration::from_millis(1)),
+            10,
+            |_| {},
+        )
+        .await
+        .expect("sweep");
+        assert_eq!(attempted, 0);
+        assert!(stub.paths().is_empty());
+
+        let id = report.created_ids[0];
+        refresher::refresh_item(&db, &processor, &stores, id)
+            .await
+            .expect("refresh");
+        let item = Item::find_by_id(id)
+            .one(&db)
+            .await
+            .expect("query")
+            .expect("item");
+        assert_eq!(item.title, "My own title");
+        assert_eq!(item.content.as_deref(), Some("My notes"));
+    }
+
+    #[test]
+    fn detects_formats() {
+        for (name, format) in [
+            ("bookmarks.html", ImportFormat::Browser),
+            ("pocket.html", ImportFormat::PocketHtml),
+            ("pocket.csv", ImportFormat::PocketCsv),
+            ("pinboard.json", ImportFormat::Pinboard),
+            ("raindrop.csv", ImportFormat::Raindrop),
+            ("instapaper.csv", ImportFormat::Instapaper),
+        ] {
+            assert_eq!(
+                ImportFormat::detect(&import_fixture(name)),
+                Some(format),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(ImportFormat::detect("name,address\nAda,London"), None);
+        assert_eq!(ImportFormat::detect(""), None);
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_without_saving() {
+        let db = test_database("import-dry-run").await;
+        let mut entries =
+            parse(ImportFormat::Instapaper, &import_fixture("instapaper.csv")).expect("parse");
+        entries.push(entry("Again", "https://example.com/saved"));
+
+        let report = import(&db, entries.clone(), true).await.expect("dry run");
+        assert!(report.dry_run);
+        assert_eq!(report.found, 5);
+        assert_eq!(report.created, 4);
+        assert_eq!(report.added[0].title, "Saved for later");
+        assert_eq!(report.skipped[0].reason, SkipReason::DuplicateInImport);
+        assert!(report.created_ids.is_empty());
+        assert!(Item::find().all(&db).await.expect("items").is_empty());
+
+        let report = import(&db, entries, false).await.expect("import");
+        assert_eq!(report.created, 4);
+        assert_eq!(report.created_ids.len(), 4);
+        assert_eq!(Item::find().all(&db).await.expect("items").len(), 4);
+    }
+
+    #[test]
+    fn normalizes_tags_and_timestamps() {
+        assert_eq!(
+            join_tags(vec![
+                "Dev".to_string(),
+                " dev ".to_string(),
+                "Web, HTTP".to_string(),
+                "".to_string(),
+            ])
+            .as_deref(),
+            Some("Dev, Web HTTP")
+        );
+        assert_eq!(join_tags(Vec::new()), None);
+
+        let expected = chrono::DateTime::from_timestamp(1_600_000_000, 0).map(|d| d.naive_utc());
+        assert_eq!(timestamp("1600000000"), expected);
+        assert_eq!(timestamp("1600000000000"), expected);
+        assert_eq!(timestamp("1600000000000000"), expected);
+        assert_eq!(timestamp("0"), None);
+        assert_eq!(timestamp("yesterday"), None);
+        assert_eq!(datetime("2020-09-13T12:26:40.000Z"), expected);
+        assert_eq!(datetime("2020-09-13T14:26:40+02:00"), expected);
+    }
+}
@@ -0,0 +1,147 @@
+//! The `bookmarks.html` format every browser exports, a loose HTML dialect from Netscape
+//! Navigator: folders are `<DT><H3>` headings followed by a nested `<DL>`, bookmarks are
+//! `<DT><A>` links, and an optional `<DD>` after a link holds its description.
+
+use scraper::{ElementRef, Html, Selector};
+
+use super::{bookmark, timestamp, ImportFormat, ReadingState};
+use crate::db_commands::CreateItemRequest;
+
+/// Folders every browser creates at the top level. Filing something under "Other bookmarks"
+/// says nothing about it, so these don't become tags.
+const ROOT_FOLDERS: &[&str] = &[
+    "bookmarks bar",
+    "bookmarks toolbar",
+    "bookmarks menu",
+    "favorites",
+    "favorites bar",
+    "mobile bookmarks",
+    "other bookmarks",
+];
+
+pub fn parse(html: &str) -> Vec<CreateItemRequest> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href]").expect("valid selector");
+
+    document
+        .select(&selector)
+        .map(|link| {
+            let element = link.value();
+            let mut tags = folders(link);
+            if let Some(explicit) = element.attr("tags") {
+                tags.extend(explicit.split(',').map(str::to_string));
+            }
+
+            bookmark(
+                ImportFormat::Browser,
+                element.attr("href").unwrap_or_default(),
+                Some(&link.text().collect::<String>()),
+                description(link).as_deref(),
+                tags,
+                element.attr("add_date").and_then(timestamp),
+                ReadingState::default(),
+            )
+        })
+        .collect()
+}
+
+/// Names of the folders containing `link`, outermost first.
+fn folders(link: ElementRef) -> Vec<String> {
+    let mut folders = link
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .filter(|ancestor| ancestor.value().name() == "dl")
+        .filter_map(|list| {
+            let heading = list
+                .prev_siblings()
+                .filter_map(ElementRef::wrap)
+                .next()
+                .filter(|sibling| sibling.value().name() == "h3")?;
+            let name = collapse(&heading.text().collect::<String>());
+            let special = heading.value().attr("personal_toolbar_folder").is_some()
+                || heading.value().attr("unfiled_bookmarks_folder").is_some()
+                || ROOT_FOLDERS.contains(&name.to_lowercase().as_str());
+            (!special && !name.is_empty()).then_some(name)
+        })
+        .collect::<Vec<_>>();
+    folders.reverse();
+    folders
+}
+
+/// The `<DD>` that directly follows the link's `<DT>`, if any.
+fn description(link: ElementRef) -> Option<String> {
+    let term = link
+        .parent()
+        .and_then(ElementRef::wrap)
+        .filter(|parent| parent.value().name() == "dt")?;
+    let next = term.next_siblings().filter_map(ElementRef::wrap).next()?;
+    if next.value().name() != "dd" {
+        return None;
+    }
+    let text = collapse(&next.text().collect::<String>());
+    (!text.is_empty()).then_some(text)
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::import_fixture;
+
+    #[test]
+    fn folders_become_tags() {
+        let entries = parse(&import_fixture("bookmarks.html"));
+        let summary = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.source_url.as_deref().unwrap_or_default(),
+                    entry.tags.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("https://www.rust-lang.org/", None),
+                (
+                    "https://docs.rs/tokio/latest/tokio/?utm_source=newsletter",
+                    Some("Dev")
+                ),
+                (
+                    "https://developer.mozilla.org/en-US/docs/Web/HTTP",
+                    Some("Dev, Web HTTP & APIs, reference, mdn")
+                ),
+                ("javascript:alert('bookmarklet')", None),
+                ("http://rust-lang.org", None),
+                ("https://example.com/caf%C3%A9", None),
+                ("https://example.org/untitled", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_titles_dates_and_descriptions() {
+        let entries = parse(&import_fixture("bookmarks.html"));
+
+        assert_eq!(entries[0].title, "Rust Programming Language");
+        assert_eq!(
+            entries[0].created_at,
+            chrono::DateTime::from_timestamp(1_600_000_100, 0).map(|date| date.naive_utc())
+        );
+        assert_eq!(entries[1].content.as_deref(), Some("Async runtime docs"));
+        assert_eq!(entries[2].content, None);
+        // Some exporters write milliseconds
+        assert_eq!(entries[5].title, "Café & bar");
+        assert_eq!(
+            entries[5].created_at,
+            chrono::DateTime::from_timestamp(1_600_001_100, 0).map(|date| date.naive_utc())
+        );
+        assert_eq!(entries[6].title, "https://example.org/untitled");
+        assert_eq!(entries[6].created_at, None);
+    }
+}
@@ -0,0 +1,72 @@
+//! Pinboard's JSON backup: an array of posts whose `description` is the title, `extended` the
+//! notes, `tags` space-separated, and `toread` "yes" for anything still unread.
+
+use serde::Deserialize;
+
+use super::{bookmark, datetime, ImportError, ImportFormat, ReadingState};
+use crate::db_commands::CreateItemRequest;
+
+#[derive(Deserialize)]
+struct Post {
+    href: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    extended: String,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    time: String,
+    #[serde(default)]
+    toread: String,
+}
+
+pub fn parse(json: &str) -> Result<Vec<CreateItemRequest>, ImportError> {
+    let posts: Vec<Post> = serde_json::from_str(json)?;
+    Ok(posts
+        .into_iter()
+        .map(|post| {
+            bookmark(
+                ImportFormat::Pinboard,
+                &post.href,
+                Some(&post.description),
+                Some(&post.extended),
+                post.tags.split_whitespace().map(str::to_string).collect(),
+                datetime(&post.time),
+                ReadingState {
+                    archived: Some(post.toread != "yes"),
+                    favorite: None,
+                },
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::import_fixture;
+
+    #[test]
+    fn reads_the_json_backup() {
+        let entries = parse(&import_fixture("pinboard.json")).expect("parse");
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "Pinned page");
+        assert_eq!(entries[0].content.as_deref(), Some("Notes about it"));
+        assert_eq!(entries[0].tags.as_deref(), Some("rust, async"));
+        assert_eq!(
+            entries[0].created_at,
+            chrono::DateTime::from_timestamp(1_600_000_000, 0).map(|date| date.naive_utc())
+        );
+        assert_eq!(
+            entries[0].metadata.as_deref(),
+            Some(
+                r#"{"archived":false,"edited_fields":["title","content"],"imported_from":"pinboard"}"#
+            )
+        );
+        assert_eq!(entries[1].title, "https://example.org/read");
+        assert_eq!(entries[1].content, None);
+        assert!(parse("{}").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Pocket exports. The original `ril_export.html` is a list of links under an "Unread" and a
+//! "Read Archive" heading with Unix `time_added` and comma-separated `tags` attributes; the CSV
+//! that replaced it has the same fields as columns, tags separated by `|`.
+
+use scraper::{Html, Selector};
+
+use super::{bookmark, read_csv, timestamp, ImportError, ImportFormat, ReadingState};
+use crate::db_commands::CreateItemRequest;
+
+pub fn parse_html(html: &str) -> Vec<CreateItemRequest> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("h1, a[href]").expect("valid selector");
+
+    let mut archived = false;
+    let mut entries = Vec::new();
+    for element in document.select(&selector) {
+        if element.value().name() == "h1" {
+            archived = element
+                .text()
+                .collect::<String>()
+                .to_lowercase()
+                .contains("archive");
+            continue;
+        }
+
+        let attr = |name| element.value().attr(name).unwrap_or_default();
+        entries.push(bookmark(
+            ImportFormat::PocketHtml,
+            attr("href"),
+            Some(&element.text().collect::<String>()),
+            None,
+            attr("tags").split(',').map(str::to_string).collect(),
+            timestamp(attr("time_added")),
+            ReadingState {
+                archived: Some(archived),
+                favorite: None,
+            },
+        ));
+    }
+    entries
+}
+
+pub fn parse_csv(csv: &str) -> Result<Vec<CreateItemRequest>, ImportError> {
+    Ok(read_csv(csv, "url")?
+        .iter()
+        .map(|row| {
+            bookmark(
+                ImportFormat::PocketCsv,
+                row.get("url").unwrap_or_default(),
+                row.get("title"),
+                None,
+                row.get("tags")
+                    .unwrap_or_default()
+                    .split('|')
+                    .map(str::to_string)
+                    .collect(),
+                row.get("time_added").and_then(timestamp),
+                ReadingState {
+                    archived: row.get("status").map(|status| status == "archive"),
+                    favorite: None,
+                },
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::import_fixture;
+
+    fn metadata(entry: &CreateItemRequest) -> serde_json::Value {
+        serde_json::from_str(entry.metadata.as_deref().expect("metadata")).expect("json")
+    }
+
+    #[test]
+    fn reads_the_html_export() {
+        let entries = parse_html(&import_fixture("pocket.html"));
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].title, "A long read");
+        assert_eq!(entries[0].tags.as_deref(), Some("essays, longform"));
+        assert_eq!(
+            entries[0].created_at,
+            chrono::DateTime::from_timestamp(1_600_000_000, 0).map(|date| date.naive_utc())
+        );
+        assert_eq!(
+            metadata(&entries[0]),
+            serde_json::json!({
+                "imported_from": "pocket",
+                "archived": false,
+                "edited_fields": ["title"],
+            })
+        );
+        assert_eq!(entries[1].title, "https://example.org/no-title");
+        assert_eq!(entries[1].tags, None);
+        assert_eq!(metadata(&entries[2])["archived"], true);
+    }
+
+    #[test]
+    fn reads_the_csv_export() {
+        let entries = parse_csv(&import_fixture("pocket.csv")).expect("parse");
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tags.as_deref(), Some("essays, longform"));
+        assert_eq!(metadata(&entries[0])["archived"], false);
+        assert_eq!(entries[1].title, "Finished, at last");
+        assert_eq!(entries[1].tags, None);
+        assert_eq!(metadata(&entries[1])["archived"], true);
+    }
+}
@@ -0,0 +1,73 @@
+//! Raindrop.io's CSV export. `folder` is the collection path with `/` between levels, `tags` is
+//! comma-separated inside one field, and `created` is an RFC 3339 date.
+
+use super::{bookmark, datetime, read_csv, ImportError, ImportFormat, ReadingState};
+use crate::db_commands::CreateItemRequest;
+
+/// Where Raindrop files anything not put in a collection; as meaningless as a tag as a
+/// browser's "Other bookmarks".
+const UNSORTED: &str = "Unsorted";
+
+pub fn parse(csv: &str) -> Result<Vec<CreateItemRequest>, ImportError> {
+    Ok(read_csv(csv, "url")?
+        .iter()
+        .map(|row| {
+            let mut tags = row
+                .get("folder")
+                .unwrap_or_default()
+                .split('/')
+                .filter(|folder| !folder.trim().eq_ignore_ascii_case(UNSORTED))
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            tags.extend(
+                row.get("tags")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::to_string),
+            );
+
+            bookmark(
+                ImportFormat::Raindrop,
+                row.get("url").unwrap_or_default(),
+                row.get("title"),
+                // The note is the user's own words; the excerpt only what the page said
+                row.get("note").or(row.get("excerpt")),
+                tags,
+                row.get("created").and_then(datetime),
+                ReadingState {
+                    archived: None,
+                    favorite: row.get("favorite").map(|favorite| favorite == "true"),
+                },
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::import_fixture;
+
+    #[test]
+    fn reads_the_csv_export() {
+        let entries = parse(&import_fixture("raindrop.csv")).expect("parse");
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "Raindrop page");
+        assert_eq!(entries[0].content.as_deref(), Some("My note"));
+        assert_eq!(entries[0].tags.as_deref(), Some("Reading, Tech, rust, web"));
+        assert_eq!(
+            entries[0].created_at,
+            chrono::DateTime::from_timestamp(1_600_000_000, 0).map(|date| date.naive_utc())
+        );
+        assert_eq!(
+            entries[0].metadata.as_deref(),
+            Some(
+                r#"{"edited_fields":["title","content"],"favorite":true,"imported_from":"raindrop"}"#
+            )
+        );
+        assert_eq!(entries[1].tags, None);
+        assert_eq!(entries[1].content, None);
+        assert!(parse("id,title\n1,No links").is_err());
+    }
+}
@@ -0,0 +1,581 @@
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+
+use crate::entities::{
+    index_outbox, item, IndexOutbox, IndexOutboxActiveModel, IndexOutboxModel, Item,
+};
+use crate::search::{ReindexReport, SearchBackend, TypesenseBackend};
+use crate::typesense::{self, SchemaStatus, TypesenseError};
+
+const POLL_INTERVAL_SECS: u64 = 5;
+const BATCH_SIZE: u64 = 50;
+const BASE_RETRY_DELAY_SECS: i64 = 2;
+const MAX_RETRY_DELAY_SECS: i64 = 300;
+const REINDEX_PAGE_SIZE: u64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexOperation {
+    Upsert,
+    Delete,
+}
+
+impl IndexOperation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IndexOperation::Upsert => "upsert",
+            IndexOperation::Delete => "delete",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "upsert" => Some(IndexOperation::Upsert),
+            "delete" => Some(IndexOperation::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// Wakes the outbox worker so freshly committed entries are indexed without waiting for the next
+/// poll, and serializes full rebuilds so a manual reindex can't race a schema migration. Also
+/// remembers whether the worker last found Typesense ready to search.
+#[derive(Clone)]
+pub struct IndexerState {
+    notify: Arc<Notify>,
+    rebuild: Arc<Mutex<()>>,
+    typesense_ready: Arc<AtomicBool>,
+}
+
+impl IndexerState {
+    pub fn new() -> Self {
+        Self {
+            notify: Arc::new(Notify::new()),
+            rebuild: Arc::new(Mutex::new(())),
+            typesense_ready: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+
+    /// Whether Typesense was healthy and on the current schema when the worker last checked.
+    pub fn typesense_ready(&self) -> bool {
+        self.typesense_ready.load(Ordering::Relaxed)
+    }
+
+    /// Writes and searches go through the alias, so they are refused until the worker has
+    /// found it pointing at the current schema; the outbox keeps pending writes until then.
+    pub fn ensure_typesense_ready(&self) -> Result<(), TypesenseError> {
+        if self.typesense_ready() {
+            Ok(())
+        } else {
+            Err(TypesenseError::SchemaNotReady)
+        }
+    }
+
+    pub(crate) fn set_typesense_ready(&self, ready: bool) {
+        self.typesense_ready.store(ready, Ordering::Relaxed);
+    }
+}
+
+/// Record a pending index change. Call this with the same transaction that modifies the item so
+/// the outbox entry is committed (or rolled back) together with the row.
+///
+/// The entry replaces any earlier one for the item, so the outbox stays at one entry per item
+/// however long the index is unavailable. It gets a new id, so a drain applying the old one
+/// doesn't remove it, and a rebuild that started before it leaves it to be applied.
+pub async fn enqueue<C: ConnectionTrait>(
+    db: &C,
+    item_id: i32,
+    operation: IndexOperation,
+) -> Result<(), DbErr> {
+    IndexOutbox::delete_many()
+        .filter(index_outbox::Column::ItemId.eq(item_id))
+        .exec(db)
+        .await?;
+    IndexOutboxActiveModel {
+        item_id: Set(item_id),
+        operation: Set(operation.as_str().to_string()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
+
+pub fn start_worker(db: DatabaseConnection, state: IndexerState) {
+    tauri::async_runtime::spawn(async move {
+        let backend = TypesenseBackend::new(state.clone());
+        let mut schema_ready = false;
+
+        loop {
+            if typesense::check_health().await.is_healthy {
+                // Migrate before draining so no write lands in a collection that is about to be
+                // replaced.
+                if !schema_ready {
+                    match migrate_schema(&db, &state).await {
+                        Ok(Some(report)) => {
+                            println!("Rebuilt search index with {} documents", report.indexed)
+                        }
+                        Ok(None) => {}
+                        Err(e) => eprintln!("Failed to migrate search index schema: {}", e),
+                    }
+                    schema_ready =
+                        matches!(typesense::schema_status().await, Ok(SchemaStatus::Current));
+                    state.set_typesense_ready(schema_ready);
+                }
+
+                if schema_ready {
+                    if let Err(e) = drain(&db, &backend, &state).await {
+                        eprintln!("Failed to drain index outbox: {}", e);
+                    }
+                }
+            } else {
+                // A restarted server may come back with an empty data directory
+                schema_ready = false;
+            }
+            // Searches and writes go by this rather than asking the server themselves
+            state.set_typesense_ready(schema_ready);
+
+            tokio::select! {
+                _ = state.notify.notified() => {}
+                _ = tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECS)) => {}
+            }
+        }
+    });
+}
+
+/// Process every outbox entry that is due, returning how many were indexed successfully. Each
+/// batch waits for a rebuild in progress, which would otherwise drop what the batch wrote to the
+/// collection it is about to replace.
+async fn drain(
+    db: &DatabaseConnection,
+    backend: &dyn SearchBackend,
+    state: &IndexerState,
+) -> Result<usize, DbErr> {
+    let mut processed = 0;
+
+    loop {
+        let _rebuild = state.rebuild.lock().await;
+        let entries = IndexOutbox::find()
+            .filter(index_outbox::Column::AvailableAt.lte(Utc::now().naive_utc()))
+            .filter(index_outbox::Column::FailedAt.is_null())
+            .order_by_asc(index_outbox::Column::Id)
+            .limit(BATCH_SIZE)
+            .all(db)
+            .await?;
+
+        if entries.is_empty() {
+            return Ok(processed);
+        }
+
+        let mut failed = false;
+        for entry in entries {
+            // No retry will ever make sense of an unknown operation, so park it for good
+            let Some(operation) = IndexOperation::parse(&entry.operation) else {
+                mark_failed(db, entry).await?;
+                continue;
+            };
+            match apply(db, backend, &entry, operation).await {
+                Ok(()) => {
+                    IndexOutbox::delete_by_id(entry.id).exec(db).await?;
+                    processed += 1;
+                }
+                Err(message) => {
+                    reschedule(db, entry, message).await?;
+                    failed = true;
+                }
+            }
+        }
+
+        // Failed entries are pushed into the future, but stop here rather than hammering a
+        // server that has just started refusing requests.
+        if failed {
+            return Ok(processed);
+        }
+    }
+}
+
+async fn apply(
+    db: &DatabaseConnection,
+    backend: &dyn SearchBackend,
+    entry: &IndexOutboxModel,
+    operation: IndexOperation,
+) -> Result<(), String> {
+    match operation {
+        IndexOperation::Upsert => {
+            let item = Item::find_by_id(entry.item_id)
+                .one(db)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // The item may have been deleted after the upsert was queued
+            match item {
+                Some(item) => backend.upsert(&item).await,
+                None => backend.delete(entry.item_id).await,
+            }
+        }
+        IndexOperation::Delete => backend.delete(entry.item_id).await,
+    }
+    .map_err(|e| e.to_string())
+}
+
+async fn reschedule(
+    db: &DatabaseConnection,
+    entry: IndexOutboxModel,
+    message: String,
+) -> Result<(), DbErr> {
+    let attempts = entry.attempts + 1;
+    let mut active: IndexOutboxActiveModel = entry.into();
+    active.attempts = Set(attempts);
+    active.last_error = Set(Some(message));
+    active.available_at = Set(Utc::now().naive_utc() + retry_delay(attempts));
+    active.update(db).await?;
+    Ok(())
+}
+
+async fn mark_failed(db: &DatabaseConnection, entry: IndexOutboxModel) -> Result<(), DbErr> {
+    eprintln!(
+        "Giving up on index outbox entry {}: unknown operation {}",
+        entry.id, entry.operation
+    );
+    let message = format!("Unknown index operation: {}", entry.operation);
+    let mut active: IndexOutboxActiveModel = entry.into();
+    active.last_error = Set(Some(message));
+    active.failed_at = Set(Some(Utc::now().naive_utc()));
+    active.update(db).await?;
+    Ok(())
+}
+
+fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    let seconds = BASE_RETRY_DELAY_SECS
+        .saturating_mul(2_i64.saturating_pow(exponent))
+        .min(MAX_RETRY_DELAY_SECS);
+    chrono::Duration::seconds(seconds)
+}
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct IndexConsistencyReport {
+    pub item_count: usize,
+    pub document_count: usize,
+    /// Items that have no document in the index.
+    pub missing: Vec<i32>,
+    /// Items whose indexed `updated_at` differs from the row.
+    pub stale: Vec<i32>,
+    /// Documents whose item no longer exists.
+    pub orphaned: Vec<i32>,
+    pub consistent: bool,
+}
+
+/// Rebuild the index from SQLite even if the schema is current, e.g. after the Typesense data
+/// directory was lost.
+pub async fn reindex_all(
+    db: &DatabaseConnection,
+    state: &IndexerState,
+) -> Result<ReindexReport, String> {
+    let _rebuild = state.rebuild.lock().await;
+    let status = typesense::schema_status()
+        .await
+        .map_err(|e| e.to_string())?;
+    rebuild_index(db, status).await
+}
+
+/// Bring the alias up to the current schema version, rebuilding from SQLite if it is behind.
+/// Returns `None` when nothing needed to change.
+pub async fn migrate_schema(
+    db: &DatabaseConnection,
+    state: &IndexerState,
+) -> Result<Option<ReindexReport>, String> {
+    let _rebuild = state.rebuild.lock().await;
+    match typesense::schema_status()
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        SchemaStatus::Current => Ok(None),
+        status => rebuild_index(db, status).await.map(Some),
+    }
+}
+
+/// Fill a freshly named collection from the `items` table, then swap the alias over to it and
+/// drop whatever it pointed at before. The old collection keeps serving searches until the swap.
+async fn rebuild_index(
+    db: &DatabaseConnection,
+    status: SchemaStatus,
+) -> Result<ReindexReport, String> {
+    let collection = typesense::fresh_collection_name();
+    let previous = typesense::alias_target().await.map_err(|e| e.to_string())?;
+
+    // Entries queued up to this point are covered by the rebuild; anything queued while it
+    // runs stays in the outbox and is applied afterwards.
+    let covered_outbox_id = IndexOutbox::find()
+        .order_by_desc(index_outbox::Column::Id)
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|entry| entry.id);
+
+    typesense::create_collection(&collection)
+        .await
+        .map_err(|e| e.to_string())?;
+    let report = match fill_collection(db, &collection).await {
+        Ok(report) => report,
+        Err(e) => {
+            // Nothing points at a half-built collection, so don't leave it lying around
+            if let Err(cleanup) = typesense::drop_collection(&collection).await {
+                eprintln!(
+                    "Failed to drop partial collection {}: {}",
+                    collection, cleanup
+                );
+            }
+            return Err(e);
+        }
+    };
+
+    // A legacy collection owns the alias name, so it has to go before the alias can exist.
+    if status == SchemaStatus::Legacy {
+        typesense::drop_legacy_collection()
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    typesense::swap_alias(&collection)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(previous) = previous.filter(|previous| *previous != collection) {
+        typesense::drop_collection(&previous)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(id) = covered_outbox_id {
+        IndexOutbox::delete_many()
+            .filter(index_outbox::Column::Id.lte(id))
+            .exec(db)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(report)
+}
+
+async fn fill_collection(
+    db: &DatabaseConnection,
+    collection: &str,
+) -> Result<ReindexReport, String> {
+    let mut report = ReindexReport {
+        indexed: 0,
+        failed: Vec::new(),
+    };
+    let mut pages = Item::find()
+        .order_by_asc(item::Column::Id)
+        .paginate(db, REINDEX_PAGE_SIZE);
+
+    while let Some(items) = pages.fetch_and_next().await.map_err(|e| e.to_string())? {
+        let failures = typesense::import_item_documents(collection, &items)
+            .await
+            .map_err(|e| e.to_string())?;
+        report.indexed += items.len() - failures.len();
+        report.failed.extend(failures);
+    }
+    Ok(report)
+}
+
+/// Compare the ids and `updated_at` values in SQLite against the indexed documents.
+pub async fn verify_index(
+    db: &DatabaseConnection,
+    state: &IndexerState,
+) -> Result<IndexConsistencyReport, String> {
+    state.ensure_typesense_ready().map_err(|e| e.to_string())?;
+    let rows = Item::find()
+        .select_only()
+        .column(item::Column::Id)
+        .column(item::Column::UpdatedAt)
+        .into_tuple::<(i32, NaiveDateTime)>()
+        .all(db)
+        .await
+        .map_err(|e| e.to_string())?;
+    let items = rows
+        .into_iter()
+        .map(|(id, updated_at)| (id, updated_at.and_utc().timestamp()))
+        .collect::<HashMap<_, _>>();
+
+    let documents = typesense::export_document_versions()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(diff_versions(&items, &documents))
+}
+
+fn diff_versions(
+    items: &HashMap<i32, i64>,
+    documents: &HashMap<i32, i64>,
+) -> IndexConsistencyReport {
+    let mut report = IndexConsistencyReport {
+        item_count: items.len(),
+        document_count: documents.len(),
+        ..Default::default()
+    };
+
+    for (id, updated_at) in items {
+        match documents.get(id) {
+            None => report.missing.push(*id),
+            Some(indexed) if indexed != updated_at => report.stale.push(*id),
+            Some(_) => {}
+        }
+    }
+    report.orphaned = documents
+        .keys()
+        .filter(|id| !items.contains_key(id))
+        .copied()
+        .collect();
+
+    report.missing.sort_unstable();
+    report.stale.sort_unstable();
+    report.orphaned.sort_unstable();
+    report.consistent =
+        report.missing.is_empty() && report.stale.is_empty() && report.orphaned.is_empty();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::FtsBackend;
+    use crate::test_support::test_database;
+
+    #[test]
+    fn retry_delay_backs_off_and_caps() {
+        assert_eq!(retry_delay(1), chrono::Duration::seconds(2));
+        assert_eq!(retry_delay(2), chrono::Duration::seconds(4));
+        assert_eq!(retry_delay(5), chrono::Duration::seconds(32));
+        assert_eq!(
+            retry_delay(40),
+            chrono::Duration::seconds(MAX_RETRY_DELAY_SECS)
+        );
+    }
+
+    #[test]
+    fn operations_round_trip() {
+        for operation in [IndexOperation::Upsert, IndexOperation::Delete] {
+            assert_eq!(IndexOperation::parse(operation.as_str()), Some(operation));
+        }
+        assert_eq!(IndexOperation::parse("reindex"), None);
+    }
+
+    #[tokio::test]
+    async fn unknown_operations_are_marked_failed_instead_of_retried() {
+        let db = test_database("indexer-unknown-operation").await;
+        let backend = FtsBackend::new(db.clone());
+        let state = IndexerState::new();
+        for operation in ["reindex", "upsert"] {
+            IndexOutboxActiveModel {
+                item_id: Set(1),
+                operation: Set(operation.to_string()),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .expect("enqueue");
+        }
+
+        assert_eq!(drain(&db, &backend, &state).await.expect("drain"), 1);
+        let left = IndexOutbox::find().all(&db).await.expect("outbox");
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].operation, "reindex");
+        assert!(left[0].failed_at.is_some());
+        assert_eq!(left[0].attempts, 0);
+
+        // Parked entries aren't picked up again
+        assert_eq!(drain(&db, &backend, &state).await.expect("drain"), 0);
+    }
+
+    #[tokio::test]
+    async fn entries_collapse_to_the_latest_per_item() {
+        let db = test_database("indexer-collapse").await;
+        for _ in 0..3 {
+            enqueue(&db, 1, IndexOperation::Upsert)
+                .await
+                .expect("enqueue");
+        }
+        let first = IndexOutbox::find()
+            .one(&db)
+            .await
+            .expect("query")
+            .expect("entry");
+        enqueue(&db, 1, IndexOperation::Delete)
+            .await
+            .expect("enqueue");
+        enqueue(&db, 2, IndexOperation::Upsert)
+            .await
+            .expect("enqueue");
+
+        let entries = IndexOutbox::find()
+            .order_by_asc(index_outbox::Column::ItemId)
+            .all(&db)
+            .await
+            .expect("outbox");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, "delete");
+        // A drain finishing with the replaced entry can't take the new one with it
+        assert!(entries[0].id > first.id);
+        assert_eq!(entries[1].item_id, 2);
+    }
+
+    #[tokio::test]
+    async fn entries_queued_during_a_rebuild_wait_for_it() {
+        let db = test_database("indexer-rebuild-wait").await;
+        let backend = FtsBackend::new(db.clone());
+        let state = IndexerState::new();
+
+        // Queued while a rebuild is filling its new collection
+        let rebuilding = state.rebuild.lock().await;
+        enqueue(&db, 7, IndexOperation::Upsert)
+            .await
+            .expect("enqueue");
+        let draining = drain(&db, &backend, &state);
+        tokio::pin!(draining);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut draining)
+                .await
+                .is_err()
+        );
+        assert_eq!(IndexOutbox::find().count(&db).await.expect("count"), 1);
+
+        // Applied once the new collection is in place
+        drop(rebuilding);
+        assert_eq!(draining.await.expect("drain"), 1);
+        assert_eq!(IndexOutbox::find().count(&db).await.expect("count"), 0);
+    }
+
+    #[test]
+    fn diff_reports_missing_stale_and_orphaned() {
+        let items = HashMap::from([(1, 100), (2, 200), (3, 300)]);
+        let documents = HashMap::from([(1, 100), (2, 150), (4, 400)]);
+
+        let report = diff_versions(&items, &documents);
+        assert_eq!(report.item_count, 3);
+        assert_eq!(report.document_count, 3);
+        assert_eq!(report.missing, vec![3]);
+        assert_eq!(report.stale, vec![2]);
+        assert_eq!(report.orphaned, vec![4]);
+        assert!(!report.consistent);
+    }
+
+    #[test]
+    fn identical_versions_are_consistent() {
+        let versions = HashMap::from([(1, 100), (2, 200)]);
+        assert!(diff_versions(&versions, &versions).consistent);
+    }
+}
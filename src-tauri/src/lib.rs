@@ -1,14 +1,29 @@
+mod blobs;
 mod bookmarks;
 mod database;
 mod db_commands;
+mod directory_import;
 mod entities;
+mod feeds;
 mod files;
+mod importers;
+mod indexer;
+mod link_checker;
+mod linked_files;
 mod migration;
+mod refresher;
+mod search;
+#[cfg(test)]
+mod test_support;
 mod typesense;
+mod watcher;
 
 use database::DatabaseState;
-use tauri::Manager;
+use directory_import::DirectoryImports;
+use indexer::IndexerState;
+use tauri::{Manager, RunEvent};
 use typesense::TypesenseState;
+use watcher::WatcherState;
 
 #[tauri::command]
 fn start_typesense_server(app: tauri::AppHandle) -> Result<(), String> {
@@ -16,8 +31,12 @@ fn start_typesense_server(app: tauri::AppHandle) -> Result<(), String> {
 }
 
 #[tauri::command]
-fn stop_typesense_server(app: tauri::AppHandle) -> Result<(), String> {
-    typesense::stop_server(app).map_err(|e| e.to_string())
+async fn stop_typesense_server(app: tauri::AppHandle) -> Result<(), String> {
+    // Waiting for a graceful exit can take seconds; keep it off the async workers
+    tauri::async_runtime::spawn_blocking(move || typesense::stop_server(app))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -25,6 +44,16 @@ fn is_typesense_server_running(app: tauri::AppHandle) -> Result<bool, String> {
     typesense::is_server_running(app).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn typesense_diagnostics(app: tauri::AppHandle) -> Result<typesense::TypesenseDiagnostics, String> {
+    typesense::diagnostics(&app).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn typesense_search_config() -> Result<typesense::SearchConfig, String> {
+    typesense::search_config().map_err(|e| e.to_string())
+}
+
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_sql::Builder::new().build())
@@ -32,17 +61,46 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .manage(TypesenseState::new())
         .manage(DatabaseState::new())
+        .manage(IndexerState::new())
+        .manage(WatcherState::new())
+        .manage(DirectoryImports::new())
         .invoke_handler(tauri::generate_handler![
             start_typesense_server,
             stop_typesense_server,
             is_typesense_server_running,
+            typesense_search_config,
+            typesense_diagnostics,
             db_commands::create_item,
             db_commands::get_all_items,
             db_commands::get_item_by_id,
             db_commands::delete_item,
             db_commands::update_item,
             db_commands::create_bookmark,
-            db_commands::create_file_item
+            db_commands::create_file_item,
+            db_commands::import_directory,
+            db_commands::cancel_directory_import,
+            db_commands::find_duplicate_file,
+            db_commands::import_bookmarks,
+            db_commands::import_bookmarks_html,
+            db_commands::check_bookmark_link,
+            db_commands::refresh_bookmark,
+            db_commands::relink_file,
+            db_commands::subscribe_feed,
+            db_commands::list_feeds,
+            db_commands::set_feed_filters,
+            db_commands::unsubscribe_feed,
+            db_commands::list_fetch_profiles,
+            db_commands::save_fetch_profile,
+            db_commands::import_fetch_cookies,
+            db_commands::delete_fetch_profile,
+            db_commands::add_watched_path,
+            db_commands::list_watched_paths,
+            db_commands::remove_watched_path,
+            db_commands::rescan_watched_path,
+            db_commands::search_items,
+            db_commands::search_facets,
+            db_commands::reindex_all,
+            db_commands::verify_index
         ])
         .setup(|app| {
             let app_handle = app.handle().clone();
@@ -55,6 +113,8 @@ pub fn run() {
 
             // Initialize database in background
             let db_state = app.state::<DatabaseState>().inner().clone();
+            let indexer_state = app.state::<IndexerState>().inner().clone();
+            let watcher_state = app.state::<WatcherState>().inner().clone();
             std::thread::spawn(move || {
                 tauri::async_runtime::block_on(async {
                     match database::DatabaseState::init_database(&app_handle.clone()).await {
@@ -64,6 +124,29 @@ pub fn run() {
                             if let Err(e) = migration::Migrator::up(&conn, None).await {
                                 eprintln!("Failed to run database migrations: {}", e);
                             } else {
+                                indexer::start_worker(conn.clone(), indexer_state.clone());
+                                link_checker::start_worker(app_handle.clone(), conn.clone());
+                                linked_files::start_worker(
+                                    app_handle.clone(),
+                                    conn.clone(),
+                                    indexer_state.clone(),
+                                );
+                                refresher::start_worker(
+                                    app_handle.clone(),
+                                    conn.clone(),
+                                    indexer_state.clone(),
+                                );
+                                feeds::start_worker(
+                                    app_handle.clone(),
+                                    conn.clone(),
+                                    indexer_state.clone(),
+                                );
+                                watcher::start_worker(
+                                    app_handle.clone(),
+                                    conn.clone(),
+                                    indexer_state,
+                                    watcher_state,
+                                );
                                 db_state.set_connection(conn).await;
                                 println!("Database initialized successfully");
                             }
@@ -77,6 +160,19 @@ pub fn run() {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| match event {
+            // Closing the last window requests an exit; stop the sidecar before we go so it
+            // doesn't outlive the app holding its port and data directory. The wait happens off
+            // the main thread, which exits again once the server is gone.
+            RunEvent::ExitRequested { code, api, .. }
+                if typesense::shutdown_in_background(app_handle, code) =>
+            {
+                api.prevent_exit()
+            }
+            // Exits that skip the request still stop it, waiting at most `SHUTDOWN_TIMEOUT`
+            RunEvent::Exit => typesense::shutdown(app_handle),
+            _ => {}
+        });
 }
@@ -0,0 +1,465 @@
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use futures::stream::{self, StreamExt};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::bookmarks::{canonicalize, BookmarkError, BookmarkProcessor, HostLimiter, LinkResponse};
+use crate::entities::{item, Item};
+
+/// Give the app a minute to settle before the first sweep.
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// How long a check result is trusted before the link is requested again.
+const RECHECK_AFTER_DAYS: i64 = 7;
+const SWEEP_BATCH_SIZE: usize = 50;
+const CONCURRENT_CHECKS: usize = 4;
+/// Minimum gap between two requests to the same host, so a sweep never hammers one site.
+const PER_HOST_INTERVAL: Duration = Duration::from_secs(2);
+/// Consecutive failed checks before an unreachable link is declared dead. One flaky night of
+/// Wi-Fi shouldn't mark a whole library dead. Only timeouts, connection errors and 5xx count
+/// towards it; a page behind a login or a rate limit is still there.
+const DEAD_AFTER_FAILURES: u32 = 3;
+
+pub const LINK_CHECKED_EVENT: &str = "bookmark-link-checked";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkState {
+    Ok,
+    /// Resolves, but only after redirecting to a different page.
+    Moved,
+    Dead,
+    /// Couldn't tell this time: a timeout, a server error, or a site refusing robots.
+    Unreachable,
+}
+
+/// The result of the last check, stored under `link_check` in the item's metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkCheck {
+    pub state: LinkState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Where the link redirects to, when that is a different page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Consecutive checks that ended `Unreachable` or `Dead`.
+    #[serde(default)]
+    pub failures: u32,
+    pub checked_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkCheckedEvent {
+    pub item_id: i32,
+    pub link_check: LinkCheck,
+}
+
+pub fn start_worker(app: tauri::AppHandle, db: DatabaseConnection) {
+    tauri::async_runtime::spawn(async move {
+        let limiter = HostLimiter::new(PER_HOST_INTERVAL);
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        loop {
+            // Rebuilt each sweep so edited fetch profiles take effect
+            let processor = BookmarkProcessor::with_saved_profiles(&db).await;
+            let checked = sweep(&db, &processor, &limiter, SWEEP_BATCH_SIZE, |event| {
+                let _ = app.emit(LINK_CHECKED_EVENT, event);
+            })
+            .await;
+            match checked {
+                Ok(0) => {}
+                Ok(count) => println!("Checked {} bookmark links", count),
+                Err(e) => eprintln!("Failed to check bookmark links: {}", e),
+            }
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+        }
+    });
+}
+
+/// Check up to `limit` bookmarks whose last check is missing or stale, least recently checked
+/// first, calling `on_checked` as each one is recorded. Returns how many were checked.
+pub async fn sweep(
+    db: &DatabaseConnection,
+    processor: &BookmarkProcessor,
+    limiter: &HostLimiter,
+    limit: usize,
+    on_checked: impl Fn(&LinkCheckedEvent),
+) -> Result<usize, DbErr> {
+    let due = due_bookmarks(db, Utc::now().naive_utc(), limit).await?;
+    let on_checked = Arc::new(on_checked);
+
+    let results = stream::iter(due)
+        .map(|(id, url, previous)| {
+            let on_checked = on_checked.clone();
+            async move {
+                limiter.wait_for_url(&url).await;
+
+                let response = processor.check_link(&url).await;
+                let link_check = assess(&url, response, previous.as_ref(), Utc::now().naive_utc());
+                record(db, id, &link_check).await?;
+                on_checked(&LinkCheckedEvent {
+                    item_id: id,
+                    link_check,
+                });
+                Ok::<_, DbErr>(())
+            }
+        })
+        .buffer_unordered(CONCURRENT_CHECKS)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut checked = 0;
+    for result in results {
+        result?;
+        checked += 1;
+    }
+    Ok(checked)
+}
+
+/// Check one item right away, regardless of when it was last checked.
+pub async fn check_item(
+    db: &DatabaseConnection,
+    processor: &BookmarkProcessor,
+    id: i32,
+) -> Result<LinkCheck, String> {
+    let item = Item::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Item not found")?;
+    let url = item.source_url.ok_or("Item has no URL to check")?;
+    let previous = item.metadata.as_deref().and_then(previous_check);
+
+    let response = processor.check_link(&url).await;
+    let link_check = assess(&url, response, previous.as_ref(), Utc::now().naive_utc());
+    record(db, id, &link_check)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(link_check)
+}
+
+async fn due_bookmarks(
+    db: &DatabaseConnection,
+    now: NaiveDateTime,
+    limit: usize,
+) -> Result<Vec<(i32, String, Option<LinkCheck>)>, DbErr> {
+    let rows: Vec<(i32, Option<String>, Option<String>)> = Item::find()
+        .select_only()
+        .column(item::Column::Id)
+        .column(item::Column::SourceUrl)
+        .column(item::Column::Metadata)
+        .filter(item::Column::ItemType.eq("bookmark"))
+        .filter(item::Column::SourceUrl.is_not_null())
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    // The last check lives in the metadata JSON, so staleness is worked out here
+    let stale_before = now - ChronoDuration::days(RECHECK_AFTER_DAYS);
+    let mut due = rows
+        .into_iter()
+        .filter_map(|(id, url, metadata)| {
+            let previous = metadata.as_deref().and_then(previous_check);
+            let checked_at = previous.as_ref().map(|check| check.checked_at);
+            if checked_at.is_some_and(|checked_at| checked_at > stale_before) {
+                return None;
+            }
+            Some((checked_at, id, url?, previous))
+        })
+        .collect::<Vec<_>>();
+    // Never-checked links (`None`) sort first
+    due.sort_by_key(|(checked_at, id, _, _)| (*checked_at, *id));
+    Ok(due
+        .into_iter()
+        .take(limit)
+        .map(|(_, id, url, previous)| (id, url, previous))
+        .collect())
+}
+
+fn previous_check(metadata: &str) -> Option<LinkCheck> {
+    let mut metadata: serde_json::Value = serde_json::from_str(metadata).ok()?;
+    serde_json::from_value(metadata.get_mut("link_check")?.take()).ok()
+}
+
+/// Turn a response, or the failure to get one, into a stored check.
+fn assess(
+    url: &str,
+    response: Result<LinkResponse, BookmarkError>,
+    previous: Option<&LinkCheck>,
+    now: NaiveDateTime,
+) -> LinkCheck {
+    let previous_failures = previous.map(|check| check.failures).unwrap_or_default();
+    let mut check = LinkCheck {
+        state: LinkState::Ok,
+        status: None,
+        redirect_url: None,
+        error: None,
+        failures: 0,
+        checked_at: now,
+    };
+    // Only outages can turn into a dead link; a login or rate limit stays up however long it lasts
+    let mut may_die = false;
+
+    match response {
+        Ok(response) => {
+            check.status = Some(response.status);
+            check.state = match response.status {
+                200..=399 => LinkState::Ok,
+                404 | 410 => LinkState::Dead,
+                // Alive but unwilling: logins, bot walls, rate limits and outages
+                401 | 403 | 429 | 500..=599 => LinkState::Unreachable,
+                _ => LinkState::Dead,
+            };
+            may_die = (500..=599).contains(&response.status);
+            if check.state == LinkState::Ok
+                && canonicalize(&response.final_url) != canonicalize(url)
+            {
+                check.state = LinkState::Moved;
+                check.redirect_url = Some(response.final_url);
+            }
+        }
+        // A URL we can't even parse was never going to load
+        Err(e @ BookmarkError::InvalidUrl(_)) => {
+            check.state = LinkState::Dead;
+            check.error = Some(e.to_string());
+        }
+        Err(e) => {
+            check.state = LinkState::Unreachable;
+            check.error = Some(e.to_string());
+            may_die = true;
+        }
+    }
+
+    if matches!(check.state, LinkState::Unreachable | LinkState::Dead) {
+        check.failures = previous_failures + 1;
+    }
+    if check.state == LinkState::Unreachable && may_die && check.failures >= DEAD_AFTER_FAILURES {
+        check.state = LinkState::Dead;
+    }
+    check
+}
+
+/// Store the check in the item's metadata without touching `updated_at`, so a sweep doesn't
+/// reshuffle the "recently changed" list. The read and write share a transaction so a
+/// concurrent change to the metadata isn't lost.
+async fn record(db: &DatabaseConnection, id: i32, check: &LinkCheck) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+    let Some(item) = Item::find_by_id(id).one(&txn).await? else {
+        // Deleted while we were checking it
+        return Ok(());
+    };
+
+    let mut metadata = item
+        .metadata
+        .as_deref()
+        .and_then(|metadata| serde_json::from_str::<serde_json::Value>(metadata).ok())
+        .filter(serde_json::Value::is_object)
+        .unwrap_or_else(|| serde_json::json!({}));
+    metadata["link_check"] =
+        serde_json::to_value(check).map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    Item::update_many()
+        .col_expr(item::Column::Metadata, Expr::value(metadata.to_string()))
+        .filter(item::Column::Id.eq(id))
+        .exec(&txn)
+        .await?;
+    txn.commit().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::{ItemActiveModel, ItemModel};
+    use crate::test_support::{test_database, HttpStub, StubResponse};
+    use sea_orm::{ActiveModelTrait, Set};
+
+    fn now() -> NaiveDateTime {
+        Utc::now().naive_utc()
+    }
+
+    async fn bookmark(db: &DatabaseConnection, url: &str, metadata: Option<String>) -> ItemModel {
+        ItemActiveModel {
+            title: Set(url.to_string()),
+            item_type: Set("bookmark".to_string()),
+            source_url: Set(Some(url.to_string())),
+            metadata: Set(metadata),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .expect("insert bookmark")
+    }
+
+    async fn stored_check(db: &DatabaseConnection, id: i32) -> LinkCheck {
+        let item = Item::find_by_id(id)
+            .one(db)
+            .await
+            .expect("query")
+            .expect("item");
+        previous_check(item.metadata.as_deref().expect("metadata")).expect("link_check")
+    }
+
+    #[tokio::test]
+    async fn sweep_records_ok_moved_and_dead_links() {
+        let stub = HttpStub::start(vec![
+            ("/alive", StubResponse::new(200, "hello")),
+            ("/old", StubResponse::redirect("/new")),
+            ("/new", StubResponse::new(200, "moved here")),
+            ("/gone", StubResponse::new(410, "gone")),
+        ])
+        .await;
+        let db = test_database("link-checker").await;
+        let alive = bookmark(
+            &db,
+            &stub.url("/alive"),
+            Some(r#"{"author":"Someone"}"#.to_string()),
+        )
+        .await;
+        let old = bookmark(&db, &stub.url("/old"), None).await;
+        let gone = bookmark(&db, &stub.url("/gone"), None).await;
+
+        let events = std::sync::Mutex::new(Vec::new());
+        let checked = sweep(
+            &db,
+            &BookmarkProcessor::new(),
+            &HostLimiter::new(Duration::from_millis(1)),
+            10,
+            |event| events.lock().expect("events").push(event.item_id),
+        )
+        .await
+        .expect("sweep");
+        assert_eq!(checked, 3);
+        let mut events = events.into_inner().expect("events");
+        events.sort();
+        assert_eq!(events, vec![alive.id, old.id, gone.id]);
+        let mut paths = stub.paths();
+        paths.sort();
+        assert_eq!(paths, vec!["/alive", "/gone", "/new", "/old"]);
+
+        let check = stored_check(&db, alive.id).await;
+        assert_eq!(check.state, LinkState::Ok);
+        assert_eq!(check.status, Some(200));
+
+        let check = stored_check(&db, old.id).await;
+        assert_eq!(check.state, LinkState::Moved);
+        assert_eq!(check.redirect_url, Some(stub.url("/new")));
+
+        let check = stored_check(&db, gone.id).await;
+        assert_eq!(check.state, LinkState::Dead);
+        assert_eq!(check.status, Some(410));
+        assert_eq!(check.failures, 1);
+
+        // Other metadata survives, and updated_at is left alone
+        let item = Item::find_by_id(alive.id)
+            .one(&db)
+            .await
+            .expect("query")
+            .expect("item");
+        assert!(item
+            .metadata
+            .expect("metadata")
+            .contains("\"author\":\"Someone\""));
+        assert_eq!(item.updated_at, alive.updated_at);
+
+        // Everything is fresh now, so another sweep has nothing to do
+        let checked = sweep(
+            &db,
+            &BookmarkProcessor::new(),
+            &HostLimiter::new(Duration::from_millis(1)),
+            10,
+            |_| {},
+        )
+        .await
+        .expect("sweep");
+        assert_eq!(checked, 0);
+    }
+
+    #[tokio::test]
+    async fn stale_checks_are_due_oldest_first() {
+        let db = test_database("link-checker-due").await;
+        let stale = |days: i64| {
+            let check = LinkCheck {
+                state: LinkState::Ok,
+                status: Some(200),
+                redirect_url: None,
+                error: None,
+                failures: 0,
+                checked_at: now() - ChronoDuration::days(days),
+            };
+            Some(serde_json::json!({ "link_check": check }).to_string())
+        };
+        let fresh = bookmark(&db, "https://example.com/fresh", stale(1)).await;
+        let older = bookmark(&db, "https://example.com/older", stale(30)).await;
+        let old = bookmark(&db, "https://example.com/old", stale(8)).await;
+        let never = bookmark(&db, "https://example.com/never", None).await;
+
+        let due = due_bookmarks(&db, now(), 10).await.expect("due");
+        let ids = due.iter().map(|(id, _, _)| *id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![never.id, older.id, old.id]);
+        assert!(!ids.contains(&fresh.id));
+    }
+
+    #[test]
+    fn repeated_failures_turn_unreachable_into_dead() {
+        let url = "https://example.com/flaky";
+        let mut previous: Option<LinkCheck> = None;
+        let mut states = Vec::new();
+        for _ in 0..DEAD_AFTER_FAILURES {
+            let check = assess(url, Err(BookmarkError::Timeout), previous.as_ref(), now());
+            states.push(check.state);
+            previous = Some(check);
+        }
+        assert_eq!(
+            states,
+            vec![
+                LinkState::Unreachable,
+                LinkState::Unreachable,
+                LinkState::Dead
+            ]
+        );
+
+        // A single success clears the count
+        let check = assess(
+            url,
+            Ok(LinkResponse {
+                status: 200,
+                final_url: "http://www.example.com/flaky/".to_string(),
+            }),
+            previous.as_ref(),
+            now(),
+        );
+        assert_eq!(check.state, LinkState::Ok);
+        assert_eq!(check.failures, 0);
+    }
+
+    #[test]
+    fn refusals_stay_unreachable_and_outages_die() {
+        let url = "https://example.com/members";
+        let last_state = |status: u16| {
+            let mut previous: Option<LinkCheck> = None;
+            for _ in 0..DEAD_AFTER_FAILURES + 2 {
+                let response = Ok(LinkResponse {
+                    status,
+                    final_url: url.to_string(),
+                });
+                previous = Some(assess(url, response, previous.as_ref(), now()));
+            }
+            previous.expect("checked")
+        };
+
+        for status in [401, 403, 429] {
+            let check = last_state(status);
+            assert_eq!(check.state, LinkState::Unreachable, "status {}", status);
+            assert_eq!(check.failures, DEAD_AFTER_FAILURES + 2);
+        }
+        assert_eq!(last_state(503).state, LinkState::Dead);
+    }
+}
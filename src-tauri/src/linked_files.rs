@@ -0,0 +1,508 @@
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::Emitter;
+
+use crate::blobs;
+use crate::entities::{item, Item, ItemActiveModel, ItemModel};
+use crate::indexer::{self, IndexOperation, IndexerState};
+
+/// Give the app a minute to settle before the first sweep.
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// How long a check is trusted before the file is looked at again. Checks are cheap unless
+/// the file changed, since only a changed fingerprint means reading it.
+const RECHECK_AFTER_MINUTES: i64 = 60;
+const SWEEP_BATCH_SIZE: usize = 200;
+
+/// Items created with the `"link"` operation have this `source_type`.
+pub const LINK_SOURCE_TYPE: &str = "link";
+pub const LINKED_FILE_CHECKED_EVENT: &str = "linked-file-checked";
+
+/// What is cheap to read about a file without opening it. A different fingerprint means the
+/// file may have changed and is hashed again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Only known on Unix, where it follows a file that is renamed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inode: Option<u64>,
+    pub size: u64,
+    /// Milliseconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_ms: Option<i64>,
+}
+
+impl Fingerprint {
+    pub fn of(metadata: &fs::Metadata) -> Self {
+        #[cfg(unix)]
+        let inode = {
+            use std::os::unix::fs::MetadataExt;
+            Some(metadata.ino())
+        };
+        #[cfg(not(unix))]
+        let inode = None;
+
+        Self {
+            inode,
+            size: metadata.len(),
+            modified_ms: metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .and_then(|since| i64::try_from(since.as_millis()).ok()),
+        }
+    }
+
+    pub fn modified_at(&self) -> Option<NaiveDateTime> {
+        chrono::DateTime::from_timestamp_millis(self.modified_ms?).map(|at| at.naive_utc())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkedState {
+    Present,
+    /// Nothing is at the path any more; `relink_file` can find it again by its hash.
+    Missing,
+}
+
+/// A file indexed where it is, stored under `linked_file` in the item's metadata. Linked
+/// items leave `content_hash` empty, since their contents aren't in the blob store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkedFile {
+    /// Hex SHA-256 of the contents when they were last read.
+    pub hash: String,
+    #[serde(flatten)]
+    pub fingerprint: Fingerprint,
+    pub state: LinkedState,
+    pub checked_at: NaiveDateTime,
+    /// When the contents were last seen to differ from what was hashed before.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_at: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing_since: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkedFileCheckedEvent {
+    pub item_id: i32,
+    pub linked_file: LinkedFile,
+    /// Where the file was before it was found under a new name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moved_from: Option<String>,
+}
+
+/// Read the file at `path` for linking.
+pub fn link(path: &Path) -> io::Result<LinkedFile> {
+    let metadata = fs::metadata(path)?;
+    let (hash, _) = blobs::hash_file(path)?;
+    Ok(LinkedFile {
+        hash,
+        fingerprint: Fingerprint::of(&metadata),
+        state: LinkedState::Present,
+        checked_at: Utc::now().naive_utc(),
+        changed_at: None,
+        missing_since: None,
+    })
+}
+
+/// The result of looking at a linked file again.
+#[derive(Debug, Clone, PartialEq)]
+struct Inspection {
+    file: LinkedFile,
+    /// Set when the file was found under another name.
+    moved_to: Option<PathBuf>,
+    /// Whether the contents differ from what was hashed before.
+    changed: bool,
+}
+
+/// Look at the file at `path` again. Contents are only hashed when the fingerprint changed,
+/// and a file renamed within its directory is followed by its inode.
+fn inspect(path: &Path, previous: &LinkedFile, now: NaiveDateTime) -> Inspection {
+    let mut inspection = Inspection {
+        file: LinkedFile {
+            checked_at: now,
+            ..previous.clone()
+        },
+        moved_to: None,
+        changed: false,
+    };
+
+    let found = match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => Some((path.to_path_buf(), metadata)),
+        _ => find_renamed(path, &previous.fingerprint).and_then(|renamed| {
+            inspection.moved_to = Some(renamed.clone());
+            fs::metadata(&renamed)
+                .ok()
+                .map(|metadata| (renamed, metadata))
+        }),
+    };
+    let Some((path, metadata)) = found else {
+        inspection.file.state = LinkedState::Missing;
+        inspection.file.missing_since = previous.missing_since.or(Some(now));
+        inspection.moved_to = None;
+        return inspection;
+    };
+
+    inspection.file.state = LinkedState::Present;
+    inspection.file.missing_since = None;
+    let fingerprint = Fingerprint::of(&metadata);
+    if fingerprint != previous.fingerprint {
+        match blobs::hash_file(&path) {
+            Ok((hash, _)) => {
+                if hash != previous.hash {
+                    inspection.changed = true;
+                    inspection.file.changed_at = Some(now);
+                }
+                inspection.file.hash = hash;
+                inspection.file.fingerprint = fingerprint;
+            }
+            // Unreadable for now; the old fingerprint makes the next check try again
+            Err(e) => eprintln!("Failed to read {}: {}", path.display(), e),
+        }
+    }
+    inspection
+}
+
+/// A file next to where `path` was with the same inode, size and modification time, which is
+/// what a rename within the directory leaves. Moves further afield need `relink_file`.
+fn find_renamed(path: &Path, fingerprint: &Fingerprint) -> Option<PathBuf> {
+    fingerprint.inode?;
+    fs::read_dir(path.parent()?)
+        .ok()?
+        .flatten()
+        .find(|entry| {
+            entry.metadata().is_ok_and(|metadata| {
+                metadata.is_file() && Fingerprint::of(&metadata) == *fingerprint
+            })
+        })
+        .map(|entry| entry.path())
+}
+
+/// The first file under `root` whose contents hash to `hash`, with its fingerprint. Only
+/// files of the right size are read.
+fn find_by_hash(root: &Path, hash: &str, size: u64) -> Option<(PathBuf, Fingerprint)> {
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            (metadata.len() == size).then(|| (entry, Fingerprint::of(&metadata)))
+        })
+        .find(|(entry, _)| blobs::hash_file(entry.path()).is_ok_and(|(found, _)| found == hash))
+        .map(|(entry, fingerprint)| (entry.into_path(), fingerprint))
+}
+
+pub fn start_worker(app: tauri::AppHandle, db: DatabaseConnection, indexer: IndexerState) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        loop {
+            let checked = sweep(&db, SWEEP_BATCH_SIZE, |event| {
+                indexer.wake();
+                let _ = app.emit(LINKED_FILE_CHECKED_EVENT, event);
+            })
+            .await;
+            match checked {
+                Ok(0) => {}
+                Ok(count) => println!("Checked {} linked files", count),
+                Err(e) => eprintln!("Failed to check linked files: {}", e),
+            }
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+        }
+    });
+}
+
+/// Check up to `limit` linked files not looked at recently, least recently checked first,
+/// calling `on_checked` as each one is recorded. Returns how many were checked.
+pub async fn sweep(
+    db: &DatabaseConnection,
+    limit: usize,
+    on_checked: impl Fn(&LinkedFileCheckedEvent),
+) -> Result<usize, DbErr> {
+    let due = due_links(db, Utc::now().naive_utc(), limit).await?;
+
+    let mut checked = 0;
+    for (id, path, previous) in due {
+        let inspection = tokio::task::spawn_blocking(move || {
+            inspect(Path::new(&path), &previous, Utc::now().naive_utc())
+        })
+        .await
+        .map_err(|e| DbErr::Custom(e.to_string()))?;
+        if let Some(event) = record(db, id, inspection).await? {
+            on_checked(&event);
+        }
+        checked += 1;
+    }
+    Ok(checked)
+}
+
+/// Look under `root` for a linked item's file by its hash, and point the item at it.
+pub async fn relink(db: &DatabaseConnection, id: i32, root: PathBuf) -> Result<ItemModel, String> {
+    let item = Item::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Item not found")?;
+    let previous = item
+        .metadata
+        .as_deref()
+        .and_then(linked_file)
+        .ok_or("Item is not a linked file")?;
+
+    let (hash, size) = (previous.hash.clone(), previous.fingerprint.size);
+    let search_root = root.clone();
+    let (found, fingerprint) =
+        tokio::task::spawn_blocking(move || find_by_hash(&search_root, &hash, size))
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("No file under {} matches", root.display()))?;
+
+    // Its contents were just hashed to find it, so there is nothing left to inspect
+    let inspection = Inspection {
+        file: LinkedFile {
+            fingerprint,
+            state: LinkedState::Present,
+            checked_at: Utc::now().naive_utc(),
+            missing_since: None,
+            ..previous
+        },
+        moved_to: Some(found),
+        changed: false,
+    };
+    record(db, id, inspection)
+        .await
+        .map_err(|e| e.to_string())?;
+    Item::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Item not found".to_string())
+}
+
+/// An existing linked item for the file at `path`.
+pub async fn find_linked(db: &DatabaseConnection, path: &str) -> Result<Option<ItemModel>, DbErr> {
+    Item::find()
+        .filter(item::Column::SourceType.eq(LINK_SOURCE_TYPE))
+        .filter(item::Column::SourceUrl.eq(path))
+        .one(db)
+        .await
+}
+
+async fn due_links(
+    db: &DatabaseConnection,
+    now: NaiveDateTime,
+    limit: usize,
+) -> Result<Vec<(i32, String, LinkedFile)>, DbErr> {
+    let rows: Vec<(i32, Option<String>, Option<String>)> = Item::find()
+        .select_only()
+        .column(item::Column::Id)
+        .column(item::Column::SourceUrl)
+        .column(item::Column::Metadata)
+        .filter(item::Column::SourceType.eq(LINK_SOURCE_TYPE))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let stale_before = now - ChronoDuration::minutes(RECHECK_AFTER_MINUTES);
+    let mut due = rows
+        .into_iter()
+        .filter_map(|(id, path, metadata)| {
+            let previous = linked_file(metadata.as_deref()?)?;
+            (previous.checked_at <= stale_before).then_some((id, path?, previous))
+        })
+        .collect::<Vec<_>>();
+    due.sort_by_key(|(id, _, previous)| (previous.checked_at, *id));
+    due.truncate(limit);
+    Ok(due)
+}
+
+fn linked_file(metadata: &str) -> Option<LinkedFile> {
+    let mut metadata: Value = serde_json::from_str(metadata).ok()?;
+    serde_json::from_value(metadata.get_mut("linked_file")?.take()).ok()
+}
+
+/// Store what a check found. A file that moved or changed also updates the item's path,
+/// size and modification time, and is queued for indexing; `updated_at` is left alone
+/// either way. Returns `None` if the item is gone.
+async fn record(
+    db: &DatabaseConnection,
+    id: i32,
+    inspection: Inspection,
+) -> Result<Option<LinkedFileCheckedEvent>, DbErr> {
+    let txn = db.begin().await?;
+    let Some(item) = Item::find_by_id(id).one(&txn).await? else {
+        // Deleted while we were checking it
+        return Ok(None);
+    };
+
+    let mut metadata = item
+        .metadata
+        .as_deref()
+        .and_then(|metadata| serde_json::from_str::<Map<String, Value>>(metadata).ok())
+        .unwrap_or_default();
+    let file = inspection.file;
+    metadata.insert(
+        "linked_file".to_string(),
+        serde_json::to_value(&file).map_err(|e| DbErr::Custom(e.to_string()))?,
+    );
+
+    let moved_from = inspection.moved_to.as_ref().and(item.source_url.clone());
+    let mut active: ItemActiveModel = item.into();
+    active.metadata = Set(Some(Value::Object(metadata).to_string()));
+    let reindex = inspection.moved_to.is_some() || inspection.changed;
+    if let Some(moved_to) = &inspection.moved_to {
+        active.source_url = Set(Some(moved_to.display().to_string()));
+    }
+    if reindex {
+        active.file_size = Set(Some(file.fingerprint.size as i64));
+        if let Some(modified_at) = file.fingerprint.modified_at() {
+            active.file_modified_at = Set(Some(modified_at));
+        }
+    }
+    active.update(&txn).await?;
+    if reindex {
+        indexer::enqueue(&txn, id, IndexOperation::Upsert).await?;
+    }
+    txn.commit().await?;
+
+    Ok(Some(LinkedFileCheckedEvent {
+        item_id: id,
+        linked_file: file,
+        moved_from,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, test_database};
+
+    fn now() -> NaiveDateTime {
+        Utc::now().naive_utc()
+    }
+
+    async fn linked_item(db: &DatabaseConnection, path: &Path) -> ItemModel {
+        let mut file = link(path).expect("link");
+        // Due for a check straight away
+        file.checked_at = now() - ChronoDuration::days(1);
+        ItemActiveModel {
+            title: Set("linked".to_string()),
+            item_type: Set("file".to_string()),
+            source_type: Set(Some(LINK_SOURCE_TYPE.to_string())),
+            source_url: Set(Some(path.display().to_string())),
+            metadata: Set(Some(serde_json::json!({ "linked_file": file }).to_string())),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .expect("insert")
+    }
+
+    async fn stored(db: &DatabaseConnection, id: i32) -> (ItemModel, LinkedFile) {
+        let item = Item::find_by_id(id)
+            .one(db)
+            .await
+            .expect("query")
+            .expect("item");
+        let file = linked_file(item.metadata.as_deref().expect("metadata")).expect("linked_file");
+        (item, file)
+    }
+
+    #[test]
+    fn inspection_notices_changes_renames_and_removals() {
+        let dir = temp_dir("linked-inspect");
+        let path = dir.join("notes.txt");
+        fs::write(&path, b"first draft").expect("write");
+        let linked = link(&path).expect("link");
+
+        let same = inspect(&path, &linked, now());
+        assert_eq!(same.file.state, LinkedState::Present);
+        assert_eq!(same.file.hash, linked.hash);
+        assert_eq!(same.file.changed_at, None);
+
+        fs::write(&path, b"second draft, longer").expect("write");
+        let changed = inspect(&path, &linked, now());
+        assert_ne!(changed.file.hash, linked.hash);
+        assert_eq!(changed.file.changed_at, Some(changed.file.checked_at));
+        assert_eq!(changed.file.fingerprint.size, 20);
+
+        let linked = changed.file;
+        let renamed = dir.join("notes-final.txt");
+        fs::rename(&path, &renamed).expect("rename");
+        let moved = inspect(&path, &linked, now());
+        if cfg!(unix) {
+            assert_eq!(moved.moved_to, Some(renamed.clone()));
+            assert_eq!(moved.file.state, LinkedState::Present);
+        } else {
+            assert_eq!(moved.file.state, LinkedState::Missing);
+        }
+
+        fs::remove_file(&renamed).expect("remove");
+        let missing = inspect(&renamed, &linked, now());
+        assert_eq!(missing.file.state, LinkedState::Missing);
+        let since = missing.file.missing_since.expect("missing since");
+        let still_missing = inspect(&renamed, &missing.file, now());
+        assert_eq!(still_missing.file.missing_since, Some(since));
+    }
+
+    #[tokio::test]
+    async fn missing_files_are_marked_and_relinked_by_hash() {
+        let db = test_database("linked-relink").await;
+        let dir = temp_dir("linked-relink-files");
+        let original = dir.join("footage.mov");
+        fs::write(&original, b"lots of video").expect("write");
+        let item = linked_item(&db, &original).await;
+
+        // Moved to another drive, so nothing is left to follow
+        let elsewhere = dir.join("archive").join("2026");
+        fs::create_dir_all(&elsewhere).expect("dir");
+        fs::write(elsewhere.join("decoy.mov"), b"lots of audio").expect("write");
+        fs::write(elsewhere.join("footage.mov"), b"lots of video").expect("write");
+        fs::remove_file(&original).expect("remove");
+
+        let events = std::sync::Mutex::new(Vec::new());
+        let checked = sweep(&db, 10, |event| {
+            events.lock().expect("events").push(event.linked_file.state)
+        })
+        .await
+        .expect("sweep");
+        assert_eq!(checked, 1);
+        assert_eq!(
+            events.into_inner().expect("events"),
+            vec![LinkedState::Missing]
+        );
+        let (_, file) = stored(&db, item.id).await;
+        assert_eq!(file.state, LinkedState::Missing);
+        assert!(file.missing_since.is_some());
+
+        // Just checked, so not due again yet
+        assert_eq!(sweep(&db, 10, |_| {}).await.expect("sweep"), 0);
+
+        let relinked = relink(&db, item.id, dir.join("archive"))
+            .await
+            .expect("relink");
+        assert_eq!(
+            relinked.source_url,
+            Some(elsewhere.join("footage.mov").display().to_string())
+        );
+        assert_eq!(relinked.updated_at, item.updated_at);
+        let (_, file) = stored(&db, item.id).await;
+        assert_eq!(file.state, LinkedState::Present);
+        assert_eq!(file.missing_since, None);
+
+        let error = relink(&db, item.id, dir.join("archive").join("empty"))
+            .await
+            .expect_err("nothing to find");
+        assert!(error.starts_with("No file under"));
+    }
+}
@@ -48,6 +48,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
+#[allow(clippy::enum_variant_names)]
 enum Item {
     #[sea_orm(iden = "items")]
     Table,
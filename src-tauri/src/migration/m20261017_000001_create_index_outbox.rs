@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IndexOutbox::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IndexOutbox::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(IndexOutbox::ItemId).integer().not_null())
+                    .col(ColumnDef::new(IndexOutbox::Operation).string().not_null())
+                    .col(
+                        ColumnDef::new(IndexOutbox::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(IndexOutbox::LastError).text())
+                    .col(
+                        ColumnDef::new(IndexOutbox::AvailableAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(IndexOutbox::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    // Set on entries that can never be applied, which the worker then leaves alone
+                    .col(ColumnDef::new(IndexOutbox::FailedAt).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        // The worker polls for entries whose retry delay has elapsed
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_index_outbox_available_at")
+                    .table(IndexOutbox::Table)
+                    .col(IndexOutbox::AvailableAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IndexOutbox::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum IndexOutbox {
+    #[sea_orm(iden = "index_outbox")]
+    Table,
+    Id,
+    ItemId,
+    Operation,
+    Attempts,
+    LastError,
+    AvailableAt,
+    CreatedAt,
+    FailedAt,
+}
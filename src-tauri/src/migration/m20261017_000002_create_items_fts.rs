@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// External-content FTS5 table over `items`, kept in sync by triggers so the fallback search
+/// backend never needs an indexing pass of its own.
+const UP: &[&str] = &[
+    "CREATE VIRTUAL TABLE IF NOT EXISTS items_fts USING fts5(
+        title,
+        content,
+        tags,
+        content = 'items',
+        content_rowid = 'id',
+        tokenize = 'unicode61 remove_diacritics 2'
+    )",
+    "CREATE TRIGGER IF NOT EXISTS items_fts_insert AFTER INSERT ON items BEGIN
+        INSERT INTO items_fts(rowid, title, content, tags)
+        VALUES (new.id, new.title, new.content, new.tags);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS items_fts_delete AFTER DELETE ON items BEGIN
+        INSERT INTO items_fts(items_fts, rowid, title, content, tags)
+        VALUES ('delete', old.id, old.title, old.content, old.tags);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS items_fts_update AFTER UPDATE OF title, content, tags ON items BEGIN
+        INSERT INTO items_fts(items_fts, rowid, title, content, tags)
+        VALUES ('delete', old.id, old.title, old.content, old.tags);
+        INSERT INTO items_fts(rowid, title, content, tags)
+        VALUES (new.id, new.title, new.content, new.tags);
+    END",
+    // Index the rows that existed before the table did
+    "INSERT INTO items_fts(items_fts) VALUES ('rebuild')",
+];
+
+const DOWN: &[&str] = &[
+    "DROP TRIGGER IF EXISTS items_fts_update",
+    "DROP TRIGGER IF EXISTS items_fts_delete",
+    "DROP TRIGGER IF EXISTS items_fts_insert",
+    "DROP TABLE IF EXISTS items_fts",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for statement in UP {
+            db.execute_unprepared(statement).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for statement in DOWN {
+            db.execute_unprepared(statement).await?;
+        }
+        Ok(())
+    }
+}
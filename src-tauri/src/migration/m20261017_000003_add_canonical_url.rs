@@ -0,0 +1,141 @@
+use reqwest::Url;
+use sea_orm::{ConnectionTrait, Statement};
+use sea_orm_migration::prelude::*;
+use std::collections::HashSet;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Item::Table)
+                    .add_column(ColumnDef::new(Item::CanonicalUrl).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Backfill existing bookmarks, oldest first; later duplicates keep a NULL key so the
+        // unique index can still be built, and show up as separate items as they always have
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let rows = db
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT id, source_url FROM items \
+                 WHERE item_type = 'bookmark' AND source_url IS NOT NULL ORDER BY id",
+            ))
+            .await?;
+        let mut seen = HashSet::new();
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let source_url: String = row.try_get("", "source_url")?;
+            let Some(canonical_url) = canonicalize(&source_url) else {
+                continue;
+            };
+            if !seen.insert(canonical_url.clone()) {
+                continue;
+            }
+            db.execute(Statement::from_sql_and_values(
+                backend,
+                "UPDATE items SET canonical_url = ? WHERE id = ?",
+                [canonical_url.into(), id.into()],
+            ))
+            .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_items_canonical_url")
+                    .table(Item::Table)
+                    .col(Item::CanonicalUrl)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_items_canonical_url")
+                    .table(Item::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Item::Table)
+                    .drop_column(Item::CanonicalUrl)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Item {
+    #[sea_orm(iden = "items")]
+    Table,
+    CanonicalUrl,
+}
+
+/// Query parameters that only say where a click came from, besides any `utm_` ones.
+const TRACKING_PARAMETERS: &[&str] = &[
+    "_hsenc", "_hsmi", "dclid", "fbclid", "gclid", "gclsrc", "igshid", "mc_cid", "mc_eid",
+    "mkt_tok", "msclkid", "ref_src", "ref_url", "s_cid", "twclid", "wickedid", "yclid",
+];
+
+/// `bookmarks::canonicalize` as it was when this migration was written. The backfill keeps its
+/// own copy so that later changes to how URLs are keyed can't change what it does.
+fn canonicalize(url: &str) -> Option<String> {
+    let mut url = Url::parse(url.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    if url.port() == Some(443) {
+        url.set_port(None).ok()?;
+    }
+    url.set_scheme("https").ok()?;
+
+    let host = url.host_str()?.trim_end_matches('.').to_ascii_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    url.set_host(Some(&host)).ok()?;
+
+    if !url
+        .fragment()
+        .is_some_and(|fragment| fragment.starts_with('!'))
+    {
+        url.set_fragment(None);
+    }
+
+    let mut parameters = url
+        .query_pairs()
+        .filter(|(name, _)| {
+            let name = name.to_ascii_lowercase();
+            !name.starts_with("utm_") && !TRACKING_PARAMETERS.contains(&name.as_str())
+        })
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    parameters.sort();
+    if parameters.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(parameters);
+    }
+
+    let path = url.path().to_string();
+    if path.len() > 1 && path.ends_with('/') {
+        url.set_path(path.trim_end_matches('/'));
+    }
+
+    Some(url.to_string())
+}
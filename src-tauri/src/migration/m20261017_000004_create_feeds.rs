@@ -0,0 +1,111 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Feeds::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Feeds::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Feeds::Url).string().not_null().unique_key())
+                    .col(ColumnDef::new(Feeds::Title).string().not_null())
+                    .col(ColumnDef::new(Feeds::IncludeKeywords).text())
+                    .col(ColumnDef::new(Feeds::ExcludeKeywords).text())
+                    .col(ColumnDef::new(Feeds::Etag).string())
+                    .col(ColumnDef::new(Feeds::LastModified).string())
+                    .col(ColumnDef::new(Feeds::LastPolledAt).timestamp())
+                    .col(ColumnDef::new(Feeds::LastError).text())
+                    .col(ColumnDef::new(Feeds::CreatedAt).timestamp().not_null())
+                    .col(ColumnDef::new(Feeds::UpdatedAt).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every entry a feed has ever listed, so a post is considered once even after its
+        // item is deleted or when a filter turned it away
+        manager
+            .create_table(
+                Table::create()
+                    .table(FeedEntries::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FeedEntries::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FeedEntries::FeedId).integer().not_null())
+                    .col(ColumnDef::new(FeedEntries::Guid).string().not_null())
+                    .col(ColumnDef::new(FeedEntries::ItemId).integer())
+                    .col(ColumnDef::new(FeedEntries::SeenAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(FeedEntries::Table, FeedEntries::FeedId)
+                            .to(Feeds::Table, Feeds::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_feed_entries_feed_guid")
+                    .table(FeedEntries::Table)
+                    .col(FeedEntries::FeedId)
+                    .col(FeedEntries::Guid)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FeedEntries::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Feeds::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Feeds {
+    Table,
+    Id,
+    Url,
+    Title,
+    IncludeKeywords,
+    ExcludeKeywords,
+    Etag,
+    LastModified,
+    LastPolledAt,
+    LastError,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum FeedEntries {
+    Table,
+    Id,
+    FeedId,
+    Guid,
+    ItemId,
+    SeenAt,
+}
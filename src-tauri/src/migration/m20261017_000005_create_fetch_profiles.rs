@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FetchProfiles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FetchProfiles::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(FetchProfiles::Domain)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(FetchProfiles::Headers).text())
+                    .col(ColumnDef::new(FetchProfiles::Cookies).text())
+                    .col(ColumnDef::new(FetchProfiles::ProxyUrl).string())
+                    .col(ColumnDef::new(FetchProfiles::UserAgent).string())
+                    .col(ColumnDef::new(FetchProfiles::MaxResponseBytes).big_integer())
+                    .col(
+                        ColumnDef::new(FetchProfiles::AcceptInvalidCerts)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(FetchProfiles::RootCertificatePath).string())
+                    .col(
+                        ColumnDef::new(FetchProfiles::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FetchProfiles::UpdatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FetchProfiles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum FetchProfiles {
+    Table,
+    Id,
+    Domain,
+    Headers,
+    Cookies,
+    ProxyUrl,
+    UserAgent,
+    MaxResponseBytes,
+    AcceptInvalidCerts,
+    RootCertificatePath,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,135 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Reference counts follow `items.content_hash` through triggers, so every write path that
+/// adds, removes or re-points an item keeps them right inside its own transaction.
+const TRIGGERS: &[&str] = &[
+    "CREATE TRIGGER IF NOT EXISTS blobs_item_insert AFTER INSERT ON items
+     WHEN new.content_hash IS NOT NULL BEGIN
+        INSERT INTO blobs(hash, size, ref_count) VALUES (new.content_hash, new.file_size, 1)
+        ON CONFLICT(hash) DO UPDATE SET ref_count = ref_count + 1;
+    END",
+    "CREATE TRIGGER IF NOT EXISTS blobs_item_delete AFTER DELETE ON items
+     WHEN old.content_hash IS NOT NULL BEGIN
+        UPDATE blobs SET ref_count = ref_count - 1 WHERE hash = old.content_hash;
+    END",
+    "CREATE TRIGGER IF NOT EXISTS blobs_item_update AFTER UPDATE OF content_hash ON items
+     WHEN old.content_hash IS NOT new.content_hash BEGIN
+        UPDATE blobs SET ref_count = ref_count - 1 WHERE hash = old.content_hash;
+        INSERT INTO blobs(hash, size, ref_count)
+        SELECT new.content_hash, new.file_size, 1 WHERE new.content_hash IS NOT NULL
+        ON CONFLICT(hash) DO UPDATE SET ref_count = ref_count + 1;
+    END",
+];
+
+const DROP_TRIGGERS: &[&str] = &[
+    "DROP TRIGGER IF EXISTS blobs_item_update",
+    "DROP TRIGGER IF EXISTS blobs_item_delete",
+    "DROP TRIGGER IF EXISTS blobs_item_insert",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite adds one column per ALTER TABLE
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Items::Table)
+                    .add_column(ColumnDef::new(Items::ContentHash).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Items::Table)
+                    .add_column(ColumnDef::new(Items::OriginalFilename).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_items_content_hash")
+                    .table(Items::Table)
+                    .col(Items::ContentHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Blobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Blobs::Hash)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Blobs::Size).big_integer())
+                    .col(
+                        ColumnDef::new(Blobs::RefCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        for statement in TRIGGERS {
+            db.execute_unprepared(statement).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for statement in DROP_TRIGGERS {
+            db.execute_unprepared(statement).await?;
+        }
+        manager
+            .drop_table(Table::drop().table(Blobs::Table).to_owned())
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_items_content_hash")
+                    .table(Items::Table)
+                    .to_owned(),
+            )
+            .await?;
+        for column in [Items::OriginalFilename, Items::ContentHash] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Items::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Items {
+    Table,
+    ContentHash,
+    OriginalFilename,
+}
+
+#[derive(DeriveIden)]
+enum Blobs {
+    Table,
+    Hash,
+    Size,
+    RefCount,
+}
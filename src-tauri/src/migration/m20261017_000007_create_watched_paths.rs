@@ -0,0 +1,142 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WatchedPaths::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WatchedPaths::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WatchedPaths::Path)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WatchedPaths::Recursive)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(ColumnDef::new(WatchedPaths::IncludePatterns).text())
+                    .col(ColumnDef::new(WatchedPaths::ExcludePatterns).text())
+                    .col(
+                        ColumnDef::new(WatchedPaths::Operation)
+                            .string()
+                            .not_null()
+                            .default("link"),
+                    )
+                    .col(ColumnDef::new(WatchedPaths::LastScan).timestamp())
+                    .col(ColumnDef::new(WatchedPaths::LastError).text())
+                    .col(
+                        ColumnDef::new(WatchedPaths::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WatchedPaths::UpdatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every file a watched folder has turned into an item, with what it looked like then,
+        // so a rescan can tell new, changed and vanished files apart
+        manager
+            .create_table(
+                Table::create()
+                    .table(WatchedFiles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WatchedFiles::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WatchedFiles::WatchedPathId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WatchedFiles::Path).string().not_null())
+                    .col(ColumnDef::new(WatchedFiles::ItemId).integer())
+                    .col(ColumnDef::new(WatchedFiles::Size).big_integer().not_null())
+                    .col(ColumnDef::new(WatchedFiles::ModifiedMs).big_integer())
+                    .col(ColumnDef::new(WatchedFiles::MissingSince).timestamp())
+                    .col(ColumnDef::new(WatchedFiles::SeenAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(WatchedFiles::Table, WatchedFiles::WatchedPathId)
+                            .to(WatchedPaths::Table, WatchedPaths::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_watched_files_watched_path")
+                    .table(WatchedFiles::Table)
+                    .col(WatchedFiles::WatchedPathId)
+                    .col(WatchedFiles::Path)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WatchedFiles::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WatchedPaths::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum WatchedPaths {
+    Table,
+    Id,
+    Path,
+    Recursive,
+    IncludePatterns,
+    ExcludePatterns,
+    Operation,
+    LastScan,
+    LastError,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WatchedFiles {
+    Table,
+    Id,
+    WatchedPathId,
+    Path,
+    ItemId,
+    Size,
+    ModifiedMs,
+    MissingSince,
+    SeenAt,
+}
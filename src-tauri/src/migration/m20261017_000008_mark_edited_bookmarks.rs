@@ -0,0 +1,63 @@
+use chrono::NaiveDateTime;
+use sea_orm::{ConnectionTrait, Statement};
+use sea_orm_migration::prelude::*;
+use serde_json::{Map, Value};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Created and updated timestamps are taken one after the other on insert, so only a gap
+/// longer than this means the bookmark was changed afterwards.
+const EDITED_AFTER_SECONDS: i64 = 1;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Bookmarks saved before refreshes existed never recorded which fields were edited by
+        // hand. Any that changed after being saved could only have been edited, so keep their
+        // title and content from being refreshed over.
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let rows = db
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT id, metadata, created_at, updated_at FROM items \
+                 WHERE item_type = 'bookmark'",
+            ))
+            .await?;
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let metadata: Option<String> = row.try_get("", "metadata")?;
+            let created_at: NaiveDateTime = row.try_get("", "created_at")?;
+            let updated_at: NaiveDateTime = row.try_get("", "updated_at")?;
+            if (updated_at - created_at).num_seconds() < EDITED_AFTER_SECONDS {
+                continue;
+            }
+            let mut object = match metadata.as_deref().map(serde_json::from_str::<Value>) {
+                None => Map::new(),
+                Some(Ok(Value::Object(object))) => object,
+                // Not ours to rewrite
+                Some(_) => continue,
+            };
+            if object.contains_key("refresh") || object.contains_key("edited_fields") {
+                continue;
+            }
+            object.insert(
+                "edited_fields".to_string(),
+                serde_json::json!(["title", "content"]),
+            );
+            db.execute(Statement::from_sql_and_values(
+                backend,
+                "UPDATE items SET metadata = ? WHERE id = ?",
+                [Value::Object(object).to_string().into(), id.into()],
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Edits recorded since can't be told apart from the ones seeded here
+        Ok(())
+    }
+}
@@ -3,6 +3,14 @@ pub use sea_orm_migration::prelude::*;
 mod m20241215_000001_create_items_table;
 mod m20250916_000001_add_bookmark_fields;
 mod m20250916_003241_add_file_metadata_fields;
+mod m20261017_000001_create_index_outbox;
+mod m20261017_000002_create_items_fts;
+mod m20261017_000003_add_canonical_url;
+mod m20261017_000004_create_feeds;
+mod m20261017_000005_create_fetch_profiles;
+mod m20261017_000006_create_blobs;
+mod m20261017_000007_create_watched_paths;
+mod m20261017_000008_mark_edited_bookmarks;
 
 pub struct Migrator;
 
@@ -13,6 +21,14 @@ impl MigratorTrait for Migrator {
             Box::new(m20241215_000001_create_items_table::Migration),
             Box::new(m20250916_000001_add_bookmark_fields::Migration),
             Box::new(m20250916_003241_add_file_metadata_fields::Migration),
+            Box::new(m20261017_000001_create_index_outbox::Migration),
+            Box::new(m20261017_000002_create_items_fts::Migration),
+            Box::new(m20261017_000003_add_canonical_url::Migration),
+            Box::new(m20261017_000004_create_feeds::Migration),
+            Box::new(m20261017_000005_create_fetch_profiles::Migration),
+            Box::new(m20261017_000006_create_blobs::Migration),
+            Box::new(m20261017_000007_create_watched_paths::Migration),
+            Box::new(m20261017_000008_mark_edited_bookmarks::Migration),
         ]
     }
 }
@@ -0,0 +1,938 @@
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use futures::stream::{self, StreamExt};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::bookmarks::{
+    canonicalize, metadata_object, AssetStore, BookmarkError, BookmarkMetadata, BookmarkProcessor,
+    Capture, HostLimiter,
+};
+use crate::entities::{item, Item, ItemActiveModel, ItemModel};
+use crate::indexer::{self, IndexOperation, IndexerState};
+
+/// Start after the link checker so the two don't both hit every site at launch.
+const STARTUP_DELAY: Duration = Duration::from_secs(2 * 60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// How long fetched metadata is trusted before the page is fetched again.
+const REFRESH_AFTER_DAYS: i64 = 30;
+/// How soon a bookmark saved offline is first retried; failing is quick while offline anyway.
+/// Each further failure doubles the wait, up to `REFRESH_AFTER_DAYS`.
+const RETRY_PENDING_AFTER_MINUTES: i64 = 10;
+const SWEEP_BATCH_SIZE: usize = 20;
+const CONCURRENT_REFRESHES: usize = 2;
+const PER_HOST_INTERVAL: Duration = Duration::from_secs(2);
+/// Prior titles and descriptions kept per bookmark, oldest dropped first.
+const MAX_HISTORY: usize = 20;
+
+pub const BOOKMARK_REFRESHED_EVENT: &str = "bookmark-refreshed";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshState {
+    /// Saved without being fetched, usually while offline; the title is just the URL.
+    PendingEnrichment,
+    #[default]
+    Enriched,
+}
+
+/// How a bookmark's metadata was last fetched, stored under `refresh` in its metadata.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RefreshStatus {
+    pub state: RefreshState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempted_at: Option<NaiveDateTime>,
+    /// When a fetch last succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refreshed_at: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Consecutive failed attempts.
+    #[serde(default)]
+    pub failures: u32,
+}
+
+/// A title and description a refresh replaced, kept under `history` in the metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataRevision {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub replaced_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BookmarkRefreshedEvent {
+    pub item_id: i32,
+    pub refresh: RefreshStatus,
+    /// Whether the title, content or description changed.
+    pub changed: bool,
+    /// The item this bookmark, saved offline, turned out to duplicate once fetched. It has been
+    /// removed in favour of that one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_into: Option<i32>,
+}
+
+/// Where a refresh keeps the icon, preview image and snapshot of a bookmark that never had
+/// them. Either can be missing, in which case that part is skipped.
+#[derive(Default)]
+pub struct Stores {
+    pub images: Option<AssetStore>,
+    pub snapshots: Option<AssetStore>,
+}
+
+impl Stores {
+    pub fn open(app: &tauri::AppHandle) -> Self {
+        let open = |store: Result<AssetStore, BookmarkError>| match store {
+            Ok(store) => Some(store),
+            Err(e) => {
+                eprintln!("Refreshed bookmarks will miss some assets: {}", e);
+                None
+            }
+        };
+        Self {
+            images: open(AssetStore::open(app)),
+            snapshots: open(AssetStore::open_snapshots(app)),
+        }
+    }
+}
+
+/// A bookmark for a URL that couldn't be fetched, to be filled in by a later refresh.
+pub fn placeholder(url: &str, error: &BookmarkError) -> ItemActiveModel {
+    let refresh = RefreshStatus {
+        state: RefreshState::PendingEnrichment,
+        attempted_at: Some(Utc::now().naive_utc()),
+        error: Some(error.to_string()),
+        failures: 1,
+        ..Default::default()
+    };
+    ItemActiveModel {
+        title: Set(url.to_string()),
+        content: Set(None),
+        item_type: Set("bookmark".to_string()),
+        tags: Set(None),
+        source_type: Set(Some("bookmark".to_string())),
+        source_url: Set(Some(url.to_string())),
+        canonical_url: Set(canonicalize(url)),
+        metadata: Set(Some(serde_json::json!({ "refresh": refresh }).to_string())),
+        ..Default::default()
+    }
+}
+
+/// Note in `metadata` that `fields` were edited by hand, so refreshes keep them as they are.
+pub fn mark_edited(metadata: Option<&str>, fields: &[&str]) -> Option<String> {
+    if fields.is_empty() {
+        return metadata.map(str::to_string);
+    }
+    let Some(mut object) = metadata_object(metadata) else {
+        // Not ours to rewrite
+        return metadata.map(str::to_string);
+    };
+    let mut edited = edited_fields(&object);
+    for field in fields {
+        if !edited.iter().any(|edited| edited == field) {
+            edited.push(field.to_string());
+        }
+    }
+    object.insert("edited_fields".to_string(), edited.into());
+    Some(Value::Object(object).to_string())
+}
+
+/// Note in `metadata` that an import brought the bookmark in `now` without fetching it, so the
+/// first refresh is a full period away however long ago it was first saved.
+pub fn mark_imported(metadata: Option<&str>, now: NaiveDateTime) -> Option<String> {
+    with_status(
+        metadata,
+        RefreshStatus {
+            attempted_at: Some(now),
+            ..Default::default()
+        },
+    )
+}
+
+/// Note in `metadata` that the bookmark's page was fetched `now` by something other than a
+/// refresh, such as an import's enrichment.
+pub fn mark_refreshed(metadata: Option<&str>, now: NaiveDateTime) -> Option<String> {
+    with_status(
+        metadata,
+        RefreshStatus {
+            attempted_at: Some(now),
+            refreshed_at: Some(now),
+            ..Default::default()
+        },
+    )
+}
+
+fn with_status(metadata: Option<&str>, status: RefreshStatus) -> Option<String> {
+    let Some(mut object) = metadata_object(metadata) else {
+        return metadata.map(str::to_string);
+    };
+    object.insert("refresh".to_string(), serde_json::json!(status));
+    Some(Value::Object(object).to_string())
+}
+
+pub fn start_worker(app: tauri::AppHandle, db: DatabaseConnection, indexer: IndexerState) {
+    tauri::async_runtime::spawn(async move {
+        let limiter = HostLimiter::new(PER_HOST_INTERVAL);
+        let stores = Stores::open(&app);
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        loop {
+            // Rebuilt each sweep so edited fetch profiles take effect
+            let processor = BookmarkProcessor::with_saved_profiles(&db).await;
+            let refreshed = sweep(
+                &db,
+                &processor,
+                &stores,
+                &limiter,
+                SWEEP_BATCH_SIZE,
+                |event| {
+                    if event.refresh.error.is_none() {
+                        indexer.wake();
+                    }
+                    let _ = app.emit(BOOKMARK_REFRESHED_EVENT, event);
+                },
+            )
+            .await;
+            match refreshed {
+                Ok(0) => {}
+                Ok(count) => println!("Refreshed {} bookmarks", count),
+                Err(e) => eprintln!("Failed to refresh bookmarks: {}", e),
+            }
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+        }
+    });
+}
+
+/// Refresh up to `limit` bookmarks that are waiting to be enriched or whose metadata is stale,
+/// calling `on_refreshed` as each one is recorded. Returns how many were attempted.
+pub async fn sweep(
+    db: &DatabaseConnection,
+    processor: &BookmarkProcessor,
+    stores: &Stores,
+    limiter: &HostLimiter,
+    limit: usize,
+    on_refreshed: impl Fn(&BookmarkRefreshedEvent),
+) -> Result<usize, DbErr> {
+    let due = due_bookmarks(db, Utc::now().naive_utc(), limit).await?;
+    let on_refreshed = Arc::new(on_refreshed);
+
+    let results = stream::iter(due)
+        .map(|(id, url)| {
+            let on_refreshed = on_refreshed.clone();
+            async move {
+                limiter.wait_for_url(&url).await;
+                let event = refresh_item(db, processor, stores, id).await?;
+                if let Some(event) = event {
+                    on_refreshed(&event);
+                }
+                Ok::<_, DbErr>(())
+            }
+        })
+        .buffer_unordered(CONCURRENT_REFRESHES)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut attempted = 0;
+    for result in results {
+        result?;
+        attempted += 1;
+    }
+    Ok(attempted)
+}
+
+/// Fetch a bookmark's page again and update it from what it says now. Titles and content
+/// edited by hand are kept, and replaced titles and descriptions go into the history. A
+/// failed fetch is recorded in the item's `refresh` status rather than returned as an error.
+/// Returns `None` if the item is gone, isn't a bookmark, has no URL, or has metadata that
+/// isn't a JSON object, which isn't ours to rewrite.
+pub async fn refresh_item(
+    db: &DatabaseConnection,
+    processor: &BookmarkProcessor,
+    stores: &Stores,
+    id: i32,
+) -> Result<Option<BookmarkRefreshedEvent>, DbErr> {
+    let Some(item) = Item::find_by_id(id).one(db).await? else {
+        return Ok(None);
+    };
+    // Notes and files can have a URL too, but the page doesn't describe them
+    if item.item_type != "bookmark" {
+        return Ok(None);
+    }
+    if metadata_object(item.metadata.as_deref()).is_none() {
+        return Ok(None);
+    }
+    let Some(url) = item.source_url else {
+        return Ok(None);
+    };
+
+    let page = match processor.capture(&url).await {
+        Ok(Capture::Page(page)) => Ok(*page),
+        Ok(_) => Err(format!("{} is no longer a web page", url)),
+        Err(e) => Err(e.to_string()),
+    };
+    // Re-read after the fetch so edits made in the meantime aren't lost
+    let Some(item) = Item::find_by_id(id).one(db).await? else {
+        return Ok(None);
+    };
+    let Some(metadata) = metadata_object(item.metadata.as_deref()) else {
+        return Ok(None);
+    };
+    let now = Utc::now().naive_utc();
+
+    match page {
+        Ok(mut page) => {
+            if refresh_status(&metadata).state == RefreshState::PendingEnrichment {
+                fetch_assets(processor, stores, &mut page).await;
+            }
+            apply(db, item, metadata, page, now).await.map(Some)
+        }
+        Err(error) => record_failure(db, item, metadata, error, now)
+            .await
+            .map(Some),
+    }
+}
+
+/// Get the images and snapshot a bookmark saved offline never had.
+async fn fetch_assets(processor: &BookmarkProcessor, stores: &Stores, page: &mut BookmarkMetadata) {
+    if let Some(store) = &stores.images {
+        processor.fetch_assets(page, store).await;
+    }
+    if let Some(store) = &stores.snapshots {
+        if let Err(e) = processor.save_snapshot(page, store).await {
+            eprintln!("No snapshot saved for {}: {}", page.url, e);
+        }
+    }
+}
+
+async fn apply(
+    db: &DatabaseConnection,
+    item: ItemModel,
+    mut metadata: Map<String, Value>,
+    page: BookmarkMetadata,
+    now: NaiveDateTime,
+) -> Result<BookmarkRefreshedEvent, DbErr> {
+    let pending = refresh_status(&metadata).state == RefreshState::PendingEnrichment;
+    let edited = edited_fields(&metadata);
+    let is_edited = |field: &str| edited.iter().any(|edited| edited == field);
+
+    // A bookmark saved offline was keyed on the URL as typed. The page's canonical link or
+    // redirect may show it is one we already have, as `create_bookmark` would have found.
+    let mut canonical_url = item.canonical_url.clone();
+    if pending && page.canonical_url.is_some() && page.canonical_url != item.canonical_url {
+        let existing = Item::find()
+            .filter(item::Column::CanonicalUrl.eq(page.canonical_url.as_deref()))
+            .filter(item::Column::Id.ne(item.id))
+            .one(db)
+            .await?;
+        match existing {
+            // Nothing of the user's would be lost, so keep just the existing item
+            Some(existing) if edited.is_empty() && item.tags.is_none() => {
+                return merge_into(db, item, existing, now).await;
+            }
+            // Otherwise leave both and point at the other for the user to sort out
+            Some(existing) => {
+                metadata.insert("duplicate_of".to_string(), Value::from(existing.id));
+            }
+            None => canonical_url = page.canonical_url.clone(),
+        }
+    }
+
+    let title = if is_edited("title") {
+        item.title.clone()
+    } else {
+        page.title.clone()
+    };
+    let content = if is_edited("content") {
+        item.content.clone()
+    } else {
+        page.article
+            .clone()
+            .or(page.description.clone())
+            .or(item.content.clone())
+    };
+    let old_description = metadata
+        .get("description")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let title_changed = title != item.title;
+    let content_changed = content != item.content;
+    let description_changed = page.details.description != old_description;
+
+    // A placeholder's title is only its URL, which isn't worth remembering
+    if !pending && (title_changed || description_changed) {
+        let mut history = metadata
+            .get("history")
+            .cloned()
+            .and_then(|history| serde_json::from_value::<Vec<MetadataRevision>>(history).ok())
+            .unwrap_or_default();
+        history.push(MetadataRevision {
+            title: item.title.clone(),
+            description: old_description,
+            replaced_at: now,
+        });
+        let excess = history.len().saturating_sub(MAX_HISTORY);
+        history.drain(..excess);
+        metadata.insert("history".to_string(), to_value(&history)?);
+    }
+
+    // What the page says now replaces what it said before; everything else is kept
+    if let Value::Object(fetched) = to_value(&page.details)? {
+        metadata.extend(fetched);
+    }
+    // The description only appears when the page has one, so a removed one is dropped here
+    if page.details.description.is_none() {
+        metadata.remove("description");
+    }
+    let refresh = RefreshStatus {
+        state: RefreshState::Enriched,
+        attempted_at: Some(now),
+        refreshed_at: Some(now),
+        error: None,
+        failures: 0,
+    };
+    metadata.insert("refresh".to_string(), to_value(&refresh)?);
+
+    let changed = title_changed || content_changed || description_changed;
+    let mut active: ItemActiveModel = item.into();
+    active.title = Set(title);
+    active.content = Set(content);
+    active.metadata = Set(Some(Value::Object(metadata).to_string()));
+    active.canonical_url = Set(canonical_url);
+    // Only a visible change counts as an update; a refresh that found nothing new doesn't
+    if title_changed || content_changed {
+        active.updated_at = Set(now);
+    }
+
+    let txn = db.begin().await?;
+    let updated = active.update(&txn).await?;
+    indexer::enqueue(&txn, updated.id, IndexOperation::Upsert).await?;
+    txn.commit().await?;
+
+    Ok(BookmarkRefreshedEvent {
+        item_id: updated.id,
+        refresh,
+        changed,
+        merged_into: None,
+    })
+}
+
+/// Remove a bookmark saved offline that turned out to be `existing`, which is left as it is.
+async fn merge_into(
+    db: &DatabaseConnection,
+    placeholder: ItemModel,
+    existing: ItemModel,
+    now: NaiveDateTime,
+) -> Result<BookmarkRefreshedEvent, DbErr> {
+    let txn = db.begin().await?;
+    Item::delete_by_id(placeholder.id).exec(&txn).await?;
+    indexer::enqueue(&txn, placeholder.id, IndexOperation::Delete).await?;
+    txn.commit().await?;
+
+    Ok(BookmarkRefreshedEvent {
+        item_id: placeholder.id,
+        refresh: RefreshStatus {
+            state: RefreshState::Enriched,
+            attempted_at: Some(now),
+            refreshed_at: Some(now),
+            error: None,
+            failures: 0,
+        },
+        changed: false,
+        merged_into: Some(existing.id),
+    })
+}
+
+/// Store a failed attempt without touching `updated_at` or anything else.
+async fn record_failure(
+    db: &DatabaseConnection,
+    item: ItemModel,
+    mut metadata: Map<String, Value>,
+    error: String,
+    now: NaiveDateTime,
+) -> Result<BookmarkRefreshedEvent, DbErr> {
+    let previous = refresh_status(&metadata);
+    let refresh = RefreshStatus {
+        attempted_at: Some(now),
+        error: Some(error),
+        failures: previous.failures + 1,
+        ..previous
+    };
+    metadata.insert("refresh".to_string(), to_value(&refresh)?);
+
+    Item::update_many()
+        .col_expr(
+            item::Column::Metadata,
+            Expr::value(Value::Object(metadata).to_string()),
+        )
+        .filter(item::Column::Id.eq(item.id))
+        .exec(db)
+        .await?;
+    Ok(BookmarkRefreshedEvent {
+        item_id: item.id,
+        refresh,
+        changed: false,
+        merged_into: None,
+    })
+}
+
+/// Bookmarks saved offline and due a retry, then the ones refreshed longest ago, as ids and
+/// URLs.
+async fn due_bookmarks(
+    db: &DatabaseConnection,
+    now: NaiveDateTime,
+    limit: usize,
+) -> Result<Vec<(i32, String)>, DbErr> {
+    let rows: Vec<(i32, Option<String>, Option<String>, NaiveDateTime)> = Item::find()
+        .select_only()
+        .column(item::Column::Id)
+        .column(item::Column::SourceUrl)
+        .column(item::Column::Metadata)
+        .column(item::Column::UpdatedAt)
+        .filter(item::Column::ItemType.eq("bookmark"))
+        .filter(item::Column::SourceUrl.is_not_null())
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    // The status lives in the metadata JSON, so what's due is worked out here
+    let stale_before = now - ChronoDuration::days(REFRESH_AFTER_DAYS);
+    let mut due = rows
+        .into_iter()
+        .filter_map(|(id, url, metadata, updated_at)| {
+            // Metadata that isn't a JSON object can't take a status, so it is left alone
+            let status = refresh_status(&metadata_object(metadata.as_deref())?);
+            let pending = status.state == RefreshState::PendingEnrichment;
+            // Items captured before refreshes existed count from when they last changed, which
+            // for a capture is when its page was fetched
+            let last_attempt = status.attempted_at.unwrap_or(updated_at);
+            let cutoff = if pending {
+                now - retry_interval(status.failures)
+            } else {
+                stale_before
+            };
+            (last_attempt <= cutoff).then_some((!pending, last_attempt, id, url?))
+        })
+        .collect::<Vec<_>>();
+    // Pending ones first (`false` sorts before `true`), then oldest attempt first
+    due.sort_by_key(|(enriched, last_attempt, id, _)| (*enriched, *last_attempt, *id));
+    Ok(due
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, id, url)| (id, url))
+        .collect())
+}
+
+/// How long a bookmark saved offline waits after its `failures`th failed attempt, so a page
+/// that's gone for good isn't fetched every few minutes forever.
+fn retry_interval(failures: u32) -> ChronoDuration {
+    let doublings = failures.saturating_sub(1).min(20);
+    ChronoDuration::minutes(RETRY_PENDING_AFTER_MINUTES << doublings)
+        .min(ChronoDuration::days(REFRESH_AFTER_DAYS))
+}
+
+fn refresh_status(metadata: &Map<String, Value>) -> RefreshStatus {
+    metadata
+        .get("refresh")
+        .cloned()
+        .and_then(|status| serde_json::from_value(status).ok())
+        .unwrap_or_default()
+}
+
+fn edited_fields(metadata: &Map<String, Value>) -> Vec<String> {
+    metadata
+        .get("edited_fields")
+        .cloned()
+        .and_then(|fields| serde_json::from_value(fields).ok())
+        .unwrap_or_default()
+}
+
+fn to_value(value: &impl Serialize) -> Result<Value, DbErr> {
+    serde_json::to_value(value).map_err(|e| DbErr::Custom(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{test_database, HttpStub, StubResponse};
+
+    fn page(title: &str, description: &str) -> StubResponse {
+        StubResponse::new(
+            200,
+            format!(
+                "<html><head><title>{}</title><meta name=\"description\" content=\"{}\">\
+                 </head><body></body></html>",
+                title, description
+            ),
+        )
+        .header("Content-Type", "text/html")
+    }
+
+    async fn bookmark(db: &DatabaseConnection, item: ItemActiveModel) -> ItemModel {
+        item.insert(db).await.expect("insert bookmark")
+    }
+
+    async fn reload(db: &DatabaseConnection, id: i32) -> (ItemModel, Map<String, Value>) {
+        let item = Item::find_by_id(id)
+            .one(db)
+            .await
+            .expect("query")
+            .expect("item");
+        let metadata = metadata_object(item.metadata.as_deref()).expect("metadata");
+        (item, metadata)
+    }
+
+    #[tokio::test]
+    async fn placeholders_are_enriched_once_reachable() {
+        let stub = HttpStub::start(vec![("/later", page("Read later", "Worth it"))]).await;
+        let db = test_database("refresher-pending").await;
+        let url = stub.url("/later");
+        let placeholder = bookmark(&db, placeholder(&url, &BookmarkError::Timeout)).await;
+        assert_eq!(placeholder.title, url);
+
+        let due = due_bookmarks(
+            &db,
+            Utc::now().naive_utc() + ChronoDuration::minutes(RETRY_PENDING_AFTER_MINUTES),
+            10,
+        )
+        .await
+        .expect("due");
+        assert_eq!(due, vec![(placeholder.id, url.clone())]);
+
+        let event = refresh_item(
+            &db,
+            &BookmarkProcessor::new(),
+            &Stores::default(),
+            placeholder.id,
+        )
+        .await
+        .expect("refresh")
+        .expect("event");
+        assert!(event.changed);
+        assert_eq!(event.refresh.state, RefreshState::Enriched);
+        assert_eq!(event.refresh.failures, 0);
+
+        let (item, metadata) = reload(&db, placeholder.id).await;
+        assert_eq!(item.title, "Read later");
+        assert_eq!(item.content.as_deref(), Some("Worth it"));
+        assert_eq!(metadata["description"], "Worth it");
+        // The URL it stood in with isn't history
+        assert!(metadata.get("history").is_none());
+        assert!(due_bookmarks(&db, Utc::now().naive_utc(), 10)
+            .await
+            .expect("due")
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn placeholders_for_pages_already_saved_are_merged() {
+        let stub = HttpStub::start(vec![(
+            "/share",
+            StubResponse::new(
+                200,
+                "<html><head><title>Shared</title>\
+                 <link rel=\"canonical\" href=\"/article\"></head></html>",
+            )
+            .header("Content-Type", "text/html"),
+        )])
+        .await;
+        let db = test_database("refresher-pending-merge").await;
+        let saved = bookmark(
+            &db,
+            ItemActiveModel {
+                title: Set("Article".to_string()),
+                item_type: Set("bookmark".to_string()),
+                source_url: Set(Some(stub.url("/article"))),
+                canonical_url: Set(canonicalize(&stub.url("/article"))),
+                ..Default::default()
+            },
+        )
+        .await;
+        let placeholder = bookmark(
+            &db,
+            placeholder(&stub.url("/share"), &BookmarkError::Timeout),
+        )
+        .await;
+
+        let event = refresh_item(
+            &db,
+            &BookmarkProcessor::new(),
+            &Stores::default(),
+            placeholder.id,
+        )
+        .await
+        .expect("refresh")
+        .expect("event");
+        assert_eq!(event.merged_into, Some(saved.id));
+        assert!(Item::find_by_id(placeholder.id)
+            .one(&db)
+            .await
+            .expect("query")
+            .is_none());
+        let (item, _) = reload(&db, saved.id).await;
+        assert_eq!(item.title, "Article");
+    }
+
+    #[tokio::test]
+    async fn only_bookmarks_are_refreshed() {
+        let stub = HttpStub::start(vec![("/page", page("Page title", "Page summary"))]).await;
+        let db = test_database("refresher-bookmarks-only").await;
+        let note = bookmark(
+            &db,
+            ItemActiveModel {
+                title: Set("My note".to_string()),
+                content: Set(Some("Mine".to_string())),
+                item_type: Set("note".to_string()),
+                source_url: Set(Some(stub.url("/page"))),
+                ..Default::default()
+            },
+        )
+        .await;
+        let file = bookmark(
+            &db,
+            ItemActiveModel {
+                title: Set("report.pdf".to_string()),
+                item_type: Set("file".to_string()),
+                source_type: Set(Some("link".to_string())),
+                source_url: Set(Some("/home/me/report.pdf".to_string())),
+                metadata: Set(Some(r#"{"linked_file":{}}"#.to_string())),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        for item in [&note, &file] {
+            let event = refresh_item(&db, &BookmarkProcessor::new(), &Stores::default(), item.id)
+                .await
+                .expect("refresh");
+            assert!(event.is_none());
+            let unchanged = Item::find_by_id(item.id)
+                .one(&db)
+                .await
+                .expect("query")
+                .expect("item");
+            assert_eq!(&unchanged, item);
+        }
+        assert!(stub.paths().is_empty());
+    }
+
+    #[tokio::test]
+    async fn metadata_that_is_not_an_object_is_left_alone() {
+        let stub = HttpStub::start(vec![("/odd", page("Page title", "Page summary"))]).await;
+        let db = test_database("refresher-odd-metadata").await;
+        let odd = bookmark(
+            &db,
+            ItemActiveModel {
+                title: Set("Odd".to_string()),
+                item_type: Set("bookmark".to_string()),
+                source_url: Set(Some(stub.url("/odd"))),
+                metadata: Set(Some("not json".to_string())),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        assert!(
+            due_bookmarks(&db, Utc::now().naive_utc() + ChronoDuration::days(365), 10)
+                .await
+                .expect("due")
+                .is_empty()
+        );
+        let event = refresh_item(&db, &BookmarkProcessor::new(), &Stores::default(), odd.id)
+            .await
+            .expect("refresh");
+        assert!(event.is_none());
+        let unchanged = Item::find_by_id(odd.id)
+            .one(&db)
+            .await
+            .expect("query")
+            .expect("item");
+        assert_eq!(unchanged, odd);
+        assert!(stub.paths().is_empty());
+    }
+
+    #[tokio::test]
+    async fn refreshes_keep_edits_and_history() {
+        let stub = HttpStub::start(vec![
+            ("/renamed", page("New title", "New summary")),
+            ("/edited", page("Site title", "Site summary")),
+        ])
+        .await;
+        let db = test_database("refresher-history").await;
+        let renamed = bookmark(
+            &db,
+            ItemActiveModel {
+                title: Set("Old title".to_string()),
+                content: Set(Some("Old summary".to_string())),
+                item_type: Set("bookmark".to_string()),
+                source_url: Set(Some(stub.url("/renamed"))),
+                metadata: Set(Some(
+                    r#"{"description":"Old summary","imported_from":"pocket"}"#.to_string(),
+                )),
+                ..Default::default()
+            },
+        )
+        .await;
+        let edited = bookmark(
+            &db,
+            ItemActiveModel {
+                title: Set("My name for it".to_string()),
+                content: Set(Some("My notes".to_string())),
+                item_type: Set("bookmark".to_string()),
+                source_url: Set(Some(stub.url("/edited"))),
+                metadata: Set(mark_edited(
+                    Some(r#"{"description":"Site summary"}"#),
+                    &["title", "content"],
+                )),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let processor = BookmarkProcessor::new();
+        refresh_item(&db, &processor, &Stores::default(), renamed.id)
+            .await
+            .expect("refresh");
+        let (item, metadata) = reload(&db, renamed.id).await;
+        assert_eq!(item.title, "New title");
+        assert_eq!(item.content.as_deref(), Some("New summary"));
+        assert_eq!(metadata["imported_from"], "pocket");
+        let history: Vec<MetadataRevision> =
+            serde_json::from_value(metadata["history"].clone()).expect("history");
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].title, "Old title");
+        assert_eq!(history[0].description.as_deref(), Some("Old summary"));
+
+        let event = refresh_item(&db, &processor, &Stores::default(), edited.id)
+            .await
+            .expect("refresh")
+            .expect("event");
+        assert!(!event.changed);
+        let (item, metadata) = reload(&db, edited.id).await;
+        assert_eq!(item.title, "My name for it");
+        assert_eq!(item.content.as_deref(), Some("My notes"));
+        assert_eq!(item.updated_at, edited.updated_at);
+        assert!(metadata.get("history").is_none());
+        assert_eq!(refresh_status(&metadata).state, RefreshState::Enriched);
+    }
+
+    #[tokio::test]
+    async fn failures_are_recorded_and_retried_later() {
+        let stub = HttpStub::start(vec![]).await;
+        let db = test_database("refresher-failure").await;
+        let stale = bookmark(
+            &db,
+            ItemActiveModel {
+                title: Set("Stale".to_string()),
+                item_type: Set("bookmark".to_string()),
+                source_url: Set(Some("not a url".to_string())),
+                created_at: Set(Utc::now().naive_utc() - ChronoDuration::days(60)),
+                updated_at: Set(Utc::now().naive_utc() - ChronoDuration::days(60)),
+                ..Default::default()
+            },
+        )
+        .await;
+        // Saved just now, so not due yet
+        bookmark(
+            &db,
+            ItemActiveModel {
+                title: Set("Recent".to_string()),
+                item_type: Set("bookmark".to_string()),
+                source_url: Set(Some(stub.url("/recent"))),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let events = std::sync::Mutex::new(Vec::new());
+        let attempted = sweep(
+            &db,
+            &BookmarkProcessor::new(),
+            &Stores::default(),
+            &HostLimiter::new(Duration::from_millis(1)),
+            10,
+            |event| events.lock().expect("events").push(event.clone()),
+        )
+        .await
+        .expect("sweep");
+        assert_eq!(attempted, 1);
+        let events = events.into_inner().expect("events");
+        assert_eq!(events[0].item_id, stale.id);
+        assert!(events[0].refresh.error.is_some());
+
+        let (item, metadata) = reload(&db, stale.id).await;
+        assert_eq!(item.title, "Stale");
+        assert_eq!(item.updated_at, stale.updated_at);
+        let status = refresh_status(&metadata);
+        assert_eq!(status.state, RefreshState::Enriched);
+        assert_eq!(status.failures, 1);
+        assert_eq!(status.refreshed_at, None);
+
+        // Tried just now, so not due again until the next refresh period
+        let due = due_bookmarks(&db, Utc::now().naive_utc(), 10)
+            .await
+            .expect("due");
+        assert!(due.is_empty());
+        assert!(stub.paths().is_empty());
+    }
+
+    #[tokio::test]
+    async fn placeholders_back_off_after_each_failure() {
+        let db = test_database("refresher-backoff").await;
+        let url = "https://example.com/gone";
+        let placeholder = bookmark(&db, placeholder(url, &BookmarkError::Timeout)).await;
+        let now = Utc::now().naive_utc();
+        let event = record_failure(&db, placeholder, "timed out".to_string(), now)
+            .await
+            .expect("record failure");
+        assert_eq!(event.refresh.failures, 2);
+
+        let due_after = |minutes: i64| {
+            let db = &db;
+            async move {
+                due_bookmarks(db, now + ChronoDuration::minutes(minutes), 10)
+                    .await
+                    .expect("due")
+            }
+        };
+        assert!(due_after(RETRY_PENDING_AFTER_MINUTES).await.is_empty());
+        assert_eq!(
+            due_after(2 * RETRY_PENDING_AFTER_MINUTES).await,
+            vec![(event.item_id, url.to_string())]
+        );
+
+        assert_eq!(
+            retry_interval(1),
+            ChronoDuration::minutes(RETRY_PENDING_AFTER_MINUTES)
+        );
+        assert_eq!(
+            retry_interval(u32::MAX),
+            ChronoDuration::days(REFRESH_AFTER_DAYS)
+        );
+    }
+
+    #[test]
+    fn edits_are_marked_once() {
+        let marked = mark_edited(Some(r#"{"lang":"en"}"#), &["title"]);
+        let marked = mark_edited(marked.as_deref(), &["title", "content"]);
+        assert_eq!(
+            marked.as_deref(),
+            Some(r#"{"edited_fields":["title","content"],"lang":"en"}"#)
+        );
+        assert_eq!(
+            mark_edited(None, &["title"]).as_deref(),
+            Some(r#"{"edited_fields":["title"]}"#)
+        );
+        assert_eq!(
+            mark_edited(Some("not json"), &["title"]).as_deref(),
+            Some("not json")
+        );
+    }
+}
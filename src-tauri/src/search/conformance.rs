@@ -0,0 +1,215 @@
+//! Behaviour every `SearchBackend` has to share, run against each implementation.
+
+use chrono::NaiveDateTime;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+
+use super::{SearchBackend, SearchQuery, SearchResults, SearchSort};
+use crate::entities::{Item, ItemActiveModel, ItemModel};
+
+fn at(seconds: i64) -> NaiveDateTime {
+    chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0)
+        .expect("valid timestamp")
+        .naive_utc()
+}
+
+async fn insert(
+    db: &DatabaseConnection,
+    title: &str,
+    content: Option<&str>,
+    item_type: &str,
+    tags: Option<&str>,
+    created: i64,
+) -> ItemModel {
+    ItemActiveModel {
+        title: Set(title.to_string()),
+        content: Set(content.map(str::to_string)),
+        item_type: Set(item_type.to_string()),
+        tags: Set(tags.map(str::to_string)),
+        created_at: Set(at(created)),
+        updated_at: Set(at(created)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .expect("insert item")
+}
+
+async fn search(backend: &dyn SearchBackend, query: SearchQuery) -> SearchResults {
+    backend.search(&query).await.expect("search")
+}
+
+fn ids(results: &SearchResults) -> Vec<i32> {
+    results.hits.iter().map(|hit| hit.id).collect()
+}
+
+fn facet(results: &[super::FacetCount], field: &str, value: &str) -> Option<u64> {
+    results
+        .iter()
+        .find(|facet| facet.field == field)?
+        .values
+        .iter()
+        .find(|facet| facet.value == value)
+        .map(|facet| facet.count)
+}
+
+pub async fn run(backend: &dyn SearchBackend, db: &DatabaseConnection) {
+    let ownership = insert(
+        db,
+        "Rust ownership guide",
+        Some("Borrowing and lifetimes explained"),
+        "note",
+        Some("rust, programming"),
+        10,
+    )
+    .await;
+    let sourdough = insert(
+        db,
+        "Sourdough starter",
+        Some("Feed the starter daily with flour"),
+        "note",
+        Some("cooking"),
+        20,
+    )
+    .await;
+    let futures = insert(
+        db,
+        "Rust async book",
+        Some("Futures and executors"),
+        "bookmark",
+        Some("rust, reading"),
+        30,
+    )
+    .await;
+    let receipts = insert(db, "Tax receipts", None, "file", None, 40).await;
+    let mut active: ItemActiveModel = futures.clone().into();
+    active.metadata = Set(Some(r#"{"subtype":"article"}"#.to_string()));
+    let futures = active.update(db).await.expect("set subtype");
+
+    // Start from a full rebuild so backends that index out of band see the fixtures
+    let report = backend.reindex(db).await.expect("reindex");
+    assert_eq!(report.indexed, 4);
+    assert!(report.failed.is_empty());
+
+    let results = search(
+        backend,
+        SearchQuery {
+            query: "rust".to_string(),
+            sort: SearchSort::CreatedAsc,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(results.found, 2);
+    assert_eq!(ids(&results), vec![ownership.id, futures.id]);
+    let title = results.hits[0]
+        .highlights
+        .iter()
+        .find(|highlight| highlight.field == "title")
+        .expect("title highlight");
+    assert!(title.snippet.contains("<mark>"));
+    assert!(title
+        .matched_tokens
+        .iter()
+        .any(|token| token.eq_ignore_ascii_case("rust")));
+
+    let facets = backend
+        .facets(&SearchQuery {
+            query: "rust".to_string(),
+            ..Default::default()
+        })
+        .await
+        .expect("facets");
+    assert_eq!(facet(&facets, "item_type", "note"), Some(1));
+    assert_eq!(facet(&facets, "item_type", "bookmark"), Some(1));
+    assert_eq!(facet(&facets, "tags", "rust"), Some(2));
+    assert_eq!(facet(&facets, "tags", "cooking"), None);
+    assert_eq!(facet(&facets, "subtype", "article"), Some(1));
+
+    let results = search(
+        backend,
+        SearchQuery {
+            query: "rust".to_string(),
+            item_type: Some("bookmark".to_string()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(ids(&results), vec![futures.id]);
+
+    let results = search(
+        backend,
+        SearchQuery {
+            subtype: Some("article".to_string()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(ids(&results), vec![futures.id]);
+
+    let results = search(
+        backend,
+        SearchQuery {
+            tags: vec!["cooking".to_string()],
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(ids(&results), vec![sourdough.id]);
+
+    let results = search(
+        backend,
+        SearchQuery {
+            sort: SearchSort::CreatedAsc,
+            page: Some(2),
+            per_page: Some(3),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(results.found, 4);
+    assert_eq!(results.page, 2);
+    assert_eq!(ids(&results), vec![receipts.id]);
+
+    let results = search(
+        backend,
+        SearchQuery {
+            sort: SearchSort::UpdatedDesc,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(
+        ids(&results),
+        vec![receipts.id, futures.id, sourdough.id, ownership.id]
+    );
+
+    let mut active: ItemActiveModel = sourdough.into();
+    active.title = Set("Rye loaf".to_string());
+    let updated = active.update(db).await.expect("update item");
+    backend.upsert(&updated).await.expect("upsert");
+    let results = search(
+        backend,
+        SearchQuery {
+            query: "rye".to_string(),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(ids(&results), vec![updated.id]);
+
+    Item::delete_by_id(receipts.id)
+        .exec(db)
+        .await
+        .expect("delete item");
+    backend.delete(receipts.id).await.expect("delete");
+    let results = search(
+        backend,
+        SearchQuery {
+            query: "receipts".to_string(),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(results.found, 0);
+    assert!(results.hits.is_empty());
+}
@@ -0,0 +1,376 @@
+use async_trait::async_trait;
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult, PaginatorTrait,
+    Statement, Value,
+};
+
+use super::{
+    DocumentHit, FacetCount, FacetValue, ReindexReport, SearchBackend, SearchError,
+    SearchHighlight, SearchQuery, SearchResults, SearchSort, MAX_FACET_VALUES,
+};
+use crate::entities::{Item, ItemModel};
+
+const MARK_START: &str = "<mark>";
+const MARK_END: &str = "</mark>";
+/// Tokens of context kept around matches in content snippets.
+const SNIPPET_TOKENS: i32 = 16;
+/// The bookmark subtype out of the `metadata` JSON, which isn't always valid JSON.
+const SUBTYPE_COLUMN: &str =
+    "CASE WHEN json_valid(items.metadata) THEN json_extract(items.metadata, '$.subtype') END";
+/// Whether the comma-separated `tags` column holds the tag bound to `?`, ignoring the spaces
+/// around commas.
+const TAG_MATCH: &str = "instr(',' || replace(replace(trim(items.tags), ', ', ','), ' ,', ',') \
+     || ',', ',' || ? || ',') > 0";
+
+/// Full-text search over the `items_fts` virtual table in the app's own SQLite database. The
+/// table is kept in step with `items` by triggers inside the writing transaction, so upserts
+/// and deletes have nothing left to do here.
+pub struct FtsBackend {
+    db: DatabaseConnection,
+}
+
+impl FtsBackend {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    async fn facet_counts(&self, matches: &Matches) -> Result<Vec<FacetCount>, SearchError> {
+        let mut facets = Vec::new();
+        for (field, statement) in [
+            ("item_type", matches.facet("items.item_type")),
+            ("tags", matches.tag_facet()),
+            ("subtype", matches.facet(SUBTYPE_COLUMN)),
+        ] {
+            let values = FacetRow::find_by_statement(statement)
+                .all(&self.db)
+                .await?
+                .into_iter()
+                .map(|row| FacetValue {
+                    value: row.value,
+                    count: row.count as u64,
+                })
+                .collect();
+            facets.push(FacetCount {
+                field: field.to_string(),
+                values,
+            });
+        }
+        Ok(facets)
+    }
+}
+
+#[derive(FromQueryResult)]
+struct FtsRow {
+    id: i32,
+    title_highlight: Option<String>,
+    content_snippet: Option<String>,
+}
+
+#[derive(FromQueryResult)]
+struct CountRow {
+    count: i64,
+}
+
+#[derive(FromQueryResult)]
+struct FacetRow {
+    value: String,
+    count: i64,
+}
+
+/// The `FROM` and `WHERE` clauses that pick out the items matching a query, shared by the
+/// page of hits, the total and the facets so each is worked out by SQLite.
+struct Matches {
+    sql: String,
+    values: Vec<Value>,
+    full_text: bool,
+}
+
+impl Matches {
+    fn new(query: &SearchQuery) -> Self {
+        let mut values: Vec<Value> = Vec::new();
+        let full_text = match match_expression(&query.query) {
+            Some(expression) => {
+                values.push(expression.into());
+                true
+            }
+            None => false,
+        };
+        let mut sql = if full_text {
+            "FROM items_fts JOIN items ON items.id = items_fts.rowid WHERE items_fts MATCH ?"
+                .to_string()
+        } else {
+            "FROM items WHERE 1 = 1".to_string()
+        };
+
+        if let Some(item_type) = query.item_type_filter() {
+            sql.push_str(" AND items.item_type = ?");
+            values.push(item_type.into());
+        }
+        if let Some(subtype) = query.subtype_filter() {
+            sql.push_str(&format!(" AND {} = ?", SUBTYPE_COLUMN));
+            values.push(subtype.into());
+        }
+        let tags = query.tag_filters();
+        if !tags.is_empty() {
+            sql.push_str(&format!(
+                " AND ({})",
+                vec![TAG_MATCH; tags.len()].join(" OR ")
+            ));
+            values.extend(tags.into_iter().map(Value::from));
+        }
+
+        Self {
+            sql,
+            values,
+            full_text,
+        }
+    }
+
+    fn statement(&self, sql: String, extra: impl IntoIterator<Item = Value>) -> Statement {
+        let mut values = self.values.clone();
+        values.extend(extra);
+        Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)
+    }
+
+    /// One page of hits in the order asked for, with highlights when there is text to match.
+    fn page(&self, query: &SearchQuery) -> Statement {
+        let columns = if self.full_text {
+            format!(
+                "items.id, highlight(items_fts, 0, '{start}', '{end}') AS title_highlight, \
+                 snippet(items_fts, 1, '{start}', '{end}', '…', {tokens}) AS content_snippet",
+                start = MARK_START,
+                end = MARK_END,
+                tokens = SNIPPET_TOKENS
+            )
+        } else {
+            "items.id, NULL AS title_highlight, NULL AS content_snippet".to_string()
+        };
+        let order = match query.sort {
+            // bm25 scores are negative, with better matches further below zero
+            SearchSort::Relevance if self.full_text => "bm25(items_fts), items.updated_at DESC",
+            SearchSort::Relevance | SearchSort::UpdatedDesc => "items.updated_at DESC",
+            SearchSort::UpdatedAsc => "items.updated_at ASC",
+            SearchSort::CreatedDesc => "items.created_at DESC",
+            SearchSort::CreatedAsc => "items.created_at ASC",
+        };
+        let per_page = query.per_page() as i64;
+        let offset = (query.page() as i64 - 1).saturating_mul(per_page);
+        self.statement(
+            format!(
+                "SELECT {} {} ORDER BY {}, items.id LIMIT ? OFFSET ?",
+                columns, self.sql, order
+            ),
+            [per_page.into(), offset.into()],
+        )
+    }
+
+    fn count(&self) -> Statement {
+        self.statement(format!("SELECT COUNT(*) AS count {}", self.sql), [])
+    }
+
+    /// The most common values of `column` among the matches.
+    fn facet(&self, column: &str) -> Statement {
+        self.statement(
+            format!(
+                "SELECT {column} AS value, COUNT(*) AS count {matches} AND {column} IS NOT NULL \
+                 GROUP BY value ORDER BY count DESC, value LIMIT {limit}",
+                column = column,
+                matches = self.sql,
+                limit = MAX_FACET_VALUES
+            ),
+            [],
+        )
+    }
+
+    /// The most common tags among the matches, split out of the comma-separated column.
+    fn tag_facet(&self) -> Statement {
+        self.statement(
+            format!(
+                "WITH RECURSIVE split(value, rest) AS ( \
+                 SELECT NULL, items.tags || ',' {matches} AND items.tags IS NOT NULL \
+                 UNION ALL \
+                 SELECT trim(substr(rest, 1, instr(rest, ',') - 1)), \
+                 substr(rest, instr(rest, ',') + 1) FROM split WHERE rest <> '') \
+                 SELECT value, COUNT(*) AS count FROM split WHERE value <> '' \
+                 GROUP BY value ORDER BY count DESC, value LIMIT {limit}",
+                matches = self.sql,
+                limit = MAX_FACET_VALUES
+            ),
+            [],
+        )
+    }
+}
+
+#[async_trait]
+impl SearchBackend for FtsBackend {
+    fn name(&self) -> &'static str {
+        "sqlite_fts"
+    }
+
+    async fn upsert(&self, _item: &ItemModel) -> Result<(), SearchError> {
+        Ok(())
+    }
+
+    async fn delete(&self, _id: i32) -> Result<(), SearchError> {
+        Ok(())
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults, SearchError> {
+        let matches = Matches::new(query);
+        let hits = FtsRow::find_by_statement(matches.page(query))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|row| DocumentHit {
+                id: row.id,
+                highlights: highlights(row.title_highlight, row.content_snippet),
+            })
+            .collect();
+        let found = CountRow::find_by_statement(matches.count())
+            .one(&self.db)
+            .await?
+            .map_or(0, |row| row.count as u64);
+
+        Ok(SearchResults {
+            hits,
+            found,
+            page: query.page(),
+            facets: self.facet_counts(&matches).await?,
+        })
+    }
+
+    async fn facets(&self, query: &SearchQuery) -> Result<Vec<FacetCount>, SearchError> {
+        self.facet_counts(&Matches::new(query)).await
+    }
+
+    async fn reindex(&self, db: &DatabaseConnection) -> Result<ReindexReport, SearchError> {
+        db.execute_unprepared("INSERT INTO items_fts(items_fts) VALUES ('rebuild')")
+            .await?;
+        let indexed = Item::find().count(db).await? as usize;
+        Ok(ReindexReport {
+            indexed,
+            failed: Vec::new(),
+        })
+    }
+}
+
+/// Turn free text into an FTS5 expression that ANDs a prefix match per word. Quoting each word
+/// keeps operators and punctuation in the input from being parsed as query syntax.
+fn match_expression(query: &str) -> Option<String> {
+    let terms = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect::<Vec<_>>();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn highlights(title: Option<String>, content: Option<String>) -> Vec<SearchHighlight> {
+    [("title", title), ("content", content)]
+        .into_iter()
+        .filter_map(|(field, snippet)| {
+            let snippet = snippet?;
+            let matched_tokens = matched_tokens(&snippet);
+            (!matched_tokens.is_empty()).then(|| SearchHighlight {
+                field: field.to_string(),
+                snippet,
+                matched_tokens,
+            })
+        })
+        .collect()
+}
+
+fn matched_tokens(snippet: &str) -> Vec<String> {
+    snippet
+        .split(MARK_START)
+        .skip(1)
+        .filter_map(|rest| {
+            rest.split_once(MARK_END)
+                .map(|(token, _)| token.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::ItemActiveModel;
+    use crate::search::conformance;
+    use sea_orm::{ActiveModelTrait, Set};
+
+    #[test]
+    fn match_expression_quotes_words() {
+        assert_eq!(
+            match_expression("rust AND \"async\" c++").as_deref(),
+            Some("\"rust\"* \"AND\"* \"async\"* \"c\"*")
+        );
+        assert_eq!(match_expression("  * - "), None);
+    }
+
+    #[test]
+    fn matched_tokens_come_from_marks() {
+        assert_eq!(
+            matched_tokens("The <mark>Rust</mark> <mark>book</mark>…"),
+            vec!["Rust", "book"]
+        );
+        assert!(matched_tokens("no matches").is_empty());
+    }
+
+    #[tokio::test]
+    async fn tags_are_matched_whole_whatever_the_spacing() {
+        let db = crate::test_support::test_database("fts-tags").await;
+        let mut ids = Vec::new();
+        for tags in ["rust, web dev", " reading ,rust", "rusty", "web"] {
+            let item = ItemActiveModel {
+                title: Set(tags.to_string()),
+                item_type: Set("note".to_string()),
+                tags: Set(Some(tags.to_string())),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .expect("insert");
+            ids.push(item.id);
+        }
+        let backend = FtsBackend::new(db);
+
+        let results = backend
+            .search(&SearchQuery {
+                tags: vec!["web dev".to_string(), "reading".to_string()],
+                sort: SearchSort::CreatedAsc,
+                ..Default::default()
+            })
+            .await
+            .expect("search");
+        assert_eq!(results.found, 2);
+        let found = results.hits.iter().map(|hit| hit.id).collect::<Vec<_>>();
+        assert_eq!(found, vec![ids[0], ids[1]]);
+
+        let tags = &results.facets[1];
+        assert_eq!(tags.field, "tags");
+        assert_eq!(
+            tags.values,
+            vec![
+                FacetValue {
+                    value: "rust".to_string(),
+                    count: 2
+                },
+                FacetValue {
+                    value: "reading".to_string(),
+                    count: 1
+                },
+                FacetValue {
+                    value: "web dev".to_string(),
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn passes_conformance_suite() {
+        let db = crate::test_support::test_database("fts").await;
+        let backend = FtsBackend::new(db.clone());
+        conformance::run(&backend, &db).await;
+    }
+}
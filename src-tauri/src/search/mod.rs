@@ -0,0 +1,263 @@
+use async_trait::async_trait;
+use sea_orm::{DatabaseConnection, DbErr};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::entities::ItemModel;
+use crate::indexer::IndexerState;
+use crate::typesense::TypesenseError;
+
+mod fts;
+mod typesense;
+
+#[cfg(test)]
+mod conformance;
+
+pub use fts::FtsBackend;
+pub use typesense::TypesenseBackend;
+
+const DEFAULT_PER_PAGE: u32 = 20;
+const MAX_PER_PAGE: u32 = 250;
+/// Values reported per facet field, matching Typesense's default `max_facet_values`.
+const MAX_FACET_VALUES: usize = 10;
+
+#[derive(Error, Debug)]
+pub enum SearchError {
+    #[error(transparent)]
+    Typesense(#[from] TypesenseError),
+    #[error("Search database error: {0}")]
+    Database(#[from] DbErr),
+    #[error("Failed to rebuild search index: {0}")]
+    Reindex(String),
+}
+
+/// Something that can index items and answer search queries. Typesense is preferred; the
+/// SQLite FTS5 backend keeps search working when the sidecar is missing or down.
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Short identifier reported to the frontend.
+    fn name(&self) -> &'static str;
+
+    async fn upsert(&self, item: &ItemModel) -> Result<(), SearchError>;
+
+    async fn delete(&self, id: i32) -> Result<(), SearchError>;
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults, SearchError>;
+
+    async fn facets(&self, query: &SearchQuery) -> Result<Vec<FacetCount>, SearchError> {
+        Ok(self.search(query).await?.facets)
+    }
+
+    /// Rebuild the whole index from the `items` table.
+    async fn reindex(&self, db: &DatabaseConnection) -> Result<ReindexReport, SearchError>;
+}
+
+/// Pick Typesense when the indexer last found it healthy and on the current schema, otherwise
+/// fall back to FTS5. Nothing is asked of the server here, so typing a query stays cheap.
+pub fn select_backend(db: &DatabaseConnection, indexer: &IndexerState) -> Box<dyn SearchBackend> {
+    if indexer.typesense_ready() {
+        Box::new(TypesenseBackend::new(indexer.clone()))
+    } else {
+        Box::new(FtsBackend::new(db.clone()))
+    }
+}
+
+/// Rebuild FTS5, and Typesense too whenever it is configured. Unlike `select_backend` this
+/// doesn't wait for Typesense to be on the current schema, since repairing it is usually why
+/// someone reindexes. Only an FTS5 failure is an error; a Typesense one is reported alongside
+/// the FTS5 rebuild that did happen.
+pub async fn reindex_all(
+    db: &DatabaseConnection,
+    indexer: &IndexerState,
+) -> Result<FullReindexReport, SearchError> {
+    let mut report = FullReindexReport {
+        sqlite_fts: FtsBackend::new(db.clone()).reindex(db).await?,
+        typesense: None,
+        typesense_error: None,
+    };
+    if crate::typesense::is_configured() {
+        match TypesenseBackend::new(indexer.clone()).reindex(db).await {
+            Ok(typesense) => report.typesense = Some(typesense),
+            Err(e) => report.typesense_error = Some(e.to_string()),
+        }
+    }
+    Ok(report)
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    #[default]
+    Relevance,
+    UpdatedDesc,
+    UpdatedAsc,
+    CreatedDesc,
+    CreatedAsc,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SearchQuery {
+    #[serde(default)]
+    pub query: String,
+    pub item_type: Option<String>,
+    /// Bookmark subtype from structured data, e.g. `recipe`.
+    pub subtype: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub sort: SearchSort,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+impl SearchQuery {
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> u32 {
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE)
+    }
+
+    /// The item type filter, if one was given.
+    pub fn item_type_filter(&self) -> Option<&str> {
+        self.item_type.as_deref().filter(|value| !value.is_empty())
+    }
+
+    /// The bookmark subtype filter, if one was given.
+    pub fn subtype_filter(&self) -> Option<&str> {
+        self.subtype.as_deref().filter(|value| !value.is_empty())
+    }
+
+    /// Tags to filter by; an item matches if it has any of them.
+    pub fn tag_filters(&self) -> Vec<&str> {
+        self.tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchHighlight {
+    pub field: String,
+    pub snippet: String,
+    pub matched_tokens: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FacetValue {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FacetCount {
+    pub field: String,
+    pub values: Vec<FacetValue>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentHit {
+    pub id: i32,
+    pub highlights: Vec<SearchHighlight>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+    pub hits: Vec<DocumentHit>,
+    pub found: u64,
+    pub page: u32,
+    pub facets: Vec<FacetCount>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ImportFailure {
+    pub id: Option<i32>,
+    pub error: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReindexReport {
+    pub indexed: usize,
+    pub failed: Vec<ImportFailure>,
+}
+
+/// What `reindex_all` rebuilt. `typesense` is `None` when it wasn't configured or failed, and
+/// `typesense_error` says why in the latter case.
+#[derive(Serialize, Debug)]
+pub struct FullReindexReport {
+    pub sqlite_fts: ReindexReport,
+    pub typesense: Option<ReindexReport>,
+    pub typesense_error: Option<String>,
+}
+
+/// Split the comma-separated `tags` column into trimmed, non-empty tags.
+pub fn tags_to_array(tags: &Option<String>) -> Vec<String> {
+    tags.as_ref()
+        .map(|value| {
+            value
+                .split(',')
+                .map(|tag| tag.trim())
+                .filter(|tag| !tag.is_empty())
+                .map(|tag| tag.to_string())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default()
+}
+
+/// The bookmark subtype recorded in an item's `metadata` JSON, if any.
+pub fn subtype_of(metadata: Option<&str>) -> Option<String> {
+    let metadata = serde_json::from_str::<serde_json::Value>(metadata?).ok()?;
+    metadata.get("subtype")?.as_str().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_database;
+    use sea_orm::ActiveModelTrait;
+
+    #[test]
+    fn tags_are_normalized() {
+        let tags = Some("  personal, work , , ideas  ".to_string());
+        assert_eq!(tags_to_array(&tags), vec!["personal", "work", "ideas"]);
+        assert_eq!(tags_to_array(&None), Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn reindex_rebuilds_fts_without_typesense() {
+        let db = test_database("search-reindex").await;
+        crate::entities::ItemActiveModel {
+            title: sea_orm::Set("Sourdough notes".to_string()),
+            item_type: sea_orm::Set("note".to_string()),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .expect("insert");
+
+        let report = reindex_all(&db, &IndexerState::new())
+            .await
+            .expect("reindex");
+        assert_eq!(report.sqlite_fts.indexed, 1);
+        assert!(report.sqlite_fts.failed.is_empty());
+        assert!(report.typesense.is_none());
+        assert!(report.typesense_error.is_none());
+    }
+
+    #[test]
+    fn paging_is_clamped() {
+        let query = SearchQuery {
+            page: Some(0),
+            per_page: Some(1000),
+            ..Default::default()
+        };
+        assert_eq!(query.page(), 1);
+        assert_eq!(query.per_page(), MAX_PER_PAGE);
+        assert_eq!(SearchQuery::default().per_page(), DEFAULT_PER_PAGE);
+    }
+}
@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use sea_orm::DatabaseConnection;
+
+use super::{ReindexReport, SearchBackend, SearchError, SearchQuery, SearchResults};
+use crate::entities::ItemModel;
+use crate::indexer::{self, IndexerState};
+use crate::typesense;
+
+/// The Typesense sidecar, reached through the versioned collection alias.
+pub struct TypesenseBackend {
+    indexer: IndexerState,
+}
+
+impl TypesenseBackend {
+    pub fn new(indexer: IndexerState) -> Self {
+        Self { indexer }
+    }
+}
+
+#[async_trait]
+impl SearchBackend for TypesenseBackend {
+    fn name(&self) -> &'static str {
+        "typesense"
+    }
+
+    async fn upsert(&self, item: &ItemModel) -> Result<(), SearchError> {
+        self.indexer.ensure_typesense_ready()?;
+        Ok(typesense::upsert_item_document(item).await?)
+    }
+
+    async fn delete(&self, id: i32) -> Result<(), SearchError> {
+        Ok(typesense::delete_item_document(id).await?)
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults, SearchError> {
+        self.indexer.ensure_typesense_ready()?;
+        Ok(typesense::search_documents(query).await?)
+    }
+
+    async fn reindex(&self, db: &DatabaseConnection) -> Result<ReindexReport, SearchError> {
+        indexer::reindex_all(db, &self.indexer)
+            .await
+            .map_err(SearchError::Reindex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::conformance;
+
+    #[tokio::test]
+    #[ignore = "needs a running typesense-server; set TYPESENSE_TEST_URL and TYPESENSE_TEST_API_KEY"]
+    async fn passes_conformance_suite() {
+        let url = std::env::var("TYPESENSE_TEST_URL").expect("TYPESENSE_TEST_URL");
+        let api_key = std::env::var("TYPESENSE_TEST_API_KEY").expect("TYPESENSE_TEST_API_KEY");
+        typesense::use_external_server(&url, &api_key);
+
+        let db = crate::test_support::test_database("typesense").await;
+        let indexer = IndexerState::new();
+        indexer.set_typesense_ready(true);
+        let backend = TypesenseBackend::new(indexer);
+        conformance::run(&backend, &db).await;
+    }
+}
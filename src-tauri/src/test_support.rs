@@ -0,0 +1,168 @@
+//! Fixtures shared by tests that need a real database or a server to talk to.
+
+use sea_orm::{Database, DatabaseConnection};
+use sea_orm_migration::MigratorTrait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::migration::Migrator;
+
+/// A fresh, migrated database file in the temp directory.
+pub async fn test_database(name: &str) -> DatabaseConnection {
+    let dir = temp_dir(name);
+    let url = format!("sqlite://{}?mode=rwc", dir.join("test.sqlite").display());
+
+    let db = Database::connect(&url).await.expect("connect");
+    Migrator::up(&db, None).await.expect("migrate");
+    db
+}
+
+/// An empty directory of its own under the temp directory.
+pub fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "junkdrawer-test-{}-{}-{}",
+        name,
+        std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    std::fs::create_dir_all(&dir).expect("temp dir");
+    dir
+}
+
+/// A sample export under `tests/fixtures/imports`.
+pub fn import_fixture(name: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/imports")
+        .join(name);
+    std::fs::read_to_string(path).expect("fixture")
+}
+
+#[derive(Clone)]
+pub struct StubResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl StubResponse {
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn redirect(location: &str) -> Self {
+        Self::new(301, "").header("Location", location)
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// A tiny HTTP/1.1 server on a random local port answering each path with a canned response
+/// and 404 for anything else. Requests are recorded.
+pub struct HttpStub {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+struct RecordedRequest {
+    path: String,
+    /// Header names lowercased.
+    headers: HashMap<String, String>,
+}
+
+impl HttpStub {
+    pub async fn start(routes: Vec<(&str, StubResponse)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let base_url = format!("http://{}", listener.local_addr().expect("address"));
+        let routes = Arc::new(
+            routes
+                .into_iter()
+                .map(|(path, response)| (path.to_string(), response))
+                .collect::<HashMap<_, _>>(),
+        );
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let log = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let routes = routes.clone();
+                let log = log.clone();
+                tokio::spawn(async move {
+                    let Some(request) = read_request(&mut stream).await else {
+                        return;
+                    };
+                    let response = routes
+                        .get(&request.path)
+                        .cloned()
+                        .unwrap_or_else(|| StubResponse::new(404, "not found"));
+                    log.lock().expect("log").push(request);
+
+                    let mut head = format!(
+                        "HTTP/1.1 {} Stub\r\nContent-Length: {}\r\nConnection: close\r\n",
+                        response.status,
+                        response.body.len()
+                    );
+                    for (name, value) in &response.headers {
+                        head.push_str(&format!("{}: {}\r\n", name, value));
+                    }
+                    head.push_str("\r\n");
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(&response.body).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Paths requested so far, in order.
+    pub fn paths(&self) -> Vec<String> {
+        let requests = self.requests.lock().expect("log");
+        requests
+            .iter()
+            .map(|request| request.path.clone())
+            .collect()
+    }
+
+    /// A header of the last request for `path`.
+    pub fn header(&self, path: &str, name: &str) -> Option<String> {
+        let requests = self.requests.lock().expect("log");
+        let request = requests.iter().rev().find(|request| request.path == path)?;
+        request.headers.get(&name.to_lowercase()).cloned()
+    }
+}
+
+/// Read a request's head; stub requests never have a body.
+async fn read_request(stream: &mut tokio::net::TcpStream) -> Option<RecordedRequest> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&buffer);
+    let mut lines = request.lines();
+    let path = lines.next()?.split_whitespace().nth(1)?.to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    Some(RecordedRequest { path, headers })
+}
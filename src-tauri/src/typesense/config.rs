@@ -0,0 +1,173 @@
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::TypesenseError;
+
+const CONFIG_FILE: &str = "typesense.json";
+const KEY_BYTES: usize = 32;
+
+static CONFIG: OnceLock<TypesenseConfig> = OnceLock::new();
+
+/// Keys generated on first run and kept in the app data directory.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct StoredKeys {
+    admin_api_key: String,
+    search_api_key: String,
+}
+
+/// Connection details for the sidecar. The keys persist across launches; the ports are picked
+/// fresh on every start so another process holding 8108 can't block us.
+#[derive(Debug, Clone)]
+pub struct TypesenseConfig {
+    pub admin_api_key: String,
+    /// Only allowed to search the notes collections; safe to hand to the webview.
+    pub search_api_key: String,
+    pub port: u16,
+    pub peering_port: u16,
+    pub base_url: String,
+}
+
+impl TypesenseConfig {
+    pub fn health_endpoint(&self) -> String {
+        format!("{}/health", self.base_url)
+    }
+}
+
+/// The configuration for this run, or an error if the server was never started.
+pub fn current() -> Result<&'static TypesenseConfig, TypesenseError> {
+    CONFIG.get().ok_or(TypesenseError::NotConfigured)
+}
+
+/// Load (or create) the stored keys and pick ports. Later calls return the first configuration
+/// so restarting the sidecar keeps the same address.
+pub fn init(app_data_dir: &Path) -> Result<&'static TypesenseConfig, TypesenseError> {
+    if let Some(config) = CONFIG.get() {
+        return Ok(config);
+    }
+
+    let keys = load_or_create_keys(app_data_dir)?;
+    let port = free_port()?;
+    let peering_port = free_port()?;
+    let config = TypesenseConfig {
+        admin_api_key: keys.admin_api_key,
+        search_api_key: keys.search_api_key,
+        port,
+        peering_port,
+        base_url: format!("http://127.0.0.1:{}", port),
+    };
+
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+/// Point the client at a server someone else started, for tests that need a real Typesense.
+#[cfg(test)]
+pub fn use_external(base_url: &str, admin_api_key: &str) -> &'static TypesenseConfig {
+    CONFIG.get_or_init(|| TypesenseConfig {
+        admin_api_key: admin_api_key.to_string(),
+        search_api_key: admin_api_key.to_string(),
+        port: 0,
+        peering_port: 0,
+        base_url: base_url.trim_end_matches('/').to_string(),
+    })
+}
+
+fn load_or_create_keys(app_data_dir: &Path) -> Result<StoredKeys, TypesenseError> {
+    let path = app_data_dir.join(CONFIG_FILE);
+
+    if let Ok(contents) = std::fs::read_to_string(&path) {
+        match serde_json::from_str::<StoredKeys>(&contents) {
+            Ok(keys) => return Ok(keys),
+            Err(e) => eprintln!("Ignoring unreadable {}: {}", path.display(), e),
+        }
+    }
+
+    let keys = StoredKeys {
+        admin_api_key: generate_key(),
+        search_api_key: generate_key(),
+    };
+
+    std::fs::create_dir_all(app_data_dir).map_err(|e| TypesenseError::DataDir(e.to_string()))?;
+    let contents =
+        serde_json::to_string_pretty(&keys).map_err(|e| TypesenseError::DataDir(e.to_string()))?;
+    // Whatever was there couldn't be read, and a new file is the only way to be sure of its mode
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| TypesenseError::DataDir(e.to_string()))?;
+    }
+    write_private(&path, contents.as_bytes())
+        .map_err(|e| TypesenseError::DataDir(e.to_string()))?;
+
+    Ok(keys)
+}
+
+/// Create `path` readable by the owner only from the start, so the admin key is never briefly
+/// readable by other users.
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+fn generate_key() -> String {
+    let mut bytes = [0u8; KEY_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Ask the OS for an unused loopback port. There is a small window before Typesense binds it,
+/// which is acceptable for a single-user desktop app.
+fn free_port() -> Result<u16, TypesenseError> {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|address| address.port())
+        .map_err(|e| TypesenseError::ProcessSpawn(format!("Failed to find a free port: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_keys_are_random_hex() {
+        let first = generate_key();
+        let second = generate_key();
+        assert_eq!(first.len(), KEY_BYTES * 2);
+        assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn keys_persist_between_runs() {
+        let dir = std::env::temp_dir().join(format!("junkdrawer-typesense-{}", generate_key()));
+
+        let created = load_or_create_keys(&dir).expect("keys created");
+        let loaded = load_or_create_keys(&dir).expect("keys loaded");
+        assert_eq!(created, loaded);
+        assert_ne!(created.admin_api_key, created.search_api_key);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join(CONFIG_FILE))
+                .expect("config file")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // A file that can't be read is replaced rather than left in the way
+        std::fs::write(dir.join(CONFIG_FILE), "not json").expect("corrupt config");
+        let replaced = load_or_create_keys(&dir).expect("keys replaced");
+        assert_ne!(replaced, created);
+        assert_eq!(load_or_create_keys(&dir).expect("keys loaded"), replaced);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,1202 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::Receiver;
+use tauri::{Emitter, Manager};
+use tauri_plugin_shell::{
+    process::{CommandChild, CommandEvent},
+    ShellExt,
+};
+use thiserror::Error;
+
+use crate::entities::ItemModel;
+use crate::search::{
+    subtype_of, tags_to_array, DocumentHit, FacetCount, FacetValue, ImportFailure, SearchHighlight,
+    SearchQuery, SearchResults, SearchSort,
+};
+
+mod config;
+mod process;
+mod supervisor;
+
+pub use supervisor::{diagnostics, TypesenseDiagnostics};
+
+#[cfg(test)]
+pub fn use_external_server(base_url: &str, admin_api_key: &str) {
+    config::use_external(base_url, admin_api_key);
+}
+
+const STARTUP_DELAY_SECS: u64 = 2;
+const HEALTH_CHECK_INTERVAL_SECS: u64 = 5;
+/// How long the server gets to exit after SIGTERM before it is killed outright.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Alias that fronts the versioned collection; documents and searches always go through it.
+const TYPESENSE_COLLECTION: &str = "notes";
+
+#[derive(Error, Debug)]
+pub enum TypesenseError {
+    #[error("Failed to create typesense-server sidecar: {0}")]
+    SidecarCreation(#[from] tauri_plugin_shell::Error),
+    #[error("Failed to spawn typesense-server process: {0}")]
+    ProcessSpawn(String),
+    #[error("Failed to manage process state: {0}")]
+    ProcessState(String),
+    #[error("No typesense process is currently running")]
+    NoProcessRunning,
+    #[error("Failed to kill typesense process: {0}")]
+    ProcessKill(String),
+    #[error("Failed to prepare typesense data directory: {0}")]
+    DataDir(String),
+    #[error("Typesense HTTP error: {0}")]
+    Http(String),
+    #[error("Search index is being migrated to the current schema")]
+    SchemaNotReady,
+    #[error("Typesense server has not been configured yet")]
+    NotConfigured,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HealthResponse {
+    pub ok: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ServerStatus {
+    pub is_healthy: bool,
+    pub message: String,
+}
+
+impl ServerStatus {
+    fn healthy() -> Self {
+        Self {
+            is_healthy: true,
+            message: "Typesense server is healthy".to_string(),
+        }
+    }
+
+    fn unhealthy(message: impl Into<String>) -> Self {
+        Self {
+            is_healthy: false,
+            message: message.into(),
+        }
+    }
+}
+
+pub struct TypesenseState {
+    pub child: Arc<Mutex<Option<CommandChild>>>,
+    pub diagnostics: Arc<Mutex<TypesenseDiagnostics>>,
+    /// Set by `stop_server` so the supervisor doesn't restart a process we killed on purpose.
+    pub stopping: Arc<AtomicBool>,
+    /// Bumped on every spawn so a supervisor can tell its process has been replaced.
+    pub generation: AtomicU64,
+    monitoring: AtomicBool,
+}
+
+impl TypesenseState {
+    pub fn new() -> Self {
+        Self {
+            child: Arc::new(Mutex::new(None)),
+            diagnostics: Arc::new(Mutex::new(TypesenseDiagnostics::default())),
+            stopping: Arc::new(AtomicBool::new(false)),
+            generation: AtomicU64::new(0),
+            monitoring: AtomicBool::new(false),
+        }
+    }
+}
+
+pub async fn check_health() -> ServerStatus {
+    let config = match config::current() {
+        Ok(config) => config,
+        Err(e) => return ServerStatus::unhealthy(e.to_string()),
+    };
+
+    match reqwest::get(config.health_endpoint()).await {
+        Ok(response) => {
+            if response.status().is_success() {
+                match response.json::<HealthResponse>().await {
+                    Ok(health) => {
+                        if health.ok {
+                            ServerStatus::healthy()
+                        } else {
+                            ServerStatus::unhealthy("Typesense server health check failed")
+                        }
+                    }
+                    Err(_) => ServerStatus::unhealthy(
+                        "Invalid health response format from Typesense server",
+                    ),
+                }
+            } else {
+                ServerStatus::unhealthy(format!(
+                    "Typesense server health check failed with status: {}",
+                    response.status()
+                ))
+            }
+        }
+        Err(e) => ServerStatus::unhealthy(format!("Failed to connect to Typesense server: {}", e)),
+    }
+}
+
+pub fn start_server(app: tauri::AppHandle) -> Result<(), TypesenseError> {
+    {
+        let state: tauri::State<TypesenseState> = app.state();
+        let child_guard = state.child.lock().map_err(|e| {
+            TypesenseError::ProcessState(format!("Failed to lock child process: {}", e))
+        })?;
+
+        if child_guard.is_some() {
+            return Ok(());
+        }
+    }
+
+    // A server orphaned by a crashed or force-quit run still holds the data directory lock
+    process::reap_stale_server(&process::pid_file(&app_data_dir(&app)?), SHUTDOWN_TIMEOUT);
+
+    let state: tauri::State<TypesenseState> = app.state();
+    state.stopping.store(false, Ordering::SeqCst);
+    {
+        let mut diagnostics = state.diagnostics.lock().map_err(|e| {
+            TypesenseError::ProcessState(format!("Failed to lock diagnostics: {}", e))
+        })?;
+        diagnostics.consecutive_failures = 0;
+        diagnostics.gave_up = false;
+    }
+
+    let (rx, generation) = spawn_sidecar(&app)?;
+    supervisor::supervise(app.clone(), rx, generation);
+
+    // One monitor covers every restart; stop/start cycles must not stack more of them
+    if !state.monitoring.swap(true, Ordering::SeqCst) {
+        start_health_monitoring(app);
+    }
+    Ok(())
+}
+
+/// Origins the webview loads the app from: `tauri://localhost` on macOS and Linux,
+/// `http(s)://tauri.localhost` on Windows, and the dev server while developing.
+fn cors_domains(dev_url: Option<&tauri::Url>) -> String {
+    let mut origins = vec![
+        "tauri://localhost".to_string(),
+        "http://tauri.localhost".to_string(),
+        "https://tauri.localhost".to_string(),
+    ];
+    origins.extend(dev_url.map(|url| url.origin().ascii_serialization()));
+    origins.join(",")
+}
+
+/// Spawn the sidecar process and record it in app state, returning its event stream and the
+/// generation it was assigned.
+fn spawn_sidecar(app: &tauri::AppHandle) -> Result<(Receiver<CommandEvent>, u64), TypesenseError> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| TypesenseError::DataDir(e.to_string()))?;
+    let app_dir = app_data_dir.join("typesense");
+
+    std::fs::create_dir_all(&app_dir).map_err(|e| TypesenseError::DataDir(e.to_string()))?;
+
+    let config = config::init(&app_data_dir)?;
+
+    // Passed to the child only, so the admin key never lands in our own environment. The server
+    // binds to loopback so other hosts can't reach it, and CORS only lets our own webview in,
+    // which queries it directly with the search-only key.
+    let dev_url = cfg!(debug_assertions)
+        .then(|| app.config().build.dev_url.clone())
+        .flatten();
+    let typesense_command = app
+        .shell()
+        .sidecar("typesense-server")?
+        .env("TYPESENSE_DATA_DIR", app_dir.display().to_string())
+        .env("TYPESENSE_API_KEY", &config.admin_api_key)
+        .env("TYPESENSE_API_ADDRESS", "127.0.0.1")
+        .env("TYPESENSE_API_PORT", config.port.to_string())
+        .env("TYPESENSE_PEERING_ADDRESS", "127.0.0.1")
+        .env("TYPESENSE_PEERING_PORT", config.peering_port.to_string())
+        .env("TYPESENSE_ENABLE_CORS", "true")
+        .env("TYPESENSE_CORS_DOMAINS", cors_domains(dev_url.as_ref()))
+        .env("TYPESENSE_TELEMETRY", "false");
+
+    let (rx, child) = typesense_command
+        .spawn()
+        .map_err(|e| TypesenseError::ProcessSpawn(e.to_string()))?;
+    let pid = child.pid();
+
+    if let Err(e) = process::write_pid(&process::pid_file(&app_data_dir), pid) {
+        eprintln!("Failed to record typesense-server pid: {}", e);
+    }
+
+    // Store the child process in app state
+    let state: tauri::State<TypesenseState> = app.state();
+    {
+        let mut child_guard = state.child.lock().map_err(|e| {
+            TypesenseError::ProcessState(format!("Failed to lock child process: {}", e))
+        })?;
+        *child_guard = Some(child);
+    }
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    {
+        let mut diagnostics = state.diagnostics.lock().map_err(|e| {
+            TypesenseError::ProcessState(format!("Failed to lock diagnostics: {}", e))
+        })?;
+        diagnostics.running = true;
+        diagnostics.pid = Some(pid);
+    }
+
+    Ok((rx, generation))
+}
+
+/// Stop the server with SIGTERM, falling back to a hard kill if it hasn't exited within
+/// `SHUTDOWN_TIMEOUT`. Blocks until the process is gone.
+pub fn stop_server(app: tauri::AppHandle) -> Result<(), TypesenseError> {
+    let state: tauri::State<TypesenseState> = app.state();
+    state.stopping.store(true, Ordering::SeqCst);
+    let child = {
+        let mut child_guard = state.child.lock().map_err(|e| {
+            TypesenseError::ProcessState(format!("Failed to lock child process: {}", e))
+        })?;
+        child_guard.take().ok_or(TypesenseError::NoProcessRunning)?
+    };
+
+    terminate_child(child)?;
+    process::remove_pid(&process::pid_file(&app_data_dir(&app)?));
+    Ok(())
+}
+
+/// Stop the sidecar on a background thread when the app is asked to exit, then exit with `code`
+/// once it is gone, so the main thread never waits on it. Returns false when there is nothing
+/// to stop and the exit can go ahead now.
+pub fn shutdown_in_background(app: &tauri::AppHandle, code: Option<i32>) -> bool {
+    let state: tauri::State<TypesenseState> = app.state();
+    let running = state
+        .child
+        .lock()
+        .map(|child| child.is_some())
+        .unwrap_or(false);
+    if !running {
+        return false;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        shutdown(&app);
+        // Requests the exit again, which goes ahead now the server is stopped
+        app.exit(code.unwrap_or(0));
+    });
+    true
+}
+
+/// Stop the sidecar as the app exits. Safe to call more than once.
+pub fn shutdown(app: &tauri::AppHandle) {
+    match stop_server(app.clone()) {
+        Ok(()) | Err(TypesenseError::NoProcessRunning) => {}
+        Err(e) => eprintln!("Failed to stop Typesense server on exit: {}", e),
+    }
+}
+
+#[cfg(unix)]
+fn terminate_child(child: CommandChild) -> Result<(), TypesenseError> {
+    if process::terminate(child.pid(), SHUTDOWN_TIMEOUT) {
+        return Ok(());
+    }
+    child
+        .kill()
+        .map_err(|e| TypesenseError::ProcessKill(e.to_string()))
+}
+
+#[cfg(not(unix))]
+fn terminate_child(child: CommandChild) -> Result<(), TypesenseError> {
+    let pid = child.pid();
+    child
+        .kill()
+        .map_err(|e| TypesenseError::ProcessKill(e.to_string()))?;
+    if process::wait_for_exit(pid, SHUTDOWN_TIMEOUT) {
+        Ok(())
+    } else {
+        Err(TypesenseError::ProcessKill(format!(
+            "typesense-server (pid {}) did not exit",
+            pid
+        )))
+    }
+}
+
+fn app_data_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, TypesenseError> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| TypesenseError::DataDir(e.to_string()))
+}
+
+pub fn is_server_running(app: tauri::AppHandle) -> Result<bool, TypesenseError> {
+    let state: tauri::State<TypesenseState> = app.state();
+    let child_guard = state.child.lock().map_err(|e| {
+        TypesenseError::ProcessState(format!("Failed to lock child process: {}", e))
+    })?;
+
+    Ok(child_guard.is_some())
+}
+
+fn start_health_monitoring(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(STARTUP_DELAY_SECS)).await;
+
+        let mut search_key_ready = false;
+
+        loop {
+            let status = check_health().await;
+
+            if status.is_healthy && !search_key_ready {
+                match ensure_search_key().await {
+                    Ok(()) => search_key_ready = true,
+                    Err(e) => eprintln!("Failed to register Typesense search key: {}", e),
+                }
+            }
+
+            if app.emit("typesense-server-status", &status).is_err() {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_secs(HEALTH_CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+/// Register the stored search-only key with the server. Typesense keeps keys in its data
+/// directory, so this is a no-op after the first run unless that directory was wiped.
+async fn ensure_search_key() -> Result<(), TypesenseError> {
+    let config = config::current()?;
+    let response = client()
+        .post(format!("{}/keys", config.base_url))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .json(&serde_json::json!({
+            "description": "Junkdrawer search-only key",
+            "value": config.search_api_key,
+            "actions": ["documents:search"],
+            "collections": [format!("{}.*", TYPESENSE_COLLECTION)]
+        }))
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if response.status().is_success() || response.status() == reqwest::StatusCode::CONFLICT {
+        return Ok(());
+    }
+
+    Err(TypesenseError::Http(format!(
+        "Failed to create search key: {}",
+        response.status()
+    )))
+}
+
+/// What a non-admin client needs to query the index directly.
+#[derive(Serialize, Debug)]
+pub struct SearchConfig {
+    pub base_url: String,
+    pub search_api_key: String,
+    pub collection: String,
+}
+
+pub fn search_config() -> Result<SearchConfig, TypesenseError> {
+    let config = config::current()?;
+    Ok(SearchConfig {
+        base_url: config.base_url.clone(),
+        search_api_key: config.search_api_key.clone(),
+        collection: TYPESENSE_COLLECTION.to_string(),
+    })
+}
+
+/// Whether the sidecar was set up in this run, so there is a Typesense index to maintain.
+pub fn is_configured() -> bool {
+    config::current().is_ok()
+}
+
+fn client() -> reqwest::Client {
+    reqwest::Client::new()
+}
+
+fn timestamp(datetime: NaiveDateTime) -> i64 {
+    datetime.and_utc().timestamp()
+}
+
+/// Bump whenever the field list changes; installs on an older version are rebuilt from SQLite
+/// into a fresh collection and the alias is swapped over once the rebuild finishes. Installs
+/// from before versioning have a plain `notes` collection instead, see `SchemaStatus::Legacy`.
+const SCHEMA_VERSION: u32 = 1;
+
+fn versioned_collection_name() -> String {
+    format!("{}_v{}", TYPESENSE_COLLECTION, SCHEMA_VERSION)
+}
+
+/// A collection name for `SCHEMA_VERSION` that no other collection has, so a rebuild never
+/// touches the one the alias is serving.
+pub fn fresh_collection_name() -> String {
+    format!(
+        "{}_{}",
+        versioned_collection_name(),
+        chrono::Utc::now().timestamp_millis()
+    )
+}
+
+/// Whether `name` is a collection `fresh_collection_name` built for `SCHEMA_VERSION`.
+fn is_current_collection(name: &str) -> bool {
+    name.strip_prefix(&versioned_collection_name())
+        .and_then(|rest| rest.strip_prefix('_'))
+        .is_some_and(|suffix| suffix.parse::<i64>().is_ok())
+}
+
+fn collection_schema(name: &str) -> serde_json::Value {
+    serde_json::json!({
+        "name": name,
+        "default_sorting_field": "updated_at",
+        "fields": [
+            {"name": "id", "type": "string"},
+            {"name": "title", "type": "string"},
+            {"name": "content", "type": "string"},
+            {"name": "item_type", "type": "string", "facet": true},
+            {"name": "tags", "type": "string[]", "facet": true},
+            {"name": "source_url", "type": "string", "optional": true},
+            {"name": "mime_type", "type": "string", "facet": true, "optional": true},
+            {"name": "file_size", "type": "int64", "optional": true},
+            {"name": "subtype", "type": "string", "facet": true, "optional": true},
+            {"name": "created_at", "type": "int64"},
+            {"name": "updated_at", "type": "int64"}
+        ]
+    })
+}
+
+/// Where the `notes` alias stands relative to the current schema version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaStatus {
+    /// The alias points at the collection for `SCHEMA_VERSION`.
+    Current,
+    /// The alias is missing or points at an older collection.
+    Outdated { previous: Option<String> },
+    /// A pre-versioning install with a real collection named `notes` and no alias.
+    Legacy,
+}
+
+#[derive(Deserialize)]
+struct AliasResponse {
+    collection_name: String,
+}
+
+/// The collection the alias points at, if there is an alias.
+pub async fn alias_target() -> Result<Option<String>, TypesenseError> {
+    let config = config::current()?;
+    let response = client()
+        .get(format!(
+            "{}/aliases/{}",
+            config.base_url, TYPESENSE_COLLECTION
+        ))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    if !response.status().is_success() {
+        return Err(TypesenseError::Http(format!(
+            "Failed to look up collection alias: {}",
+            response.status()
+        )));
+    }
+
+    let alias = response
+        .json::<AliasResponse>()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+    Ok(Some(alias.collection_name))
+}
+
+async fn collection_exists(name: &str) -> Result<bool, TypesenseError> {
+    let config = config::current()?;
+    let response = client()
+        .get(format!("{}/collections/{}", config.base_url, name))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if response.status().is_success() {
+        return Ok(true);
+    }
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(false);
+    }
+
+    Err(TypesenseError::Http(format!(
+        "Failed to check collection status: {}",
+        response.status()
+    )))
+}
+
+pub async fn schema_status() -> Result<SchemaStatus, TypesenseError> {
+    match alias_target().await? {
+        Some(target) if is_current_collection(&target) => Ok(SchemaStatus::Current),
+        Some(target) => Ok(SchemaStatus::Outdated {
+            previous: Some(target),
+        }),
+        None if collection_exists(TYPESENSE_COLLECTION).await? => Ok(SchemaStatus::Legacy),
+        None => Ok(SchemaStatus::Outdated { previous: None }),
+    }
+}
+
+pub async fn create_collection(name: &str) -> Result<(), TypesenseError> {
+    let config = config::current()?;
+    let response = client()
+        .post(format!("{}/collections", config.base_url))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .json(&collection_schema(name))
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if response.status().is_success() {
+        return Ok(());
+    }
+
+    Err(TypesenseError::Http(format!(
+        "Failed to create collection {}: {}",
+        name,
+        response.status()
+    )))
+}
+
+/// Remove the unversioned `notes` collection left by older installs so its name can become the
+/// alias. Only valid while no alias exists, otherwise the delete would resolve through it.
+pub async fn drop_legacy_collection() -> Result<(), TypesenseError> {
+    drop_collection(TYPESENSE_COLLECTION).await
+}
+
+/// Point the alias at `collection`. Typesense swaps aliases atomically, so searches never see a
+/// half-built collection.
+pub async fn swap_alias(collection: &str) -> Result<(), TypesenseError> {
+    let config = config::current()?;
+    let response = client()
+        .put(format!(
+            "{}/aliases/{}",
+            config.base_url, TYPESENSE_COLLECTION
+        ))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .json(&serde_json::json!({ "collection_name": collection }))
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if response.status().is_success() {
+        return Ok(());
+    }
+
+    Err(TypesenseError::Http(format!(
+        "Failed to point alias at {}: {}",
+        collection,
+        response.status()
+    )))
+}
+
+fn item_document(item: &ItemModel) -> serde_json::Value {
+    let mut document = serde_json::json!({
+        "id": item.id.to_string(),
+        "title": item.title,
+        "content": item.content.clone().unwrap_or_default(),
+        "item_type": item.item_type,
+        "tags": tags_to_array(&item.tags),
+        "created_at": timestamp(item.created_at),
+        "updated_at": timestamp(item.updated_at)
+    });
+
+    // Optional fields are left out rather than sent as null
+    if let Some(source_url) = &item.source_url {
+        document["source_url"] = serde_json::json!(source_url);
+    }
+    if let Some(mime_type) = &item.mime_type {
+        document["mime_type"] = serde_json::json!(mime_type);
+    }
+    if let Some(file_size) = item.file_size {
+        document["file_size"] = serde_json::json!(file_size);
+    }
+    if let Some(subtype) = subtype_of(item.metadata.as_deref()) {
+        document["subtype"] = serde_json::json!(subtype);
+    }
+
+    document
+}
+
+pub async fn upsert_item_document(item: &ItemModel) -> Result<(), TypesenseError> {
+    let config = config::current()?;
+
+    let payload = item_document(item);
+
+    let response = client()
+        .post(format!(
+            "{}/collections/{}/documents?action=upsert",
+            config.base_url, TYPESENSE_COLLECTION
+        ))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(TypesenseError::Http(format!(
+            "Failed to upsert document: {}",
+            response.status()
+        )));
+    }
+
+    Ok(())
+}
+
+pub async fn delete_item_document(id: i32) -> Result<(), TypesenseError> {
+    let config = config::current()?;
+    let response = client()
+        .delete(format!(
+            "{}/collections/{}/documents/{}",
+            config.base_url, TYPESENSE_COLLECTION, id
+        ))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if response.status().is_success() || response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(());
+    }
+
+    Err(TypesenseError::Http(format!(
+        "Failed to delete document: {}",
+        response.status()
+    )))
+}
+
+#[derive(Deserialize)]
+struct ImportLine {
+    success: bool,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    document: Option<String>,
+}
+
+/// Typesense answers a bulk import with one JSON status object per input line.
+fn parse_import_response(body: &str) -> Result<Vec<ImportFailure>, TypesenseError> {
+    let mut failures = Vec::new();
+    for line in body.lines().filter(|line| !line.trim().is_empty()) {
+        let status = serde_json::from_str::<ImportLine>(line)
+            .map_err(|e| TypesenseError::Http(format!("Invalid import response: {}", e)))?;
+        if status.success {
+            continue;
+        }
+        let id = status
+            .document
+            .and_then(|document| serde_json::from_str::<serde_json::Value>(&document).ok())
+            .and_then(|document| document.get("id")?.as_str()?.parse::<i32>().ok());
+        failures.push(ImportFailure {
+            id,
+            error: status
+                .error
+                .unwrap_or_else(|| "Unknown import error".to_string()),
+        });
+    }
+    Ok(failures)
+}
+
+pub async fn drop_collection(name: &str) -> Result<(), TypesenseError> {
+    let config = config::current()?;
+    let response = client()
+        .delete(format!("{}/collections/{}", config.base_url, name))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if response.status().is_success() || response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(());
+    }
+
+    Err(TypesenseError::Http(format!(
+        "Failed to drop collection: {}",
+        response.status()
+    )))
+}
+
+/// Upsert a batch of items into `collection` through the JSONL bulk import endpoint, returning
+/// per-document failures. Takes a concrete collection name so rebuilds can fill a collection
+/// before the alias points at it.
+pub async fn import_item_documents(
+    collection: &str,
+    items: &[ItemModel],
+) -> Result<Vec<ImportFailure>, TypesenseError> {
+    let config = config::current()?;
+    if items.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let body = items
+        .iter()
+        .map(|item| item_document(item).to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let response = client()
+        .post(format!(
+            "{}/collections/{}/documents/import?action=upsert",
+            config.base_url, collection
+        ))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .header(reqwest::header::CONTENT_TYPE, "text/plain")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(TypesenseError::Http(format!(
+            "Failed to import documents: {}",
+            response.status()
+        )));
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+    parse_import_response(&body)
+}
+
+#[derive(Deserialize)]
+struct ExportedVersion {
+    id: String,
+    updated_at: i64,
+}
+
+fn parse_export_versions(body: &str) -> Result<HashMap<i32, i64>, TypesenseError> {
+    let mut versions = HashMap::new();
+    for line in body.lines().filter(|line| !line.trim().is_empty()) {
+        let document = serde_json::from_str::<ExportedVersion>(line)
+            .map_err(|e| TypesenseError::Http(format!("Invalid export response: {}", e)))?;
+        if let Ok(id) = document.id.parse::<i32>() {
+            versions.insert(id, document.updated_at);
+        }
+    }
+    Ok(versions)
+}
+
+/// Fetch the `updated_at` timestamp of every indexed document, keyed by item id.
+pub async fn export_document_versions() -> Result<HashMap<i32, i64>, TypesenseError> {
+    let config = config::current()?;
+
+    let response = client()
+        .get(format!(
+            "{}/collections/{}/documents/export",
+            config.base_url, TYPESENSE_COLLECTION
+        ))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .query(&[("include_fields", "id,updated_at")])
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(TypesenseError::Http(format!(
+            "Failed to export documents: {}",
+            response.status()
+        )));
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+    parse_export_versions(&body)
+}
+
+fn sort_by(sort: SearchSort) -> &'static str {
+    match sort {
+        SearchSort::Relevance => "_text_match:desc,updated_at:desc",
+        SearchSort::UpdatedDesc => "updated_at:desc",
+        SearchSort::UpdatedAsc => "updated_at:asc",
+        SearchSort::CreatedDesc => "created_at:desc",
+        SearchSort::CreatedAsc => "created_at:asc",
+    }
+}
+
+#[derive(Deserialize)]
+struct RawSearchResponse {
+    found: u64,
+    page: u32,
+    #[serde(default)]
+    hits: Vec<RawHit>,
+    #[serde(default)]
+    facet_counts: Vec<RawFacetCount>,
+}
+
+#[derive(Deserialize)]
+struct RawHit {
+    document: RawHitDocument,
+    #[serde(default)]
+    highlights: Vec<RawHighlight>,
+}
+
+#[derive(Deserialize)]
+struct RawHitDocument {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct RawHighlight {
+    field: String,
+    #[serde(default)]
+    snippet: Option<String>,
+    #[serde(default)]
+    snippets: Vec<String>,
+    #[serde(default)]
+    matched_tokens: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct RawFacetCount {
+    field_name: String,
+    #[serde(default)]
+    counts: Vec<RawFacetValue>,
+}
+
+#[derive(Deserialize)]
+struct RawFacetValue {
+    value: String,
+    count: u64,
+}
+
+/// Wrap a filter value in backticks so commas and operators inside it are taken literally.
+fn filter_value(value: &str) -> String {
+    format!("`{}`", value.replace('`', ""))
+}
+
+fn search_params(query: &SearchQuery) -> Vec<(&'static str, String)> {
+    let q = query.query.trim();
+
+    let mut filters = Vec::new();
+    if let Some(item_type) = query.item_type_filter() {
+        filters.push(format!("item_type:={}", filter_value(item_type)));
+    }
+    if let Some(subtype) = query.subtype_filter() {
+        filters.push(format!("subtype:={}", filter_value(subtype)));
+    }
+    let tags = query
+        .tag_filters()
+        .into_iter()
+        .map(filter_value)
+        .collect::<Vec<_>>();
+    if !tags.is_empty() {
+        filters.push(format!("tags:=[{}]", tags.join(",")));
+    }
+
+    let mut params = vec![
+        (
+            "q",
+            if q.is_empty() {
+                "*".to_string()
+            } else {
+                q.to_string()
+            },
+        ),
+        ("query_by", "title,content,tags".to_string()),
+        ("sort_by", sort_by(query.sort).to_string()),
+        ("facet_by", "item_type,tags,subtype".to_string()),
+        ("highlight_fields", "title,content".to_string()),
+        ("page", query.page().to_string()),
+        ("per_page", query.per_page().to_string()),
+    ];
+    if !filters.is_empty() {
+        params.push(("filter_by", filters.join(" && ")));
+    }
+    params
+}
+
+fn parse_search_response(raw: RawSearchResponse) -> SearchResults {
+    let hits = raw
+        .hits
+        .into_iter()
+        .filter_map(|hit| {
+            let id = hit.document.id.parse::<i32>().ok()?;
+            let highlights = hit
+                .highlights
+                .into_iter()
+                .map(|highlight| {
+                    let snippet = highlight
+                        .snippet
+                        .or_else(|| highlight.snippets.into_iter().next())
+                        .unwrap_or_default();
+                    // Array fields report one token list per matched element.
+                    let matched_tokens = match highlight.matched_tokens {
+                        serde_json::Value::Array(values) => values
+                            .into_iter()
+                            .flat_map(|value| match value {
+                                serde_json::Value::String(token) => vec![token],
+                                serde_json::Value::Array(tokens) => tokens
+                                    .into_iter()
+                                    .filter_map(|token| token.as_str().map(str::to_string))
+                                    .collect(),
+                                _ => Vec::new(),
+                            })
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    SearchHighlight {
+                        field: highlight.field,
+                        snippet,
+                        matched_tokens,
+                    }
+                })
+                .collect();
+            Some(DocumentHit { id, highlights })
+        })
+        .collect();
+
+    let facets = raw
+        .facet_counts
+        .into_iter()
+        .map(|facet| FacetCount {
+            field: facet.field_name,
+            values: facet
+                .counts
+                .into_iter()
+                .map(|count| FacetValue {
+                    value: count.value,
+                    count: count.count,
+                })
+                .collect(),
+        })
+        .collect();
+
+    SearchResults {
+        hits,
+        found: raw.found,
+        page: raw.page,
+        facets,
+    }
+}
+
+pub async fn search_documents(query: &SearchQuery) -> Result<SearchResults, TypesenseError> {
+    let config = config::current()?;
+
+    let response = client()
+        .get(format!(
+            "{}/collections/{}/documents/search",
+            config.base_url, TYPESENSE_COLLECTION
+        ))
+        .header("X-TYPESENSE-API-KEY", &config.admin_api_key)
+        .query(&search_params(query))
+        .send()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(TypesenseError::Http(format!(
+            "Failed to search documents: {}",
+            response.status()
+        )));
+    }
+
+    let raw = response
+        .json::<RawSearchResponse>()
+        .await
+        .map_err(|e| TypesenseError::Http(e.to_string()))?;
+
+    Ok(parse_search_response(raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn timestamp_converts_naive_datetime() {
+        let datetime = NaiveDateTime::from_timestamp_opt(1_700_000_000, 0).expect("valid timestamp");
+        assert_eq!(timestamp(datetime), 1_700_000_000);
+    }
+
+    #[test]
+    fn versioned_collection_carries_schema_version() {
+        assert_eq!(
+            versioned_collection_name(),
+            format!("notes_v{}", SCHEMA_VERSION)
+        );
+        assert_eq!(
+            collection_schema("notes_v9")["name"],
+            serde_json::json!("notes_v9")
+        );
+    }
+
+    #[test]
+    fn cors_allows_only_the_webview() {
+        assert_eq!(
+            cors_domains(None),
+            "tauri://localhost,http://tauri.localhost,https://tauri.localhost"
+        );
+        let dev_url = "http://localhost:1420/".parse().expect("url");
+        assert!(cors_domains(Some(&dev_url)).ends_with(",http://localhost:1420"));
+    }
+
+    #[test]
+    fn fresh_collections_count_as_current() {
+        let fresh = fresh_collection_name();
+        assert_ne!(fresh, versioned_collection_name());
+        assert!(is_current_collection(&fresh));
+        assert!(!is_current_collection(&versioned_collection_name()));
+        assert!(!is_current_collection(&format!(
+            "notes_v{}",
+            SCHEMA_VERSION + 1
+        )));
+        assert!(!is_current_collection(&format!(
+            "notes_v{}1_5",
+            SCHEMA_VERSION
+        )));
+        assert!(!is_current_collection("notes"));
+    }
+
+    #[test]
+    fn item_document_omits_missing_optional_fields() {
+        let now = chrono::Utc::now().naive_utc();
+        let mut item = ItemModel {
+            id: 3,
+            title: "Report".to_string(),
+            content: None,
+            item_type: "file".to_string(),
+            tags: None,
+            source_type: None,
+            source_url: None,
+            mime_type: Some("application/pdf".to_string()),
+            file_size: Some(2048),
+            file_modified_at: None,
+            metadata: None,
+            canonical_url: None,
+            content_hash: None,
+            original_filename: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let document = item_document(&item);
+        assert_eq!(document["id"], "3");
+        assert_eq!(document["mime_type"], "application/pdf");
+        assert_eq!(document["file_size"], 2048);
+        assert!(document.get("source_url").is_none());
+
+        item.source_url = Some("/tmp/report.pdf".to_string());
+        assert_eq!(item_document(&item)["source_url"], "/tmp/report.pdf");
+        assert!(document.get("subtype").is_none());
+
+        item.metadata = Some(r#"{"subtype":"scholarly_article"}"#.to_string());
+        assert_eq!(item_document(&item)["subtype"], "scholarly_article");
+    }
+
+    #[test]
+    fn search_params_include_filters_and_paging() {
+        let query = SearchQuery {
+            query: "  rust  ".to_string(),
+            item_type: Some("bookmark".to_string()),
+            tags: vec![
+                "work".to_string(),
+                " ".to_string(),
+                "read later".to_string(),
+            ],
+            subtype: Some("recipe".to_string()),
+            sort: SearchSort::UpdatedDesc,
+            page: Some(2),
+            per_page: Some(1_000),
+        };
+        let params = search_params(&query);
+        let get = |key: &str| {
+            params
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.as_str())
+        };
+
+        assert_eq!(get("q"), Some("rust"));
+        assert_eq!(get("sort_by"), Some("updated_at:desc"));
+        assert_eq!(get("page"), Some("2"));
+        assert_eq!(get("per_page"), Some("250"));
+        assert_eq!(
+            get("filter_by"),
+            Some("item_type:=`bookmark` && subtype:=`recipe` && tags:=[`work`,`read later`]")
+        );
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        let params = search_params(&SearchQuery::default());
+        assert!(params.contains(&("q", "*".to_string())));
+        assert!(!params.iter().any(|(name, _)| *name == "filter_by"));
+    }
+
+    #[test]
+    fn search_response_is_parsed() {
+        let raw: RawSearchResponse = serde_json::from_value(serde_json::json!({
+            "found": 2,
+            "page": 1,
+            "hits": [
+                {
+                    "document": {"id": "7"},
+                    "highlights": [
+                        {"field": "title", "snippet": "<mark>Rust</mark> book", "matched_tokens": ["Rust"]},
+                        {"field": "tags", "snippets": ["<mark>rust</mark>"], "matched_tokens": [["rust"]]}
+                    ]
+                },
+                {"document": {"id": "not-a-number"}}
+            ],
+            "facet_counts": [
+                {"field_name": "item_type", "counts": [{"value": "note", "count": 2}]}
+            ]
+        }))
+        .expect("valid response");
+
+        let results = parse_search_response(raw);
+        assert_eq!(results.found, 2);
+        assert_eq!(results.hits.len(), 1);
+        assert_eq!(results.hits[0].id, 7);
+        assert_eq!(results.hits[0].highlights[1].snippet, "<mark>rust</mark>");
+        assert_eq!(results.hits[0].highlights[1].matched_tokens, vec!["rust"]);
+        assert_eq!(results.facets[0].values[0].count, 2);
+    }
+
+    #[test]
+    fn import_failures_are_collected() {
+        let body = [
+            r#"{"success": true}"#,
+            r#"{"success": false, "error": "Bad field", "document": "{\"id\": \"12\"}"}"#,
+            r#"{"success": false, "error": "Unparseable"}"#,
+        ]
+        .join("\n");
+
+        let failures = parse_import_response(&body).expect("valid response");
+        assert_eq!(
+            failures,
+            vec![
+                ImportFailure {
+                    id: Some(12),
+                    error: "Bad field".to_string()
+                },
+                ImportFailure {
+                    id: None,
+                    error: "Unparseable".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn export_versions_are_keyed_by_id() {
+        let body = "{\"id\":\"1\",\"updated_at\":10}\n{\"id\":\"2\",\"updated_at\":20}\n";
+        let versions = parse_export_versions(body).expect("valid export");
+        assert_eq!(versions.get(&1), Some(&10));
+        assert_eq!(versions.get(&2), Some(&20));
+    }
+}
@@ -0,0 +1,192 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const PID_FILE: &str = "typesense.pid";
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const SIDECAR_NAME: &str = "typesense-server";
+
+pub fn pid_file(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(PID_FILE)
+}
+
+pub fn write_pid(path: &Path, pid: u32) -> std::io::Result<()> {
+    std::fs::write(path, pid.to_string())
+}
+
+pub fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+pub fn remove_pid(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            eprintln!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Kill a server left behind by a previous run that crashed or was force-quit, so it releases
+/// the data directory lock before we spawn a new one. Only processes that still look like the
+/// sidecar are touched, since the pid may have been reused.
+pub fn reap_stale_server(pid_path: &Path, timeout: Duration) {
+    let Some(pid) = read_pid(pid_path) else {
+        return;
+    };
+
+    if is_alive(pid) && is_typesense_process(pid) {
+        println!(
+            "Stopping stale typesense-server (pid {}) from a previous run",
+            pid
+        );
+        if !terminate(pid, timeout) {
+            eprintln!("Stale typesense-server (pid {}) did not exit", pid);
+        }
+    }
+
+    remove_pid(pid_path);
+}
+
+/// Ask `pid` to exit with SIGTERM, escalating to SIGKILL if it is still running after
+/// `timeout`. Returns whether the process is gone.
+#[cfg(unix)]
+pub fn terminate(pid: u32, timeout: Duration) -> bool {
+    send_signal(pid, libc::SIGTERM);
+    if wait_for_exit(pid, timeout) {
+        return true;
+    }
+
+    send_signal(pid, libc::SIGKILL);
+    wait_for_exit(pid, timeout)
+}
+
+#[cfg(not(unix))]
+pub fn terminate(pid: u32, timeout: Duration) -> bool {
+    let _ = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/F"])
+        .status();
+    wait_for_exit(pid, timeout)
+}
+
+pub fn wait_for_exit(pid: u32, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while is_alive(pid) {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    true
+}
+
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: libc::c_int) {
+    // SAFETY: kill(2) has no memory-safety requirements; a stale pid just yields ESRCH.
+    unsafe {
+        libc::kill(pid as libc::pid_t, signal);
+    }
+}
+
+#[cfg(unix)]
+pub fn is_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks whether the process exists and may be signalled.
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    if result == 0 {
+        // Exited children linger as zombies until reaped; treat them as gone.
+        return !is_zombie(pid);
+    }
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+pub fn is_alive(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+fn is_zombie(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|stat| {
+            // The state follows the parenthesised command name, which may itself contain spaces.
+            let after_name = stat.rsplit_once(')')?.1;
+            after_name
+                .split_whitespace()
+                .next()
+                .map(|state| state == "Z")
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_zombie(pid: u32) -> bool {
+    process_command(pid, "stat").is_some_and(|state| state.starts_with('Z'))
+}
+
+#[cfg(target_os = "linux")]
+fn is_typesense_process(pid: u32) -> bool {
+    std::fs::read(format!("/proc/{}/cmdline", pid))
+        .map(|cmdline| String::from_utf8_lossy(&cmdline).contains(SIDECAR_NAME))
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_typesense_process(pid: u32) -> bool {
+    process_command(pid, "comm").is_some_and(|command| command.contains(SIDECAR_NAME))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_command(pid: u32, field: &str) -> Option<String> {
+    let output = std::process::Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", &format!("{}=", field)])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pid_file_round_trips() {
+        let dir = std::env::temp_dir().join(format!("junkdrawer-pid-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = pid_file(&dir);
+
+        write_pid(&path, 4242).expect("write pid");
+        assert_eq!(read_pid(&path), Some(4242));
+        remove_pid(&path);
+        assert_eq!(read_pid(&path), None);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn current_process_is_alive_but_not_typesense() {
+        assert!(is_alive(std::process::id()));
+        assert!(!is_typesense_process(std::process::id()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn terminate_stops_a_running_process() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .expect("spawn sleep");
+        let pid = child.id();
+
+        // Reap the child in the background so it doesn't linger as a zombie
+        let waiter = std::thread::spawn(move || child.wait());
+        assert!(terminate(pid, Duration::from_secs(5)));
+        assert!(!is_alive(pid));
+        waiter.join().expect("waiter").expect("wait");
+    }
+}
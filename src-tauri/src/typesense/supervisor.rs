@@ -0,0 +1,315 @@
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tauri::async_runtime::Receiver;
+use tauri::{Emitter, Manager};
+use tauri_plugin_shell::process::CommandEvent;
+
+use super::{ServerStatus, TypesenseError, TypesenseState};
+
+const MAX_CONSECUTIVE_RESTARTS: u32 = 5;
+const BASE_RESTART_DELAY_SECS: u64 = 1;
+const MAX_RESTART_DELAY_SECS: u64 = 60;
+/// A run that lasts this long counts as healthy and resets the restart budget.
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+const LOG_FILE: &str = "typesense.log";
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+const MAX_LOG_FILES: usize = 3;
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TypesenseDiagnostics {
+    pub running: bool,
+    pub pid: Option<u32>,
+    /// Restarts performed by the supervisor since the app launched.
+    pub restart_count: u32,
+    /// Crashes since the server last stayed up for a while; restarts stop at the cap.
+    pub consecutive_failures: u32,
+    pub gave_up: bool,
+    pub last_exit_code: Option<i32>,
+    pub last_exit_signal: Option<i32>,
+    pub last_exit_at: Option<NaiveDateTime>,
+    pub log_path: Option<String>,
+}
+
+/// Watch a spawned sidecar's event stream, logging its output and restarting it with
+/// exponential backoff whenever it exits without being asked to.
+pub(super) fn supervise(app: tauri::AppHandle, rx: Receiver<CommandEvent>, generation: u64) {
+    tauri::async_runtime::spawn(async move {
+        let mut log = match log_path(&app) {
+            Ok(path) => {
+                set_log_path(&app, &path);
+                Some(RotatingLog::new(path, MAX_LOG_BYTES, MAX_LOG_FILES))
+            }
+            Err(e) => {
+                eprintln!("Typesense output will not be logged: {}", e);
+                None
+            }
+        };
+
+        let mut rx = rx;
+        let mut generation = generation;
+        let mut started_at = Instant::now();
+
+        loop {
+            let payload = loop {
+                match rx.recv().await {
+                    Some(CommandEvent::Stdout(line)) => write_log(&mut log, "stdout", &line),
+                    Some(CommandEvent::Stderr(line)) => write_log(&mut log, "stderr", &line),
+                    Some(CommandEvent::Error(message)) => {
+                        write_log(&mut log, "error", message.as_bytes())
+                    }
+                    Some(CommandEvent::Terminated(payload)) => break Some(payload),
+                    Some(_) => {}
+                    None => break None,
+                }
+            };
+
+            let (code, signal) = payload
+                .map(|payload| (payload.code, payload.signal))
+                .unwrap_or((None, None));
+            write_log(
+                &mut log,
+                "supervisor",
+                format!(
+                    "typesense-server exited (code {:?}, signal {:?})",
+                    code, signal
+                )
+                .as_bytes(),
+            );
+
+            let state = app.state::<TypesenseState>();
+
+            // After a stop/start cycle a newer process and supervisor own the state
+            if state.generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+
+            if let Ok(mut child) = state.child.lock() {
+                *child = None;
+            }
+
+            let attempt = {
+                let mut diagnostics = match state.diagnostics.lock() {
+                    Ok(diagnostics) => diagnostics,
+                    Err(_) => return,
+                };
+                diagnostics.running = false;
+                diagnostics.pid = None;
+                diagnostics.last_exit_code = code;
+                diagnostics.last_exit_signal = signal;
+                diagnostics.last_exit_at = Some(Utc::now().naive_utc());
+
+                if state.stopping.load(Ordering::SeqCst) {
+                    return;
+                }
+
+                if started_at.elapsed() >= STABLE_UPTIME {
+                    diagnostics.consecutive_failures = 0;
+                }
+                diagnostics.consecutive_failures += 1;
+
+                if diagnostics.consecutive_failures > MAX_CONSECUTIVE_RESTARTS {
+                    diagnostics.gave_up = true;
+                    None
+                } else {
+                    Some(diagnostics.consecutive_failures)
+                }
+            };
+
+            let Some(attempt) = attempt else {
+                let message = format!(
+                    "Typesense server crashed {} times in a row; not restarting",
+                    MAX_CONSECUTIVE_RESTARTS + 1
+                );
+                write_log(&mut log, "supervisor", message.as_bytes());
+                let _ = app.emit("typesense-server-status", &ServerStatus::unhealthy(message));
+                return;
+            };
+
+            tokio::time::sleep(restart_delay(attempt)).await;
+
+            // `stop_server` may have been called while we were waiting
+            if state.stopping.load(Ordering::SeqCst) {
+                return;
+            }
+
+            match super::spawn_sidecar(&app) {
+                Ok((next, next_generation)) => {
+                    rx = next;
+                    generation = next_generation;
+                    started_at = Instant::now();
+                    if let Ok(mut diagnostics) = state.diagnostics.lock() {
+                        diagnostics.restart_count += 1;
+                    }
+                    write_log(&mut log, "supervisor", b"typesense-server restarted");
+                }
+                Err(e) => {
+                    let message = format!("Failed to restart Typesense server: {}", e);
+                    write_log(&mut log, "supervisor", message.as_bytes());
+                    if let Ok(mut diagnostics) = state.diagnostics.lock() {
+                        diagnostics.gave_up = true;
+                    }
+                    let _ = app.emit("typesense-server-status", &ServerStatus::unhealthy(message));
+                    return;
+                }
+            }
+        }
+    });
+}
+
+pub fn diagnostics(app: &tauri::AppHandle) -> Result<TypesenseDiagnostics, TypesenseError> {
+    let state = app.state::<TypesenseState>();
+    let diagnostics = state
+        .diagnostics
+        .lock()
+        .map_err(|e| TypesenseError::ProcessState(format!("Failed to lock diagnostics: {}", e)))?;
+    Ok(diagnostics.clone())
+}
+
+fn restart_delay(attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(16);
+    Duration::from_secs(
+        BASE_RESTART_DELAY_SECS
+            .saturating_mul(2_u64.pow(exponent))
+            .min(MAX_RESTART_DELAY_SECS),
+    )
+}
+
+fn log_path(app: &tauri::AppHandle) -> Result<PathBuf, TypesenseError> {
+    let dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| TypesenseError::DataDir(e.to_string()))?;
+    fs::create_dir_all(&dir).map_err(|e| TypesenseError::DataDir(e.to_string()))?;
+    Ok(dir.join(LOG_FILE))
+}
+
+fn set_log_path(app: &tauri::AppHandle, path: &Path) {
+    let state = app.state::<TypesenseState>();
+    if let Ok(mut diagnostics) = state.diagnostics.lock() {
+        diagnostics.log_path = Some(path.display().to_string());
+    };
+}
+
+fn write_log(log: &mut Option<RotatingLog>, stream: &str, line: &[u8]) {
+    if let Some(log) = log {
+        if let Err(e) = log.write_line(stream, line) {
+            eprintln!("Failed to write Typesense log: {}", e);
+        }
+    }
+}
+
+/// Size-capped log file that shifts `typesense.log` to `typesense.log.1`, `.2`, ... when full.
+struct RotatingLog {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+}
+
+impl RotatingLog {
+    fn new(path: PathBuf, max_bytes: u64, max_files: usize) -> Self {
+        Self {
+            path,
+            max_bytes,
+            max_files,
+            file: None,
+        }
+    }
+
+    fn write_line(&mut self, stream: &str, line: &[u8]) -> std::io::Result<()> {
+        let size = fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if size >= self.max_bytes {
+            self.rotate()?;
+        }
+
+        if self.file.is_none() {
+            self.file = Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?,
+            );
+        }
+
+        let text = String::from_utf8_lossy(line);
+        let file = self.file.as_mut().expect("log file was just opened");
+        writeln!(
+            file,
+            "{} [{}] {}",
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+            stream,
+            text.trim_end()
+        )
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file = None;
+
+        let oldest = self.rotated_path(self.max_files.saturating_sub(1).max(1));
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for index in (1..self.max_files.saturating_sub(1).max(1)).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        if self.path.exists() {
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_delay_backs_off_and_caps() {
+        assert_eq!(restart_delay(1), Duration::from_secs(1));
+        assert_eq!(restart_delay(2), Duration::from_secs(2));
+        assert_eq!(restart_delay(4), Duration::from_secs(8));
+        assert_eq!(
+            restart_delay(30),
+            Duration::from_secs(MAX_RESTART_DELAY_SECS)
+        );
+    }
+
+    #[test]
+    fn log_rotates_when_full() {
+        let dir = std::env::temp_dir().join(format!(
+            "junkdrawer-typesense-log-{}",
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join(LOG_FILE);
+        let mut log = RotatingLog::new(path.clone(), 16, 3);
+
+        for line in ["first line", "second line", "third line", "fourth line"] {
+            log.write_line("stdout", line.as_bytes()).expect("write");
+        }
+
+        let current = fs::read_to_string(&path).expect("current log");
+        let previous = fs::read_to_string(log.rotated_path(1)).expect("rotated log");
+        let oldest = fs::read_to_string(log.rotated_path(2)).expect("oldest log");
+        assert!(current.contains("fourth line"));
+        assert!(previous.contains("third line"));
+        assert!(oldest.contains("second line"));
+        assert!(!log.rotated_path(3).exists());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,989 @@
+//! Watched folders. Files in them become items, found by a filesystem watcher while the app
+//! runs and by a scan on startup that catches up with whatever changed while it didn't.
+//! Changed files update their items, and files that disappear are flagged rather than their
+//! items deleted.
+
+mod rules;
+
+pub use rules::Rules;
+
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Emitter;
+use thiserror::Error;
+use tokio::sync::{mpsc, Notify};
+
+use crate::blobs::{self, BlobStore};
+use crate::bookmarks::metadata_object;
+use crate::entities::{
+    item, watched_file, watched_path, Item, ItemActiveModel, ItemModel, WatchedFile,
+    WatchedFileActiveModel, WatchedFileModel, WatchedPath, WatchedPathActiveModel,
+    WatchedPathModel,
+};
+use crate::files::{self, DuplicateAction, FileOperationRequest, FileProcessor};
+use crate::indexer::{self, IndexOperation, IndexerState};
+use crate::linked_files::{self, Fingerprint, LinkedFile};
+
+const STARTUP_DELAY: Duration = Duration::from_secs(30);
+/// Quiet time before changes to a file are handled, so a file still being written, or saved
+/// in several steps, is imported once.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+/// A full rescan now and then catches what the watcher can miss, such as changes on network
+/// drives.
+const RESCAN_AFTER_HOURS: i64 = 24;
+const RESCAN_CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+pub const WATCHED_FILES_SYNCED_EVENT: &str = "watched-files-synced";
+
+#[derive(Error, Debug)]
+pub enum WatchError {
+    #[error("Not a folder: {0}")]
+    NotAFolder(String),
+    #[error("{0} overlaps the watched folder {1}")]
+    Overlaps(String, String),
+    #[error("Watched folder is not available: {0}")]
+    Unavailable(String),
+    #[error("Invalid pattern: {0}")]
+    Pattern(#[from] globset::Error),
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+/// How a watched folder's new files become items.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchOperation {
+    /// Index them where they are.
+    #[default]
+    Link,
+    /// Store a copy, leaving the folder as it is.
+    Copy,
+}
+
+impl WatchOperation {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Link => "link",
+            Self::Copy => "copy",
+        }
+    }
+}
+
+/// Wakes the worker when watched folders are added or removed.
+#[derive(Clone, Default)]
+pub struct WatcherState {
+    reload: Arc<Notify>,
+}
+
+impl WatcherState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reload(&self) {
+        self.reload.notify_one();
+    }
+}
+
+/// What a sync did, sent as `WATCHED_FILES_SYNCED_EVENT`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SyncReport {
+    pub watched_path_id: i32,
+    /// Items created for new files.
+    pub imported: Vec<i32>,
+    /// Items whose file changed.
+    pub updated: Vec<i32>,
+    /// Items whose file is gone.
+    pub missing: Vec<i32>,
+    /// Items whose file is back.
+    pub restored: Vec<i32>,
+    /// Files that couldn't be imported or updated; the next sync tries again.
+    pub failed: usize,
+}
+
+impl SyncReport {
+    fn is_empty(&self) -> bool {
+        self.imported.is_empty()
+            && self.updated.is_empty()
+            && self.missing.is_empty()
+            && self.restored.is_empty()
+            && self.failed == 0
+    }
+}
+
+/// Where a watched item's file is, stored under `watched` in the item's metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchedSource {
+    pub watched_path_id: i32,
+    pub path: String,
+    /// Set while the file is gone from the folder.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing_since: Option<NaiveDateTime>,
+}
+
+/// What a sync found at a path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Seen {
+    File(Fingerprint),
+    /// There, but the folder's patterns or ignore files now leave it out. Its item is kept as
+    /// it is.
+    Excluded,
+    Gone,
+}
+
+/// Which paths a sync looks at.
+enum Scope {
+    Everything,
+    /// Paths the watcher reported; folders among them are looked through.
+    Paths(Vec<PathBuf>),
+}
+
+/// Start watching a folder. Watching the same folder again returns it as it is; folders
+/// inside or around one already watched are refused, so a file only ever belongs to one.
+pub async fn add(
+    db: &DatabaseConnection,
+    path: &str,
+    recursive: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    operation: WatchOperation,
+) -> Result<WatchedPathModel, WatchError> {
+    let root = fs::canonicalize(path.trim())
+        .ok()
+        .filter(|root| root.is_dir())
+        .ok_or_else(|| WatchError::NotAFolder(path.to_string()))?;
+    let (include, exclude) = (clean(include), clean(exclude));
+    Rules::new(&root, recursive, &include, &exclude)?;
+
+    for existing in WatchedPath::find().all(db).await? {
+        let existing_root = Path::new(&existing.path);
+        if existing_root == root {
+            return Ok(existing);
+        }
+        if root.starts_with(existing_root) || existing_root.starts_with(&root) {
+            return Err(WatchError::Overlaps(
+                root.display().to_string(),
+                existing.path,
+            ));
+        }
+    }
+
+    Ok(WatchedPathActiveModel {
+        path: Set(root.display().to_string()),
+        recursive: Set(recursive),
+        include_patterns: Set(joined(&include)),
+        exclude_patterns: Set(joined(&exclude)),
+        operation: Set(operation.as_str().to_string()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?)
+}
+
+pub async fn list(db: &DatabaseConnection) -> Result<Vec<WatchedPathModel>, DbErr> {
+    WatchedPath::find()
+        .order_by_asc(watched_path::Column::Path)
+        .all(db)
+        .await
+}
+
+/// Stop watching a folder. Items made from its files are kept.
+pub async fn remove(db: &DatabaseConnection, id: i32) -> Result<bool, DbErr> {
+    let txn = db.begin().await?;
+    WatchedFile::delete_many()
+        .filter(watched_file::Column::WatchedPathId.eq(id))
+        .exec(&txn)
+        .await?;
+    let deleted = WatchedPath::delete_by_id(id)
+        .exec(&txn)
+        .await?
+        .rows_affected
+        > 0;
+    txn.commit().await?;
+    Ok(deleted)
+}
+
+fn clean(patterns: Vec<String>) -> Vec<String> {
+    patterns
+        .iter()
+        .map(|pattern| pattern.trim().to_string())
+        .filter(|pattern| !pattern.is_empty())
+        .collect()
+}
+
+fn joined(patterns: &[String]) -> Option<String> {
+    (!patterns.is_empty()).then(|| patterns.join("\n"))
+}
+
+fn split(patterns: Option<&str>) -> Vec<String> {
+    clean(
+        patterns
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+fn rules_for(watched: &WatchedPathModel) -> Result<Rules, globset::Error> {
+    Rules::new(
+        Path::new(&watched.path),
+        watched.recursive,
+        &split(watched.include_patterns.as_deref()),
+        &split(watched.exclude_patterns.as_deref()),
+    )
+}
+
+pub fn start_worker(
+    app: tauri::AppHandle,
+    db: DatabaseConnection,
+    indexer: IndexerState,
+    state: WatcherState,
+) {
+    tauri::async_runtime::spawn(async move {
+        let store = match FileProcessor::blob_store(&app) {
+            Ok(store) => store,
+            Err(e) => {
+                eprintln!("Watched folders are disabled: {}", e);
+                return;
+            }
+        };
+        let (sender, mut changes) = mpsc::unbounded_channel::<Vec<PathBuf>>();
+        let debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let _ = sender.send(events.into_iter().map(|event| event.path).collect());
+            }
+            Err(e) => eprintln!("File watcher error: {}", e),
+        });
+        let mut debouncer = match debouncer {
+            Ok(debouncer) => debouncer,
+            Err(e) => {
+                eprintln!("Watched folders are disabled: {}", e);
+                return;
+            }
+        };
+        let publish = |report: SyncReport| {
+            if !report.is_empty() {
+                indexer.wake();
+                let _ = app.emit(WATCHED_FILES_SYNCED_EVENT, &report);
+            }
+        };
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        let mut watching: HashMap<i32, WatchedPathModel> = HashMap::new();
+        // Folders that couldn't be watched, such as on a drive that isn't plugged in, are
+        // tried again each time round
+        let mut unwatched: HashSet<i32> = HashSet::new();
+        loop {
+            match WatchedPath::find().all(&db).await {
+                Ok(folders) => {
+                    watching.retain(|id, old| {
+                        let kept = folders.iter().any(|folder| folder.id == *id);
+                        if !kept {
+                            let _ = debouncer.watcher().unwatch(Path::new(&old.path));
+                            unwatched.remove(id);
+                        }
+                        kept
+                    });
+                    let rescan_before =
+                        Utc::now().naive_utc() - ChronoDuration::hours(RESCAN_AFTER_HOURS);
+                    for folder in folders {
+                        // Watch before scanning, so nothing changing meanwhile is missed
+                        let is_new = !watching.contains_key(&folder.id);
+                        let mut resumed = false;
+                        if is_new || unwatched.contains(&folder.id) {
+                            let mode = if folder.recursive {
+                                RecursiveMode::Recursive
+                            } else {
+                                RecursiveMode::NonRecursive
+                            };
+                            match debouncer.watcher().watch(Path::new(&folder.path), mode) {
+                                Ok(()) => resumed = unwatched.remove(&folder.id),
+                                Err(e) => {
+                                    // Only said once, not every time it is retried
+                                    if unwatched.insert(folder.id) {
+                                        eprintln!("Failed to watch {}: {}", folder.path, e);
+                                    }
+                                }
+                            }
+                        }
+                        // Catch up with what happened while the app wasn't running or watching
+                        if is_new || resumed || folder.last_scan.is_none_or(|at| at < rescan_before)
+                        {
+                            match scan(&db, &store, &folder).await {
+                                Ok(report) => publish(report),
+                                Err(e) => eprintln!("Failed to scan {}: {}", folder.path, e),
+                            }
+                        }
+                        watching.insert(folder.id, folder);
+                    }
+                }
+                Err(e) => eprintln!("Failed to load watched folders: {}", e),
+            }
+
+            tokio::select! {
+                Some(paths) = changes.recv() => {
+                    for (folder, paths) in by_folder(&watching, paths) {
+                        match sync(&db, &store, folder, Scope::Paths(paths)).await {
+                            Ok(report) => publish(report),
+                            Err(e) => eprintln!("Failed to sync {}: {}", folder.path, e),
+                        }
+                    }
+                }
+                _ = state.reload.notified() => {}
+                _ = tokio::time::sleep(RESCAN_CHECK_INTERVAL) => {}
+            }
+        }
+    });
+}
+
+/// Reported paths grouped by the watched folder they are in.
+fn by_folder(
+    watching: &HashMap<i32, WatchedPathModel>,
+    paths: Vec<PathBuf>,
+) -> Vec<(&WatchedPathModel, Vec<PathBuf>)> {
+    let mut grouped: BTreeMap<i32, (&WatchedPathModel, Vec<PathBuf>)> = BTreeMap::new();
+    for path in paths {
+        if let Some(folder) = watching
+            .values()
+            .find(|folder| path.starts_with(&folder.path))
+        {
+            grouped
+                .entry(folder.id)
+                .or_insert_with(|| (folder, Vec::new()))
+                .1
+                .push(path);
+        }
+    }
+    grouped.into_values().collect()
+}
+
+/// Compare the whole folder with what was imported from it, and record when.
+pub async fn scan(
+    db: &DatabaseConnection,
+    store: &BlobStore,
+    watched: &WatchedPathModel,
+) -> Result<SyncReport, WatchError> {
+    let report = sync(db, store, watched, Scope::Everything).await;
+    let mut active: WatchedPathActiveModel = watched.clone().into();
+    match &report {
+        Ok(_) => {
+            active.last_scan = Set(Some(Utc::now().naive_utc()));
+            active.last_error = Set(None);
+        }
+        Err(e) => active.last_error = Set(Some(e.to_string())),
+    }
+    active.update(db).await?;
+    report
+}
+
+async fn sync(
+    db: &DatabaseConnection,
+    store: &BlobStore,
+    watched: &WatchedPathModel,
+    scope: Scope,
+) -> Result<SyncReport, WatchError> {
+    // An unplugged drive isn't a folder full of deleted files
+    if !Path::new(&watched.path).is_dir() {
+        return Err(WatchError::Unavailable(watched.path.clone()));
+    }
+    let rules = rules_for(watched)?;
+    let known: HashMap<PathBuf, WatchedFileModel> = WatchedFile::find()
+        .filter(watched_file::Column::WatchedPathId.eq(watched.id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|file| (PathBuf::from(&file.path), file))
+        .collect();
+
+    let known_paths = known.keys().cloned().collect();
+    let seen = tokio::task::spawn_blocking(move || look(&rules, scope, known_paths))
+        .await
+        .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    let mut report = SyncReport {
+        watched_path_id: watched.id,
+        ..Default::default()
+    };
+    let now = Utc::now().naive_utc();
+    for (path, seen) in seen {
+        let result = match (known.get(&path), seen) {
+            (None, Seen::File(fingerprint)) => {
+                import(db, store, watched, &path, fingerprint, &mut report).await
+            }
+            (Some(file), Seen::File(fingerprint)) => {
+                refresh(db, store, file, &path, fingerprint, now, &mut report).await
+            }
+            (Some(file), Seen::Gone) if file.missing_since.is_none() => {
+                mark_missing(db, file, now, &mut report).await
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to sync {}: {}", path.display(), e);
+            report.failed += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// What is at each path in `scope`, and at every path imported before that it covers.
+fn look(rules: &Rules, scope: Scope, known: Vec<PathBuf>) -> BTreeMap<PathBuf, Seen> {
+    let mut paths = BTreeSet::new();
+    match scope {
+        Scope::Everything => {
+            paths.extend(walk(rules, rules.root()));
+            paths.extend(known);
+        }
+        Scope::Paths(reported) => {
+            for path in reported {
+                if path.is_dir() {
+                    paths.extend(walk(rules, &path));
+                }
+                // Also what used to be in a folder that is gone now
+                paths.extend(
+                    known
+                        .iter()
+                        .filter(|known| known.starts_with(&path))
+                        .cloned(),
+                );
+                paths.insert(path);
+            }
+        }
+    }
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let seen = match fs::metadata(&path) {
+                Ok(metadata) if metadata.is_file() => {
+                    if rules.allows(&path) {
+                        Seen::File(Fingerprint::of(&metadata))
+                    } else {
+                        Seen::Excluded
+                    }
+                }
+                // A folder is looked through above
+                Ok(_) => return None,
+                Err(_) => Seen::Gone,
+            };
+            Some((path, seen))
+        })
+        .collect()
+}
+
+/// Files under `dir` that the rules allow.
+fn walk(rules: &Rules, dir: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(dir)
+        .max_depth(if rules.recursive() { usize::MAX } else { 1 })
+        .into_iter()
+        .filter_entry(|entry| !entry.file_type().is_dir() || rules.enters(entry.path()))
+        .flatten()
+        .filter(|entry| entry.file_type().is_file() && rules.allows(entry.path()))
+        .map(walkdir::DirEntry::into_path)
+        .collect()
+}
+
+async fn import(
+    db: &DatabaseConnection,
+    store: &BlobStore,
+    watched: &WatchedPathModel,
+    path: &Path,
+    fingerprint: Fingerprint,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    let request = FileOperationRequest {
+        file_path: path.display().to_string(),
+        operation: watched.operation.clone(),
+        // A path linked by hand before becomes the watched item; copies always get their own
+        on_duplicate: if watched.operation == "link" {
+            DuplicateAction::Link
+        } else {
+            DuplicateAction::KeepBoth
+        },
+    };
+    let (item, existing) = files::import_file(db, store, &request).await?;
+
+    let source = WatchedSource {
+        watched_path_id: watched.id,
+        path: request.file_path,
+        missing_since: None,
+    };
+    let tracked = async {
+        let txn = db.begin().await?;
+        set_source(&txn, item.id, &source).await?;
+        WatchedFileActiveModel {
+            watched_path_id: Set(watched.id),
+            path: Set(source.path.clone()),
+            item_id: Set(Some(item.id)),
+            size: Set(fingerprint.size as i64),
+            modified_ms: Set(fingerprint.modified_ms),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await
+    }
+    .await;
+    if let Err(e) = tracked {
+        // Untracked, it would be imported again by every scan; one linked by hand stays
+        if !existing {
+            if let Err(undo) = discard(db, item.id).await {
+                eprintln!("Failed to remove untracked item {}: {}", item.id, undo);
+            }
+        }
+        return Err(e.to_string());
+    }
+    report.imported.push(item.id);
+    Ok(())
+}
+
+/// Delete an item imported for a file that couldn't be tracked.
+async fn discard(db: &DatabaseConnection, id: i32) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+    Item::delete_by_id(id).exec(&txn).await?;
+    indexer::enqueue(&txn, id, IndexOperation::Delete).await?;
+    txn.commit().await
+}
+
+/// Catch an imported file up: unflag it if it is back, and update its item if it changed.
+async fn refresh(
+    db: &DatabaseConnection,
+    store: &BlobStore,
+    file: &WatchedFileModel,
+    path: &Path,
+    fingerprint: Fingerprint,
+    now: NaiveDateTime,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    let changed =
+        file.size != fingerprint.size as i64 || file.modified_ms != fingerprint.modified_ms;
+    if !changed && file.missing_since.is_none() {
+        return Ok(());
+    }
+
+    // The item may have been deleted, in which case the file is only tracked
+    let item = match file.item_id {
+        Some(id) => Item::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(|e| e.to_string())?,
+        None => None,
+    };
+    if let Some(item) = item {
+        if changed {
+            update_item(db, store, item.clone(), path, fingerprint, now).await?;
+            report.updated.push(item.id);
+        }
+        if file.missing_since.is_some() {
+            let source = WatchedSource {
+                watched_path_id: file.watched_path_id,
+                path: file.path.clone(),
+                missing_since: None,
+            };
+            set_source(db, item.id, &source)
+                .await
+                .map_err(|e| e.to_string())?;
+            report.restored.push(item.id);
+        }
+    }
+
+    let mut active: WatchedFileActiveModel = file.clone().into();
+    active.size = Set(fingerprint.size as i64);
+    active.modified_ms = Set(fingerprint.modified_ms);
+    active.missing_since = Set(None);
+    active.seen_at = Set(now);
+    active.update(db).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Bring an item up to date with its changed file: a linked file is hashed again, a copy is
+/// stored again.
+async fn update_item(
+    db: &DatabaseConnection,
+    store: &BlobStore,
+    item: ItemModel,
+    path: &Path,
+    fingerprint: Fingerprint,
+    now: NaiveDateTime,
+) -> Result<(), String> {
+    let request = |operation: &str| FileOperationRequest {
+        file_path: path.display().to_string(),
+        operation: operation.to_string(),
+        on_duplicate: DuplicateAction::KeepBoth,
+    };
+    // Metadata that isn't a JSON object is kept as it is, without the file's new details
+    let mut metadata = metadata_object(item.metadata.as_deref());
+    let mut active: ItemActiveModel = item.clone().into();
+
+    let storing = blobs::hold().await;
+    let staged = if item.source_type.as_deref() == Some(linked_files::LINK_SOURCE_TYPE) {
+        let (_, mut linked) = files::blocking(&request("link"), store, |request, _| {
+            FileProcessor::new().link_file(request)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        if let Some(metadata) = metadata.as_mut() {
+            let previous = metadata
+                .get("linked_file")
+                .cloned()
+                .and_then(|previous| serde_json::from_value::<LinkedFile>(previous).ok());
+            linked.changed_at = match previous {
+                Some(previous) if previous.hash == linked.hash => previous.changed_at,
+                _ => Some(now),
+            };
+            let linked = serde_json::to_value(&linked).map_err(|e| e.to_string())?;
+            metadata.insert("linked_file".to_string(), linked);
+        }
+        None
+    } else {
+        let (stored, staged) = files::blocking(&request("copy"), store, |request, store| {
+            FileProcessor::new().process_file(request, store)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        active.source_url = Set(Some(stored.final_path));
+        active.content_hash = Set(Some(stored.content_hash));
+        Some(staged)
+    };
+
+    active.file_size = Set(Some(fingerprint.size as i64));
+    if let Some(modified_at) = fingerprint.modified_at() {
+        active.file_modified_at = Set(Some(modified_at));
+    }
+    if let Some(metadata) = metadata {
+        active.metadata = Set(Some(Value::Object(metadata).to_string()));
+    }
+    active.updated_at = Set(now);
+
+    let saved = async {
+        let txn = db.begin().await?;
+        active.update(&txn).await?;
+        indexer::enqueue(&txn, item.id, IndexOperation::Upsert).await?;
+        txn.commit().await
+    }
+    .await;
+    drop(storing);
+    match (saved, staged) {
+        (Ok(()), Some(staged)) => {
+            // A copy leaves the source alone, so there is nothing to let go of
+            let _ = staged.commit();
+            // The previous contents may not be used by anything now
+            if let Err(e) = blobs::collect_garbage(db, store).await {
+                eprintln!("Failed to remove unused files: {}", e);
+            }
+            Ok(())
+        }
+        (Ok(()), None) => Ok(()),
+        (Err(e), staged) => {
+            if let Some(staged) = staged {
+                files::undo_staged(db, staged).await;
+            }
+            Err(e.to_string())
+        }
+    }
+}
+
+async fn mark_missing(
+    db: &DatabaseConnection,
+    file: &WatchedFileModel,
+    now: NaiveDateTime,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    if let Some(id) = file.item_id {
+        let source = WatchedSource {
+            watched_path_id: file.watched_path_id,
+            path: file.path.clone(),
+            missing_since: Some(now),
+        };
+        if set_source(db, id, &source)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            report.missing.push(id);
+        }
+    }
+
+    let mut active: WatchedFileActiveModel = file.clone().into();
+    active.missing_since = Set(Some(now));
+    active.update(db).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Store where an item's file is without touching `updated_at`. Returns false if the item is
+/// gone.
+async fn set_source(
+    db: &impl ConnectionTrait,
+    id: i32,
+    source: &WatchedSource,
+) -> Result<bool, DbErr> {
+    let Some(item) = Item::find_by_id(id).one(db).await? else {
+        return Ok(false);
+    };
+    let Some(mut metadata) = metadata_object(item.metadata.as_deref()) else {
+        // Not ours to rewrite
+        return Ok(true);
+    };
+    metadata.insert(
+        "watched".to_string(),
+        serde_json::to_value(source).map_err(|e| DbErr::Custom(e.to_string()))?,
+    );
+    Item::update_many()
+        .col_expr(
+            item::Column::Metadata,
+            Expr::value(Value::Object(metadata).to_string()),
+        )
+        .filter(item::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, test_database};
+
+    async fn watched_source(db: &DatabaseConnection, id: i32) -> (ItemModel, WatchedSource) {
+        let item = Item::find_by_id(id)
+            .one(db)
+            .await
+            .expect("query")
+            .expect("item");
+        let source = metadata_object(item.metadata.as_deref())
+            .expect("metadata")
+            .remove("watched")
+            .expect("watched");
+        (item, serde_json::from_value(source).expect("source"))
+    }
+
+    #[tokio::test]
+    async fn scans_import_update_and_flag_linked_files() {
+        let db = test_database("watch-link").await;
+        let store = BlobStore::at(temp_dir("watch-link-blobs")).expect("store");
+        let root = temp_dir("watch-link-files");
+        fs::create_dir_all(root.join("2026")).expect("dir");
+        fs::write(root.join("plan.md"), "# Plan").expect("write");
+        fs::write(root.join("2026/trip.jpg"), "jpeg bytes").expect("write");
+        fs::write(root.join("debug.log"), "noise").expect("write");
+
+        let watched = add(
+            &db,
+            &root.display().to_string(),
+            true,
+            vec![],
+            vec!["*.log".to_string(), " ".to_string()],
+            WatchOperation::Link,
+        )
+        .await
+        .expect("add");
+        assert_eq!(watched.exclude_patterns.as_deref(), Some("*.log"));
+        assert!(matches!(
+            add(
+                &db,
+                &root.join("2026").display().to_string(),
+                true,
+                vec![],
+                vec![],
+                WatchOperation::Link
+            )
+            .await,
+            Err(WatchError::Overlaps(_, _))
+        ));
+
+        let report = scan(&db, &store, &watched).await.expect("scan");
+        assert_eq!(report.imported.len(), 2);
+        let plan = root.join("plan.md").canonicalize().expect("path");
+        let item = Item::find()
+            .filter(item::Column::SourceUrl.eq(plan.display().to_string()))
+            .one(&db)
+            .await
+            .expect("query")
+            .expect("linked item");
+        assert_eq!(
+            item.source_type.as_deref(),
+            Some(linked_files::LINK_SOURCE_TYPE)
+        );
+        let (_, source) = watched_source(&db, item.id).await;
+        assert_eq!(source.missing_since, None);
+
+        // Nothing changed, so nothing to do
+        let report = scan(&db, &store, &watched).await.expect("scan");
+        assert_eq!(
+            report,
+            SyncReport {
+                watched_path_id: watched.id,
+                ..Default::default()
+            }
+        );
+
+        fs::write(&plan, "# Plan, revised at length").expect("write");
+        let report = scan(&db, &store, &watched).await.expect("scan");
+        assert_eq!(report.updated, vec![item.id]);
+        let (updated, _) = watched_source(&db, item.id).await;
+        assert_eq!(updated.file_size, Some(25));
+
+        fs::remove_file(&plan).expect("remove");
+        let report = scan(&db, &store, &watched).await.expect("scan");
+        assert_eq!(report.missing, vec![item.id]);
+        let (_, source) = watched_source(&db, item.id).await;
+        assert!(source.missing_since.is_some());
+        // Flagged once, and the item is kept
+        let report = scan(&db, &store, &watched).await.expect("scan");
+        assert!(report.missing.is_empty());
+
+        fs::write(&plan, "# Plan, revised at length").expect("write");
+        let report = sync(&db, &store, &watched, Scope::Paths(vec![plan.clone()]))
+            .await
+            .expect("sync");
+        assert_eq!(report.restored, vec![item.id]);
+        let (_, source) = watched_source(&db, item.id).await;
+        assert_eq!(source.missing_since, None);
+
+        let last_scan = WatchedPath::find_by_id(watched.id)
+            .one(&db)
+            .await
+            .expect("query")
+            .expect("watched")
+            .last_scan;
+        assert!(last_scan.is_some());
+    }
+
+    #[tokio::test]
+    async fn copies_are_stored_again_when_changed() {
+        let db = test_database("watch-copy").await;
+        let store = BlobStore::at(temp_dir("watch-copy-blobs")).expect("store");
+        let root = temp_dir("watch-copy-files");
+        let note = root.join("note.txt");
+        fs::write(&note, "first").expect("write");
+
+        let watched = add(
+            &db,
+            &root.display().to_string(),
+            false,
+            vec!["*.txt".to_string()],
+            vec![],
+            WatchOperation::Copy,
+        )
+        .await
+        .expect("add");
+        let note = note.canonicalize().expect("path");
+        let report = sync(&db, &store, &watched, Scope::Paths(vec![note.clone()]))
+            .await
+            .expect("sync");
+        let [id] = report.imported[..] else {
+            panic!("expected one import, got {:?}", report);
+        };
+        let (first, _) = watched_source(&db, id).await;
+        let first_blob = PathBuf::from(first.source_url.clone().expect("stored"));
+        assert_eq!(fs::read(&first_blob).expect("read"), b"first");
+
+        fs::write(&note, "second version").expect("write");
+        let report = sync(&db, &store, &watched, Scope::Paths(vec![note.clone()]))
+            .await
+            .expect("sync");
+        assert_eq!(report.updated, vec![id]);
+        let (second, _) = watched_source(&db, id).await;
+        assert_ne!(second.content_hash, first.content_hash);
+        assert_eq!(
+            fs::read(second.source_url.expect("stored")).expect("read"),
+            b"second version"
+        );
+        // The old contents weren't used by anything else
+        assert!(!first_blob.exists());
+        // The folder itself is left alone
+        assert!(note.exists());
+    }
+
+    #[tokio::test]
+    async fn unavailable_folders_are_not_flagged_missing() {
+        let db = test_database("watch-unplugged").await;
+        let store = BlobStore::at(temp_dir("watch-unplugged-blobs")).expect("store");
+        let root = temp_dir("watch-unplugged-files");
+        fs::write(root.join("song.flac"), "audio").expect("write");
+        let watched = add(
+            &db,
+            &root.display().to_string(),
+            true,
+            vec![],
+            vec![],
+            WatchOperation::Link,
+        )
+        .await
+        .expect("add");
+        scan(&db, &store, &watched).await.expect("scan");
+
+        fs::remove_dir_all(&root).expect("unplug");
+        let error = scan(&db, &store, &watched).await.expect_err("unavailable");
+        assert!(matches!(error, WatchError::Unavailable(_)));
+        let files = WatchedFile::find().all(&db).await.expect("query");
+        assert!(files.iter().all(|file| file.missing_since.is_none()));
+        let watched = WatchedPath::find_by_id(watched.id)
+            .one(&db)
+            .await
+            .expect("query")
+            .expect("watched");
+        assert!(watched.last_error.is_some());
+
+        assert!(remove(&db, watched.id).await.expect("remove"));
+        assert!(WatchedFile::find()
+            .all(&db)
+            .await
+            .expect("query")
+            .is_empty());
+        assert_eq!(Item::find().all(&db).await.expect("query").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn items_are_removed_when_their_file_cant_be_tracked() {
+        let db = test_database("watch-untracked").await;
+        let store = BlobStore::at(temp_dir("watch-untracked-blobs")).expect("store");
+        let root = temp_dir("watch-untracked-files");
+        let note = root.join("note.txt");
+        fs::write(&note, "tracked twice").expect("write");
+        let watched = add(
+            &db,
+            &root.display().to_string(),
+            false,
+            vec![],
+            vec![],
+            WatchOperation::Copy,
+        )
+        .await
+        .expect("add");
+
+        // Already has a row, so recording the import's fails
+        let fingerprint = Fingerprint::of(&fs::metadata(&note).expect("metadata"));
+        WatchedFileActiveModel {
+            watched_path_id: Set(watched.id),
+            path: Set(note.display().to_string()),
+            size: Set(fingerprint.size as i64),
+            modified_ms: Set(fingerprint.modified_ms),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .expect("insert");
+
+        let mut report = SyncReport::default();
+        import(&db, &store, &watched, &note, fingerprint, &mut report)
+            .await
+            .expect_err("already tracked");
+        assert!(report.imported.is_empty());
+        assert!(Item::find().all(&db).await.expect("query").is_empty());
+        assert!(note.exists());
+    }
+}
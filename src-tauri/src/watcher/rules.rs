@@ -0,0 +1,184 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Files of `.gitignore`-style patterns honoured in every folder under a watched path.
+pub const IGNORE_FILES: &[&str] = &[".gitignore", ".junkdrawerignore"];
+
+/// Which files under a watched path are imported.
+pub struct Rules {
+    root: PathBuf,
+    recursive: bool,
+    /// When set, files must match one of these.
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Ignore files already read, by folder. Rules are rebuilt for every sync, so edits to an
+    /// ignore file are picked up by the next one.
+    ignore_files: Mutex<HashMap<PathBuf, Arc<Gitignore>>>,
+}
+
+impl Rules {
+    /// Patterns are matched against paths relative to `root`, where `*` also crosses folders,
+    /// so `*.pdf` finds PDFs at any depth.
+    pub fn new(
+        root: &Path,
+        recursive: bool,
+        include: &[String],
+        exclude: &[String],
+    ) -> Result<Self, globset::Error> {
+        Ok(Self {
+            root: root.to_path_buf(),
+            recursive,
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(glob_set(include)?)
+            },
+            exclude: glob_set(exclude)?,
+            ignore_files: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn recursive(&self) -> bool {
+        self.recursive
+    }
+
+    /// Whether the file at `path` should be imported.
+    pub fn allows(&self, path: &Path) -> bool {
+        let Some(relative) = self.relative(path) else {
+            return false;
+        };
+        if !self.recursive && relative.components().count() > 1 {
+            return false;
+        }
+        if self
+            .include
+            .as_ref()
+            .is_some_and(|include| !include.is_match(relative))
+        {
+            return false;
+        }
+        // An excluded folder excludes everything in it
+        if relative.ancestors().any(|path| self.exclude.is_match(path)) {
+            return false;
+        }
+        !self.ignored(path, false)
+    }
+
+    /// Whether a scan should look inside the folder at `path`.
+    pub fn enters(&self, path: &Path) -> bool {
+        if path == self.root {
+            return true;
+        }
+        let Some(relative) = self.relative(path) else {
+            return false;
+        };
+        self.recursive && !self.exclude.is_match(relative) && !self.ignored(path, true)
+    }
+
+    /// `path` relative to the root, or `None` for paths outside it and hidden files and
+    /// folders such as `.git`, which are never imported.
+    fn relative<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let hidden = relative
+            .components()
+            .any(|component| component.as_os_str().to_string_lossy().starts_with('.'));
+        (!hidden && relative.components().next().is_some()).then_some(relative)
+    }
+
+    /// The nearest ignore file with an opinion about `path` decides, as with git.
+    fn ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for dir in path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.root))
+        {
+            match self
+                .ignore_file(dir)
+                .matched_path_or_any_parents(path, is_dir)
+            {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+
+    fn ignore_file(&self, dir: &Path) -> Arc<Gitignore> {
+        let mut cache = self.ignore_files.lock().unwrap_or_else(|e| e.into_inner());
+        cache
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let mut builder = GitignoreBuilder::new(dir);
+                for name in IGNORE_FILES {
+                    let file = dir.join(name);
+                    if file.is_file() {
+                        if let Some(e) = builder.add(&file) {
+                            eprintln!("Ignoring bad patterns in {}: {}", file.display(), e);
+                        }
+                    }
+                }
+                Arc::new(builder.build().unwrap_or_else(|_| Gitignore::empty()))
+            })
+            .clone()
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn patterns_and_ignore_files_decide_what_is_imported() {
+        let root = temp_dir("watch-rules");
+        fs::create_dir_all(root.join("drafts/old")).expect("dirs");
+        fs::create_dir_all(root.join("build")).expect("dirs");
+        fs::write(root.join(".gitignore"), "build/\n*.tmp\n").expect("write");
+        fs::write(root.join("drafts/.junkdrawerignore"), "old/\n!keep.tmp\n").expect("write");
+
+        let strings =
+            |patterns: &[&str]| patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        let rules = Rules::new(&root, true, &[], &strings(&["*.log"])).expect("rules");
+        assert!(rules.allows(&root.join("report.pdf")));
+        assert!(rules.allows(&root.join("drafts/chapter.md")));
+        assert!(!rules.allows(&root.join("server.log")));
+        assert!(!rules.allows(&root.join("scratch.tmp")));
+        assert!(!rules.allows(&root.join(".DS_Store")));
+        assert!(!rules.allows(&root.join(".git/config")));
+        assert!(!rules.allows(&root.join("build/app.bin")));
+        assert!(!rules.allows(&root.join("drafts/old/chapter.md")));
+        // The nearer ignore file wins
+        assert!(rules.allows(&root.join("drafts/keep.tmp")));
+        assert!(!rules.allows(Path::new("/elsewhere/report.pdf")));
+
+        assert!(rules.enters(&root.join("drafts")));
+        assert!(!rules.enters(&root.join("build")));
+        assert!(!rules.enters(&root.join("drafts/old")));
+
+        let pdfs = Rules::new(&root, false, &strings(&["*.pdf"]), &[]).expect("rules");
+        assert!(pdfs.allows(&root.join("report.pdf")));
+        assert!(!pdfs.allows(&root.join("notes.md")));
+        assert!(!pdfs.allows(&root.join("drafts/report.pdf")));
+        assert!(!pdfs.enters(&root.join("drafts")));
+
+        assert!(Rules::new(&root, true, &strings(&["[unclosed"]), &[]).is_err());
+    }
+}
@@ -55,6 +55,7 @@ const noteFactory = (overrides: Partial<Item> = {}): Item => ({
   file_size: null,
   file_modified_at: null,
   metadata: null,
+  canonical_url: null,
   created_at: new Date("2024-01-01T00:00:00.000Z").toISOString(),
   updated_at: new Date("2024-01-01T00:00:00.000Z").toISOString(),
   ...overrides,
@@ -248,6 +249,70 @@ describe("NotesWorkspace", () => {
       })
     })
 
+    it("reloads items after subscribing to a feed URL", async () => {
+      const entry = noteFactory({
+        id: 4,
+        title: "Latest post",
+        item_type: "bookmark",
+        source_type: "feed",
+        source_url: "https://blog.example.com/latest",
+      })
+
+      getAllItems.mockResolvedValueOnce([]).mockResolvedValue([entry])
+      createBookmark.mockResolvedValue({
+        kind: "feed",
+        id: 1,
+        url: "https://blog.example.com/feed.xml",
+        title: "Example Blog",
+      })
+
+      render(<NotesWorkspace />)
+
+      await screen.findByText("Create your first note or bookmark to get started.")
+
+      const urlInput = screen.getByPlaceholderText("Paste URL to bookmark...")
+
+      await act(async () => {
+        fireEvent.change(urlInput, { target: { value: "https://blog.example.com/feed.xml" } })
+        fireEvent.keyDown(urlInput, { key: "Enter", code: "Enter" })
+      })
+
+      expect(await screen.findByText("Latest post")).toBeInTheDocument()
+      expect(urlInput).toHaveValue("")
+    })
+
+    it("moves an already saved bookmark to the top instead of listing it twice", async () => {
+      const note = noteFactory()
+      const bookmark = noteFactory({
+        id: 2,
+        title: "Example Site",
+        item_type: "bookmark",
+        source_type: "bookmark",
+        source_url: "https://example.com",
+        canonical_url: "https://example.com/",
+      })
+
+      getAllItems.mockResolvedValue([note, bookmark])
+      createBookmark.mockResolvedValue({ ...bookmark, title: "Example Site, refreshed" })
+
+      render(<NotesWorkspace />)
+
+      await screen.findByDisplayValue("First note")
+
+      const urlInput = screen.getByPlaceholderText("Paste URL to bookmark...")
+
+      await act(async () => {
+        fireEvent.change(urlInput, { target: { value: "https://example.com/?utm_source=feed" } })
+        fireEvent.keyDown(urlInput, { key: "Enter", code: "Enter" })
+      })
+
+      expect(await screen.findByRole("heading", { name: "Example Site, refreshed" })).toBeInTheDocument()
+      expect(screen.queryByText("Example Site")).not.toBeInTheDocument()
+      const deleteButtons = screen.getAllByRole("button", { name: /delete/i })
+      expect(deleteButtons).toHaveLength(2)
+      expect(deleteButtons[0].closest("li")).toHaveTextContent("Example Site, refreshed")
+    })
+
     it("displays bookmarks with distinct visual treatment", async () => {
       const note = noteFactory()
       const bookmark = noteFactory({
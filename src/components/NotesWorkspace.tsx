@@ -115,9 +115,15 @@ export default function NotesWorkspace({
 
     setCreatingBookmark(true)
     try {
-      const created = await createBookmark(bookmarkUrl.trim())
-      setNotes((prev) => [created, ...prev])
-      setSelectedId(created.id)
+      const saved = await createBookmark(bookmarkUrl.trim())
+      if (saved.kind === "feed") {
+        // Subscribing saved the feed's current entries as items
+        setNotes(await getAllItems())
+      } else {
+        // A URL already saved comes back as the existing item, which moves to the top
+        setNotes((prev) => [saved, ...prev.filter((note) => note.id !== saved.id)])
+        setSelectedId(saved.id)
+      }
       setBookmarkUrl("")
       setError(null)
     } catch (err) {
@@ -1,5 +1,32 @@
 import { invoke } from "@tauri-apps/api/core"
-import type { CreateItemRequest, Item, UpdateItemRequest } from "../types/database"
+import { listen } from "@tauri-apps/api/event"
+import type {
+  BookmarkRefreshedEvent,
+  CreateItemRequest,
+  DirectoryImportProgress,
+  DirectoryImportReport,
+  EnrichmentProgress,
+  FacetCount,
+  Feed,
+  FeedPolledEvent,
+  FetchProfile,
+  FetchProfileRequest,
+  FullReindexReport,
+  ImportFormat,
+  ImportReport,
+  IndexConsistencyReport,
+  Item,
+  LinkCheck,
+  LinkCheckedEvent,
+  LinkedFileCheckedEvent,
+  SavedUrl,
+  SearchItemsResponse,
+  SearchQuery,
+  UpdateItemRequest,
+  WatchOperation,
+  WatchSyncReport,
+  WatchedPath,
+} from "../types/database"
 
 export async function createItem(request: CreateItemRequest): Promise<Item> {
   return invoke("create_item", { request })
@@ -21,15 +48,272 @@ export async function updateItem(request: UpdateItemRequest): Promise<Item> {
   return invoke("update_item", { request })
 }
 
-export async function createBookmark(url: string): Promise<Item> {
+/** Save a URL as a bookmark or file item, or subscribe to it when it is a feed */
+export async function createBookmark(url: string): Promise<SavedUrl> {
   return invoke("create_bookmark", { url })
 }
 
 export interface FileOperationRequest {
   filePath: string
-  operation: "copy" | "move"
+  /** "link" indexes the file where it is instead of storing a copy */
+  operation: "copy" | "move" | "link"
+  /** When the contents are already stored: return that item (default) or add another */
+  onDuplicate?: "link" | "keep_both"
 }
 
 export async function createFileItem(request: FileOperationRequest): Promise<Item> {
   return invoke("create_file_item", { request })
 }
+
+export interface DirectoryImportRequest {
+  path: string
+  /** Chosen by the caller, to match progress events and to cancel the import */
+  importId: string
+  /** Defaults to true */
+  recursive?: boolean
+  /** Globs relative to the folder; when given, only matching files are imported */
+  includePatterns?: string[]
+  excludePatterns?: string[]
+  /** Bytes */
+  minSize?: number
+  maxSize?: number
+  /** Defaults to "copy" */
+  operation?: FileOperationRequest["operation"]
+  onDuplicate?: FileOperationRequest["onDuplicate"]
+}
+
+/** Import every file in a folder that passes the filters; resolves once all are done */
+export async function importDirectory(
+  request: DirectoryImportRequest
+): Promise<DirectoryImportReport> {
+  return invoke("import_directory", { request })
+}
+
+/** Stop an import after the files it is in the middle of; its report says what was left */
+export async function cancelDirectoryImport(importId: string): Promise<void> {
+  return invoke("cancel_directory_import", { importId })
+}
+
+/**
+ * Listen for progress of folder imports, sent after each file and when an import ends
+ * @returns Function to unsubscribe
+ */
+export async function onDirectoryImportProgress(
+  callback: (event: DirectoryImportProgress) => void
+): Promise<() => void> {
+  return listen<DirectoryImportProgress>("directory-import-progress", (event) => {
+    callback(event.payload)
+  })
+}
+
+/** The item already storing the same contents as the file at `path`, if any */
+export async function findDuplicateFile(path: string): Promise<Item | null> {
+  return invoke("find_duplicate_file", { path })
+}
+
+/** Point a linked file that went missing at the file under `root` with the same contents */
+export async function relinkFile(id: number, root: string): Promise<Item> {
+  return invoke("relink_file", { id, root })
+}
+
+/**
+ * Listen for background checks of linked files, which notice changes, renames and removals
+ * @returns Function to unsubscribe
+ */
+export async function onLinkedFileChecked(
+  callback: (event: LinkedFileCheckedEvent) => void
+): Promise<() => void> {
+  const unlisten = await listen<LinkedFileCheckedEvent>("linked-file-checked", (event) => {
+    callback(event.payload)
+  })
+  return unlisten
+}
+
+/**
+ * Import a bookmark export from a browser or a read-later service
+ * @param options.format Detected from the file when omitted
+ * @param options.dryRun Only report what would be created and skipped
+ */
+export async function importBookmarks(
+  path: string,
+  options: { format?: ImportFormat; dryRun?: boolean; enrich?: boolean } = {}
+): Promise<ImportReport> {
+  return invoke("import_bookmarks", {
+    path,
+    format: options.format ?? null,
+    dryRun: options.dryRun ?? false,
+    enrich: options.enrich ?? false,
+  })
+}
+
+/** Import a browser's exported `bookmarks.html`, optionally fetching page metadata afterwards */
+export async function importBookmarksHtml(path: string, enrich: boolean): Promise<ImportReport> {
+  return invoke("import_bookmarks_html", { path, enrich })
+}
+
+export async function onBookmarkImportProgress(
+  callback: (progress: EnrichmentProgress) => void
+): Promise<() => void> {
+  return listen<EnrichmentProgress>("bookmark-import-progress", (event) => {
+    callback(event.payload)
+  })
+}
+
+export async function checkBookmarkLink(id: number): Promise<LinkCheck> {
+  return invoke("check_bookmark_link", { id })
+}
+
+/** Re-fetch a bookmark's metadata now; titles and content edited by hand are kept. */
+export async function refreshBookmark(id: number): Promise<Item> {
+  return invoke("refresh_bookmark", { id })
+}
+
+/**
+ * Listen for bookmark metadata refreshes, both background sweeps and `refreshBookmark` calls
+ * @returns Function to unsubscribe
+ */
+export async function onBookmarkRefreshed(
+  callback: (event: BookmarkRefreshedEvent) => void
+): Promise<() => void> {
+  const unlisten = await listen<BookmarkRefreshedEvent>("bookmark-refreshed", (event) => {
+    callback(event.payload)
+  })
+  return unlisten
+}
+
+/**
+ * Listen for bookmark link checks, both background sweeps and `checkBookmarkLink` calls
+ * @returns Function to unsubscribe
+ */
+export async function onBookmarkLinkChecked(
+  callback: (event: LinkCheckedEvent) => void
+): Promise<() => void> {
+  const unlisten = await listen<LinkCheckedEvent>("bookmark-link-checked", (event) => {
+    callback(event.payload)
+  })
+  return unlisten
+}
+
+export async function subscribeFeed(
+  url: string,
+  filters: { includeKeywords?: string[]; excludeKeywords?: string[] } = {}
+): Promise<Feed> {
+  return invoke("subscribe_feed", {
+    url,
+    includeKeywords: filters.includeKeywords ?? null,
+    excludeKeywords: filters.excludeKeywords ?? null,
+  })
+}
+
+export async function listFeeds(): Promise<Feed[]> {
+  return invoke("list_feeds")
+}
+
+export async function setFeedFilters(
+  id: number,
+  filters: { includeKeywords?: string[]; excludeKeywords?: string[] }
+): Promise<Feed> {
+  return invoke("set_feed_filters", {
+    id,
+    includeKeywords: filters.includeKeywords ?? null,
+    excludeKeywords: filters.excludeKeywords ?? null,
+  })
+}
+
+export async function unsubscribeFeed(id: number): Promise<void> {
+  return invoke("unsubscribe_feed", { id })
+}
+
+/**
+ * Listen for feed polls, including the first one made when subscribing
+ * @returns Function to unsubscribe
+ */
+export async function onFeedPolled(
+  callback: (event: FeedPolledEvent) => void
+): Promise<() => void> {
+  return listen<FeedPolledEvent>("feed-polled", (event) => {
+    callback(event.payload)
+  })
+}
+
+/**
+ * Watch a folder and import its files, linking them in place unless `operation` is "copy"
+ * @param options.includePatterns Globs relative to the folder; only matching files are imported
+ * @param options.excludePatterns Globs for files and folders never imported
+ */
+export async function addWatchedPath(
+  path: string,
+  options: {
+    recursive?: boolean
+    includePatterns?: string[]
+    excludePatterns?: string[]
+    operation?: WatchOperation
+  } = {}
+): Promise<WatchedPath> {
+  return invoke("add_watched_path", {
+    path,
+    recursive: options.recursive ?? null,
+    includePatterns: options.includePatterns ?? null,
+    excludePatterns: options.excludePatterns ?? null,
+    operation: options.operation ?? null,
+  })
+}
+
+export async function listWatchedPaths(): Promise<WatchedPath[]> {
+  return invoke("list_watched_paths")
+}
+
+/** Stop watching a folder; items made from its files are kept */
+export async function removeWatchedPath(id: number): Promise<void> {
+  return invoke("remove_watched_path", { id })
+}
+
+export async function rescanWatchedPath(id: number): Promise<WatchSyncReport> {
+  return invoke("rescan_watched_path", { id })
+}
+
+/**
+ * Listen for files imported, updated or gone missing in watched folders
+ * @returns Function to unsubscribe
+ */
+export async function onWatchedFilesSynced(
+  callback: (event: WatchSyncReport) => void
+): Promise<() => void> {
+  return listen<WatchSyncReport>("watched-files-synced", (event) => {
+    callback(event.payload)
+  })
+}
+
+export async function listFetchProfiles(): Promise<FetchProfile[]> {
+  return invoke("list_fetch_profiles")
+}
+
+/** Create or update the fetch profile for `request.domain`, keeping its cookies */
+export async function saveFetchProfile(request: FetchProfileRequest): Promise<FetchProfile> {
+  return invoke("save_fetch_profile", { request })
+}
+
+/** Replace a domain's cookies with those for it in a Netscape `cookies.txt` file */
+export async function importFetchCookies(domain: string, path: string): Promise<FetchProfile> {
+  return invoke("import_fetch_cookies", { domain, path })
+}
+
+export async function deleteFetchProfile(id: number): Promise<void> {
+  return invoke("delete_fetch_profile", { id })
+}
+
+export async function searchItems(request: SearchQuery): Promise<SearchItemsResponse> {
+  return invoke("search_items", { request })
+}
+
+export async function searchFacets(request: SearchQuery): Promise<FacetCount[]> {
+  return invoke("search_facets", { request })
+}
+
+export async function reindexAll(): Promise<FullReindexReport> {
+  return invoke("reindex_all")
+}
+
+export async function verifyIndex(): Promise<IndexConsistencyReport> {
+  return invoke("verify_index")
+}
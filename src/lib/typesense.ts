@@ -6,6 +6,24 @@ export interface ServerStatus {
   message: string
 }
 
+export interface TypesenseSearchConfig {
+  base_url: string
+  search_api_key: string
+  collection: string
+}
+
+export interface TypesenseDiagnostics {
+  running: boolean
+  pid: number | null
+  restart_count: number
+  consecutive_failures: number
+  gave_up: boolean
+  last_exit_code: number | null
+  last_exit_signal: number | null
+  last_exit_at: string | null
+  log_path: string | null
+}
+
 export function isTauriEnvironment(): boolean {
   return (
     typeof window !== "undefined" &&
@@ -61,6 +79,32 @@ export async function isTypesenseServerRunning(): Promise<boolean> {
   }
 }
 
+/**
+ * Get restart counts, last exit status and log location for the Typesense sidecar
+ * @returns Promise that resolves to the supervisor's diagnostics
+ * @throws Error if the diagnostics cannot be read
+ */
+export async function getTypesenseDiagnostics(): Promise<TypesenseDiagnostics> {
+  try {
+    return await invoke("typesense_diagnostics")
+  } catch (error) {
+    throw new Error(`Failed to get Typesense diagnostics: ${error}`)
+  }
+}
+
+/**
+ * Get the address and search-only API key for querying Typesense directly
+ * @returns Promise that resolves to the search configuration for this run
+ * @throws Error if the server has not been started yet
+ */
+export async function getTypesenseSearchConfig(): Promise<TypesenseSearchConfig> {
+  try {
+    return await invoke("typesense_search_config")
+  } catch (error) {
+    throw new Error(`Failed to get Typesense search config: ${error}`)
+  }
+}
+
 /**
  * Listen for Typesense server status updates
  * @param callback Function to call when status updates are received
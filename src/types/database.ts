@@ -10,10 +10,158 @@ export interface Item {
   file_size: number | null
   file_modified_at: string | null
   metadata: string | null
+  canonical_url: string | null
+  /** SHA-256 of a stored file's contents; items with the same hash share one copy */
+  content_hash: string | null
+  original_filename: string | null
   created_at: string
   updated_at: string
 }
 
+/** Shape of `Item.metadata` for bookmarks, stored as a JSON string. */
+export interface BookmarkDetails {
+  canonical_url?: string
+  author?: string
+  published_time?: string
+  lang?: string
+  open_graph?: Record<string, string>
+  twitter?: Record<string, string>
+  favicon_path?: string
+  preview_image_path?: string
+  description?: string
+  word_count?: number
+  snapshot_path?: string
+  link_check?: LinkCheck
+  refresh?: RefreshStatus
+  history?: MetadataRevision[]
+  edited_fields?: string[]
+  subtype?: BookmarkSubtype
+  structured?: StructuredFields
+  oembed?: OEmbed
+}
+
+/** What a bookmarked page is about, from its schema.org data or citation tags. */
+export type BookmarkSubtype =
+  | "article"
+  | "video"
+  | "product"
+  | "recipe"
+  | "event"
+  | "code_repository"
+  | "scholarly_article"
+
+/** Typed fields from the page's structured data; durations and dates are ISO 8601. */
+export interface StructuredFields {
+  name?: string
+  authors?: string[]
+  date_published?: string
+  price?: string
+  price_currency?: string
+  availability?: string
+  brand?: string
+  rating?: string
+  rating_count?: string
+  duration?: string
+  upload_date?: string
+  embed_url?: string
+  thumbnail_url?: string
+  ingredients?: string[]
+  instructions?: string[]
+  recipe_yield?: string
+  prep_time?: string
+  cook_time?: string
+  total_time?: string
+  start_date?: string
+  end_date?: string
+  location?: string
+  doi?: string
+  journal?: string
+  programming_language?: string
+  code_repository?: string
+}
+
+export interface OEmbed {
+  type: string
+  title?: string
+  author_name?: string
+  author_url?: string
+  provider_name?: string
+  thumbnail_url?: string
+  html?: string
+}
+
+export type LinkState = "ok" | "moved" | "dead" | "unreachable"
+
+/** Result of the last time a bookmark's URL was re-requested. */
+export interface LinkCheck {
+  state: LinkState
+  status?: number
+  redirect_url?: string
+  error?: string
+  failures: number
+  checked_at: string
+}
+
+/** Payload of the `bookmark-link-checked` event. */
+export interface LinkCheckedEvent {
+  item_id: number
+  link_check: LinkCheck
+}
+
+/** `pending_enrichment` bookmarks were saved offline and still have the URL as their title. */
+export type RefreshState = "pending_enrichment" | "enriched"
+
+/** How a bookmark's metadata was last fetched. */
+export interface RefreshStatus {
+  state: RefreshState
+  attempted_at?: string
+  refreshed_at?: string
+  error?: string
+  failures: number
+}
+
+/** A title and description replaced by a metadata refresh. */
+export interface MetadataRevision {
+  title: string
+  description?: string
+  replaced_at: string
+}
+
+/** Payload of the `bookmark-refreshed` event. */
+export interface BookmarkRefreshedEvent {
+  item_id: number
+  refresh: RefreshStatus
+  changed: boolean
+  /** Set when the bookmark, saved offline, was removed as a duplicate of this item. */
+  merged_into?: number
+}
+
+/** Shape of `Item.metadata` for files linked in place (`source_type` "link"). */
+export interface LinkedFileDetails {
+  linked_file: LinkedFile
+}
+
+/** A file indexed where it is, with what was last seen of it. */
+export interface LinkedFile {
+  hash: string
+  /** Only known on Unix */
+  inode?: number
+  size: number
+  modified_ms?: number
+  state: "present" | "missing"
+  checked_at: string
+  changed_at?: string
+  missing_since?: string
+}
+
+/** Payload of the `linked-file-checked` event. */
+export interface LinkedFileCheckedEvent {
+  item_id: number
+  linked_file: LinkedFile
+  /** The old path, when the file was found renamed */
+  moved_from?: string
+}
+
 export interface CreateItemRequest {
   title: string
   content?: string | null
@@ -25,6 +173,8 @@ export interface CreateItemRequest {
   file_size?: number | null
   file_modified_at?: string | null
   metadata?: string | null
+  /** When the item was originally saved, for imports; defaults to now. */
+  created_at?: string | null
 }
 
 export interface UpdateItemRequest {
@@ -40,3 +190,212 @@ export interface UpdateItemRequest {
   file_modified_at?: string | null
   metadata?: string | null
 }
+
+export type SearchSort = "relevance" | "updated_desc" | "updated_asc" | "created_desc" | "created_asc"
+
+export interface SearchQuery {
+  query: string
+  item_type?: string | null
+  subtype?: BookmarkSubtype | null
+  tags?: string[]
+  sort?: SearchSort
+  page?: number | null
+  per_page?: number | null
+}
+
+export interface SearchHighlight {
+  field: string
+  snippet: string
+  matched_tokens: string[]
+}
+
+export interface FacetCount {
+  field: string
+  values: { value: string; count: number }[]
+}
+
+export interface SearchItemsResponse {
+  hits: { item: Item; highlights: SearchHighlight[] }[]
+  found: number
+  page: number
+  facets: FacetCount[]
+  backend: SearchBackendName
+}
+
+export type SearchBackendName = "typesense" | "sqlite_fts"
+
+export interface ReindexReport {
+  indexed: number
+  failed: { id: number | null; error: string }[]
+}
+
+export interface FullReindexReport {
+  sqlite_fts: ReindexReport
+  typesense: ReindexReport | null
+  typesense_error: string | null
+}
+
+export interface IndexConsistencyReport {
+  item_count: number
+  document_count: number
+  missing: number[]
+  stale: number[]
+  orphaned: number[]
+  consistent: boolean
+}
+
+export type ImportSkipReason = "unsupported_url" | "already_saved" | "duplicate_in_import"
+
+export type ImportFormat =
+  | "browser"
+  | "pocket_html"
+  | "pocket_csv"
+  | "pinboard"
+  | "raindrop"
+  | "instapaper"
+
+export interface ImportReport {
+  format: ImportFormat | null
+  /** Nothing was saved; `created` and `added` describe what would have been. */
+  dry_run: boolean
+  found: number
+  created: number
+  added: { title: string; url: string }[]
+  skipped: { title: string; url: string; reason: ImportSkipReason }[]
+  enriching: boolean
+}
+
+/** Payload of the `bookmark-import-progress` event. */
+export interface EnrichmentProgress {
+  total: number
+  processed: number
+  enriched: number
+  failed: number
+  finished: boolean
+}
+
+export interface Feed {
+  id: number
+  url: string
+  title: string
+  /** Comma-separated; when set, only entries mentioning one of these are saved. */
+  include_keywords: string | null
+  exclude_keywords: string | null
+  etag: string | null
+  last_modified: string | null
+  last_polled_at: string | null
+  last_error: string | null
+  created_at: string
+  updated_at: string
+}
+
+/** What `createBookmark` made of a URL: an item, or a subscription when it was a feed. */
+export type SavedUrl = (Item & { kind: "item" }) | (Feed & { kind: "feed" })
+
+/** Payload of the `feed-polled` event. */
+export interface FeedPolledEvent {
+  feed_id: number
+  /** Items created for new entries. */
+  created: number[]
+  /** New entries the keyword filters turned away. */
+  filtered: number
+  not_modified: boolean
+  error?: string
+}
+
+/** How pages on a domain and its subdomains are fetched. */
+export interface FetchProfile {
+  id: number
+  domain: string
+  headers: Record<string, string>
+  /** Imported cookies; their values stay in the backend. */
+  cookie_count: number
+  proxy_url: string | null
+  user_agent: string | null
+  max_response_bytes: number | null
+  accept_invalid_certs: boolean
+  /** PEM file with an extra CA to trust. */
+  root_certificate_path: string | null
+}
+
+export interface FetchProfileRequest {
+  domain: string
+  headers?: Record<string, string>
+  proxy_url?: string | null
+  user_agent?: string | null
+  max_response_bytes?: number | null
+  accept_invalid_certs?: boolean
+  root_certificate_path?: string | null
+}
+
+/** Why a folder import left a file alone. */
+export type FileSkipReason = "excluded" | "too_small" | "too_large" | "already_imported"
+
+export type FileOutcome =
+  | { status: "imported"; item_id: number }
+  /** `item_id` is the existing item for files already imported. */
+  | { status: "skipped"; reason: FileSkipReason; item_id?: number }
+  | { status: "failed"; error: string }
+
+/** Payload of the `directory-import-progress` event. */
+export interface DirectoryImportProgress {
+  import_id: string
+  /** Files to import, after filtering. */
+  total: number
+  processed: number
+  imported: number
+  skipped: number
+  failed: number
+  /** The file just processed, with what became of it. */
+  path?: string
+  outcome?: FileOutcome
+  finished: boolean
+  cancelled: boolean
+}
+
+export interface DirectoryImportReport {
+  import_id: string
+  /** Files found in the folder, including those skipped. */
+  found: number
+  imported: { path: string; item_id: number }[]
+  skipped: { path: string; reason: FileSkipReason; item_id?: number }[]
+  failed: { path: string; error: string }[]
+  cancelled: boolean
+  /** Files a cancelled import never got to. */
+  not_started: number
+}
+
+/** How a watched folder's new files become items. */
+export type WatchOperation = "link" | "copy"
+
+export interface WatchedPath {
+  id: number
+  path: string
+  recursive: boolean
+  /** One glob per line; when set, only matching files are imported. */
+  include_patterns: string | null
+  exclude_patterns: string | null
+  operation: WatchOperation
+  last_scan: string | null
+  last_error: string | null
+  created_at: string
+  updated_at: string
+}
+
+/** Where a watched item's file is, under `watched` in `Item.metadata`. */
+export interface WatchedSource {
+  watched_path_id: number
+  path: string
+  /** Set while the file is gone from the folder */
+  missing_since?: string
+}
+
+/** Payload of the `watched-files-synced` event, also returned by `rescanWatchedPath`. */
+export interface WatchSyncReport {
+  watched_path_id: number
+  imported: number[]
+  updated: number[]
+  missing: number[]
+  restored: number[]
+  failed: number
+}